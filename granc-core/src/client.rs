@@ -32,6 +32,9 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! Descriptor sets may also be given gzipped (e.g. the content of a `descriptor.bin.gz` file):
+//! they are recognized by their magic bytes and decompressed before being decoded.
 pub mod offline;
 pub mod online;
 pub mod online_without_reflection;
@@ -39,11 +42,88 @@ mod types;
 
 pub use types::*;
 
-use crate::{grpc::client::GrpcClient, reflection::client::ReflectionClient};
-use prost_reflect::DescriptorPool;
+use crate::{grpc::client::GrpcClient, gzip, reflection::client::ReflectionClient};
+use prost_reflect::{DescriptorError, DescriptorPool};
 use std::fmt::Debug;
 use tonic::transport::Channel;
 
+/// Errors that can occur when loading a `FileDescriptorSet` from bytes.
+#[derive(Debug, thiserror::Error)]
+pub enum DescriptorSetError {
+    #[error("Failed to decompress gzipped file descriptor set: '{0}'")]
+    Gzip(String),
+    #[error("{}", located(.0))]
+    Decode(#[from] DescriptorError),
+}
+
+/// Errors that can occur when resolving a method from its `package.Service/Method` path.
+#[derive(Debug, thiserror::Error)]
+pub enum ResolveMethodError {
+    #[error("Invalid endpoint format: '{0}'. Expected 'package.Service/Method'")]
+    InvalidPath(String),
+    #[error("Invalid endpoint format: '{0}'. Expected exactly one '/' between service and method")]
+    TooManySeparators(String),
+    #[error("Service and Method names cannot be empty")]
+    EmptyName,
+    #[error("Service '{0}' not found")]
+    ServiceNotFound(String),
+    #[error("Method '{0}' not found")]
+    MethodNotFound(String),
+}
+
+/// Splits a method path, `package.Service/Method`, into its service and method names.
+///
+/// A leading slash (`/package.Service/Method`, the form found in gRPC logs and used on the wire)
+/// is accepted too.
+///
+/// # Returns
+///
+/// * `Ok((service, method))` - The fully qualified service name and the method name.
+/// * `Err(ResolveMethodError)` - If the path doesn't have exactly one `/` between two non-empty names.
+pub fn parse_method_path(path: &str) -> Result<(&str, &str), ResolveMethodError> {
+    let trimmed = path.strip_prefix('/').unwrap_or(path);
+
+    let (service, method) = trimmed
+        .split_once('/')
+        .ok_or_else(|| ResolveMethodError::InvalidPath(path.to_string()))?;
+
+    if method.contains('/') {
+        return Err(ResolveMethodError::TooManySeparators(path.to_string()));
+    }
+
+    if service.trim().is_empty() || method.trim().is_empty() {
+        return Err(ResolveMethodError::EmptyName);
+    }
+
+    Ok((service, method))
+}
+
+// The message of a `DescriptorError`, followed by the file (and position, when the set has source
+// info) where it occurred, so the broken file of a set can be found
+pub(crate) fn located(err: &DescriptorError) -> String {
+    match (err.file(), err.line(), err.column()) {
+        (Some(file), Some(line), Some(column)) => {
+            format!(
+                "{err} (in '{file}', line {}, column {})",
+                line + 1,
+                column + 1
+            )
+        }
+        (Some(file), _, _) => format!("{err} (in '{file}')"),
+        (None, _, _) => err.to_string(),
+    }
+}
+
+// Decodes a `FileDescriptorSet`, decompressing it first if it is gzipped
+fn decode_descriptor_set(bytes: &[u8]) -> Result<DescriptorPool, DescriptorSetError> {
+    if gzip::is_gzip(bytes) {
+        let bytes = gzip::decompress(bytes).map_err(DescriptorSetError::Gzip)?;
+        return Ok(DescriptorPool::decode(bytes.as_slice())?);
+    }
+
+    Ok(DescriptorPool::decode(bytes)?)
+}
+
 /// The main client for interacting with gRPC servers dynamically.
 ///
 /// The generic parameter `T` represents the current state of the client.
@@ -61,6 +141,7 @@ impl<T> GrancClient<T> {
 /// State: Connected to server, Schema resolved from Server Reflection.
 #[derive(Debug, Clone)]
 pub struct Online<S = Channel> {
+    service: S,
     reflection_client: ReflectionClient<S>,
     grpc_client: GrpcClient<S>,
 }
@@ -4,9 +4,12 @@
 //! `DescriptorPool` but is **not connected** to any gRPC server.
 //!
 //! In this state, the client is strictly limited to introspection tasks.
-use super::{GrancClient, Offline};
+use super::{
+    DescriptorSetError, GrancClient, Offline, ResolveMethodError, decode_descriptor_set,
+    parse_method_path,
+};
 use crate::client::{OfflineReflectionState, types::Descriptor};
-use prost_reflect::{DescriptorError, DescriptorPool};
+use prost_reflect::{DescriptorPool, FileDescriptor, MethodDescriptor};
 
 impl GrancClient<Offline> {
     /// Creates a new `GrancClient` in the Offline state using a raw byte buffer
@@ -17,14 +20,14 @@ impl GrancClient<Offline> {
     ///
     /// # Arguments
     ///
-    /// * `file_descriptor` - A vector of bytes containing the encoded `FileDescriptorSet`.
+    /// * `file_descriptor` - A vector of bytes containing the encoded `FileDescriptorSet`, optionally gzipped.
     ///
     /// # Returns
     ///
     /// * `Ok(GrancClient<Offline>)` - The initialized offline client.
-    /// * `Err(DescriptorError)` - If the bytes are not a valid descriptor set.
-    pub fn offline(file_descriptor: Vec<u8>) -> Result<Self, DescriptorError> {
-        let pool = DescriptorPool::decode(file_descriptor.as_slice())?;
+    /// * `Err(DescriptorSetError)` - If the bytes are not a valid (or validly gzipped) descriptor set.
+    pub fn offline(file_descriptor: Vec<u8>) -> Result<Self, DescriptorSetError> {
+        let pool = decode_descriptor_set(&file_descriptor)?;
         Ok(GrancClient::new(Offline::new(pool)))
     }
 }
@@ -33,6 +36,11 @@ impl<T> GrancClient<T>
 where
     T: OfflineReflectionState,
 {
+    /// The local `DescriptorPool`, e.g. to walk every definition of the schema.
+    pub fn descriptor_pool(&self) -> &DescriptorPool {
+        self.state.descriptor_pool()
+    }
+
     /// Lists all services defined in the local `DescriptorPool`.
     ///
     /// # Returns
@@ -70,4 +78,40 @@ where
         }
         None
     }
+
+    /// Looks up a method in the local `DescriptorPool` by its path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The method path, `package.Service/Method` (see [`parse_method_path`]).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(MethodDescriptor)` - The resolved method.
+    /// * `Err(ResolveMethodError)` - If the path is invalid, or the service or the method does not exist.
+    pub fn resolve_method(&self, path: &str) -> Result<MethodDescriptor, ResolveMethodError> {
+        let (service, method) = parse_method_path(path)?;
+
+        self.state
+            .descriptor_pool()
+            .get_service_by_name(service)
+            .ok_or_else(|| ResolveMethodError::ServiceNotFound(service.to_string()))?
+            .methods()
+            .find(|m| m.name() == method)
+            .ok_or_else(|| ResolveMethodError::MethodNotFound(method.to_string()))
+    }
+
+    /// Looks up a proto file by name in the local `DescriptorPool`.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - The name of the file (e.g. `my/package/service.proto`).
+    ///
+    /// # Returns
+    ///
+    /// * `Some(FileDescriptor)` - The file if found.
+    /// * `None` - If the file does not exist in the pool.
+    pub fn get_file_by_name(&self, filename: &str) -> Option<FileDescriptor> {
+        self.state.descriptor_pool().get_file_by_name(filename)
+    }
 }
@@ -3,37 +3,111 @@
 //! This module defines the `GrancClient` behavior when it is connected to a server
 //! and using Server Reflection for schema resolution.
 use super::{
-    Descriptor, DynamicRequest, DynamicResponse, GrancClient, Online, OnlineWithoutReflection,
+    Descriptor, DescriptorSetError, DynamicRequest, DynamicResponse, GrancClient, Online,
+    OnlineWithoutReflection, RequestBody, ResolveMethodError, decode_descriptor_set, located,
+    parse_method_path,
 };
 use crate::{
     BoxError,
     client::Offline,
-    grpc::client::GrpcClient,
+    grpc::{
+        client::{GrpcClient, GrpcRequestError},
+        web::{GrpcWebChannel, grpc_web_channel},
+    },
     reflection::client::{ReflectionClient, ReflectionResolveError},
 };
+use bytes::Bytes;
+use futures_util::Stream;
 use http_body::Body as HttpBody;
-use prost_reflect::{DescriptorError, DescriptorPool};
-use std::fmt::Debug;
+use prost_reflect::{DescriptorError, DescriptorPool, FileDescriptor, MethodDescriptor};
+use prost_types::FileDescriptorSet;
+use std::{collections::HashSet, fmt::Debug, time::Duration};
+use tokio_util::sync::CancellationToken;
 use tonic::{
     Code,
+    codegen::Service,
+    service::{Interceptor, interceptor::InterceptedService},
     transport::{Channel, Endpoint},
 };
 
 /// Errors that can occur when connecting to a gRPC server.
+///
+/// TLS connections are not supported: `tonic` is built without its TLS features, so there is no
+/// TLS error either. An `https://` URI is connected to in plaintext, and a server expecting TLS is
+/// only detected once the first request fails, see [`is_not_http2`].
 #[derive(Debug, thiserror::Error)]
 pub enum ClientConnectError {
     #[error("Invalid URI '{0}': {1}")]
     InvalidUri(String, #[source] tonic::transport::Error),
+    /// The host of the URI could not be resolved to an address, before connecting.
+    #[error("Failed to resolve the host of '{0}', check that it is spelled correctly: {1}")]
+    DnsResolution(String, #[source] std::io::Error),
+    #[error("Connection refused by '{0}', check that the server is listening on that port: {1}")]
+    ConnectionRefused(String, #[source] tonic::transport::Error),
+    #[error("Timed out connecting to '{0}': {1}")]
+    Timeout(String, #[source] tonic::transport::Error),
     #[error("Failed to connect to '{0}': {1}")]
     ConnectionFailed(String, #[source] tonic::transport::Error),
 }
 
+impl ClientConnectError {
+    /// Classifies a connection failure by looking at the causes of the transport error.
+    ///
+    /// Resolution failures are not among them, the host is resolved beforehand (see [`resolve`]).
+    fn connection(addr: &str, err: tonic::transport::Error) -> Self {
+        let addr = addr.to_string();
+        let mut source = std::error::Error::source(&err);
+
+        while let Some(cause) = source {
+            if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+                match io.kind() {
+                    std::io::ErrorKind::ConnectionRefused => {
+                        return Self::ConnectionRefused(addr, err);
+                    }
+                    std::io::ErrorKind::TimedOut => return Self::Timeout(addr, err),
+                    _ => {}
+                }
+            }
+
+            source = cause.source();
+        }
+
+        Self::ConnectionFailed(addr, err)
+    }
+}
+
+/// Whether `err` was caused by a peer that doesn't speak HTTP/2, like a REST (HTTP/1.1) endpoint
+/// or a server that expects TLS.
+///
+/// The connection is only checked once the first request is sent, so this shows up as the cause of
+/// the error (or [`tonic::Status`]) of that request rather than as a [`ClientConnectError`].
+pub fn is_not_http2(err: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = Some(err);
+
+    while let Some(cause) = source {
+        // Anything other than the HTTP/2 preface of the server (e.g. `HTTP/1.1 400 Bad Request`
+        // or a TLS alert) is read as a frame that is too large or is not the expected SETTINGS
+        if let Some(h2) = cause.downcast_ref::<h2::Error>() {
+            return h2.is_go_away()
+                && h2.is_library()
+                && matches!(
+                    h2.reason(),
+                    Some(h2::Reason::FRAME_SIZE_ERROR | h2::Reason::PROTOCOL_ERROR)
+                );
+        }
+
+        source = cause.source();
+    }
+
+    false
+}
+
 /// Errors that can occur during a dynamic call in Online mode.
 #[derive(Debug, thiserror::Error)]
 pub enum DynamicCallError {
     #[error("Reflection resolution failed: '{0}'")]
     ReflectionResolve(#[from] ReflectionResolveError),
-    #[error("Failed to decode file descriptor set: '{0}'")]
+    #[error("Failed to decode file descriptor set: '{}'", located(.0))]
     DescriptorError(#[from] DescriptorError),
     #[error(transparent)]
     DynamicCallError(#[from] super::online_without_reflection::DynamicCallError),
@@ -44,10 +118,12 @@ pub enum DynamicCallError {
 pub enum GetDescriptorError {
     #[error("Reflection resolution failed: '{0}'")]
     ReflectionResolve(#[from] ReflectionResolveError),
-    #[error("Failed to decode file descriptor set: '{0}'")]
+    #[error("Failed to decode file descriptor set: '{}'", located(.0))]
     DescriptorError(#[from] DescriptorError),
     #[error("Descriptor at path '{0}' not found")]
     NotFound(String),
+    #[error(transparent)]
+    ResolveMethod(#[from] ResolveMethodError),
 }
 
 impl GrancClient<Online<Channel>> {
@@ -55,6 +131,8 @@ impl GrancClient<Online<Channel>> {
     ///
     /// This is the entry point for interacting with a server. By default, the client assumes
     /// the server supports the gRPC Server Reflection Protocol.
+    /// Reflection requests carry the authority of `addr` (e.g. `localhost:50051`) as their `host`,
+    /// see [`ReflectionClient::with_host`] to send another one.
     ///
     /// # Arguments
     ///
@@ -64,16 +142,111 @@ impl GrancClient<Online<Channel>> {
     ///
     /// * `Ok(GrancClient<Online>)` - A connected client ready to make dynamic requests via reflection.
     /// * `Err(ClientConnectError)` - If the URI is invalid or the TCP connection cannot be established.
+    ///   Connection failures are reported as specifically as possible: an unknown host, a refused
+    ///   connection or a timeout.
     pub async fn connect(addr: &str) -> Result<Self, ClientConnectError> {
-        let endpoint = Endpoint::new(addr.to_string())
+        Self::connect_endpoint(addr, None).await
+    }
+
+    /// Creates a client in the `Online` state on top of an existing [`Channel`].
+    ///
+    /// A `Channel` multiplexes every request over the same HTTP/2 connection and is cheap to clone,
+    /// so one connection can be shared by many clients (or with generated `tonic` clients).
+    /// This also allows configuring the connection with [`tonic::transport::Endpoint`] (TLS,
+    /// keep-alive, ...) before handing it over. For a connection wrapped in `tower` layers, use
+    /// [`GrancClient::from`] instead, which accepts any gRPC service.
+    ///
+    /// ```rust,no_run
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use granc_core::client::{DynamicRequest, GrancClient};
+    /// use granc_core::tonic::transport::Channel;
+    ///
+    /// // Connect once...
+    /// let channel = Channel::from_static("http://localhost:50051").connect().await?;
+    ///
+    /// // ...and make calls from as many clients as needed
+    /// let mut greeter = GrancClient::from_channel(channel.clone());
+    /// let mut echo = GrancClient::from_channel(channel);
+    ///
+    /// let hello = DynamicRequest::builder("helloworld.Greeter", "SayHello")
+    ///     .body(serde_json::json!({ "name": "Ferris" }))
+    ///     .build();
+    /// let ping = DynamicRequest::builder("echo.EchoService", "UnaryEcho")
+    ///     .body(serde_json::json!({ "message": "ping" }))
+    ///     .build();
+    ///
+    /// let (hello, ping) = tokio::join!(greeter.dynamic(hello), echo.dynamic(ping));
+    /// println!("{:?} {:?}", hello?, ping?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_channel(channel: Channel) -> Self {
+        GrancClient::from(channel)
+    }
+
+    /// Same as [`GrancClient::connect`], but gives up if the connection is not established within `timeout`.
+    ///
+    /// Without it, connecting to an unreachable host can hang for the OS default TCP timeout (often minutes).
+    /// This is unrelated to the deadline of each call (see [`DynamicRequest::timeout`]).
+    ///
+    /// # Returns
+    ///
+    /// * `Err(ClientConnectError::Timeout)` - If the server can't be reached in time.
+    pub async fn connect_with_timeout(
+        addr: &str,
+        timeout: Duration,
+    ) -> Result<Self, ClientConnectError> {
+        Self::connect_endpoint(addr, Some(timeout)).await
+    }
+
+    async fn connect_endpoint(
+        addr: &str,
+        timeout: Option<Duration>,
+    ) -> Result<Self, ClientConnectError> {
+        let mut endpoint = Endpoint::new(addr.to_string())
             .map_err(|e| ClientConnectError::InvalidUri(addr.to_string(), e))?;
 
+        if let Some(timeout) = timeout {
+            endpoint = endpoint.connect_timeout(timeout);
+        }
+
+        resolve(endpoint.uri(), timeout)
+            .await
+            .map_err(|e| ClientConnectError::DnsResolution(addr.to_string(), e))?;
+
+        let host = authority(&endpoint);
         let channel = endpoint
             .connect()
             .await
-            .map_err(|e| ClientConnectError::ConnectionFailed(addr.to_string(), e))?;
+            .map_err(|e| ClientConnectError::connection(addr, e))?;
 
-        Ok(GrancClient::from(channel))
+        Ok(GrancClient::from(channel)
+            .map_reflection_client(|reflection| reflection.with_host(host)))
+    }
+}
+
+impl GrancClient<Online<GrpcWebChannel>> {
+    /// Connects to a server that speaks gRPC-Web (e.g. behind Envoy), in the `Online` state.
+    ///
+    /// The connection is established as with [`GrancClient::connect`], then every request and
+    /// response goes through `tonic_web`'s client layer.
+    /// See [`crate::grpc::web`] for the limitations of client and bidirectional streaming.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The server URI (e.g., `http://localhost:8080`).
+    /// * `connect_timeout` - Optional limit on the time it takes to establish the connection.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(GrancClient<Online<GrpcWebChannel>>)` - A connected client ready to make dynamic requests via reflection.
+    /// * `Err(ClientConnectError)` - If the URI is invalid or the connection cannot be established.
+    pub async fn grpc_web(
+        addr: &str,
+        connect_timeout: Option<Duration>,
+    ) -> Result<Self, ClientConnectError> {
+        let client = GrancClient::connect_endpoint(addr, connect_timeout).await?;
+        Ok(client.map_service(grpc_web_channel))
     }
 }
 
@@ -85,9 +258,10 @@ where
 {
     fn from(service: S) -> Self {
         let reflection_client = ReflectionClient::new(service.clone());
-        let grpc_client = GrpcClient::new(service);
+        let grpc_client = GrpcClient::new(service.clone());
         Self {
             state: Online {
+                service,
                 reflection_client,
                 grpc_client,
             },
@@ -113,17 +287,18 @@ where
     ///
     /// # Arguments
     ///
-    /// * `file_descriptor` - A vector of bytes containing the encoded `FileDescriptorSet` (protobuf binary format).
+    /// * `file_descriptor` - A vector of bytes containing the encoded `FileDescriptorSet` (protobuf binary format),
+    ///   optionally gzipped.
     ///
     /// # Returns
     ///
     /// * `Ok(GrancClient<OnlineWithoutReflection>)` - The client in the new state.
-    /// * `Err(DescriptorError)` - If the provided bytes cannot be decoded into a valid descriptor pool.
+    /// * `Err(DescriptorSetError)` - If the provided bytes cannot be decompressed or decoded into a valid descriptor pool.
     pub fn with_file_descriptor(
         self,
         file_descriptor: Vec<u8>,
-    ) -> Result<GrancClient<OnlineWithoutReflection<S>>, DescriptorError> {
-        let pool = DescriptorPool::decode(file_descriptor.as_slice())?;
+    ) -> Result<GrancClient<OnlineWithoutReflection<S>>, DescriptorSetError> {
+        let pool = decode_descriptor_set(&file_descriptor)?;
 
         Ok(GrancClient::new(OnlineWithoutReflection::new(
             self.state.grpc_client,
@@ -131,6 +306,98 @@ where
         )))
     }
 
+    /// Wraps the underlying connection with a [`tonic::service::Interceptor`].
+    ///
+    /// The interceptor runs before **every** request sent by the client, including Server Reflection
+    /// requests, which makes it the right place to compute dynamic headers (e.g. refreshing an auth token
+    /// or adding a trace id). Returning an error from the interceptor cancels the request with that status.
+    ///
+    /// Any closure with the signature `FnMut(tonic::Request<()>) -> Result<tonic::Request<()>, tonic::Status>`
+    /// is an interceptor.
+    ///
+    /// To combine an interceptor with a local descriptor, call this method before [`Self::with_file_descriptor`].
+    ///
+    /// # Arguments
+    ///
+    /// * `interceptor` - The interceptor applied to every outgoing request.
+    ///
+    /// # Returns
+    ///
+    /// A client in the `Online` state whose connection is wrapped in an [`InterceptedService`].
+    pub fn with_interceptor<F, B>(
+        self,
+        interceptor: F,
+    ) -> GrancClient<Online<InterceptedService<S, F>>>
+    where
+        F: Interceptor + Clone,
+        S: Service<http::Request<tonic::body::Body>, Response = http::Response<B>>,
+        <S as Service<http::Request<tonic::body::Body>>>::Error: Into<BoxError>,
+        B: HttpBody<Data = tonic::codegen::Bytes> + Send + 'static,
+        B::Error: Into<BoxError> + Send,
+    {
+        self.map_service(|service| InterceptedService::new(service, interceptor))
+    }
+
+    /// Replaces the underlying connection with the result of `f`.
+    ///
+    /// This is the general form of [`Self::with_interceptor`]: it can wrap the connection in
+    /// `tower` layers, or put connections of different types behind a single type.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Receives the current connection and returns the new one.
+    ///
+    /// # Returns
+    ///
+    /// A client in the `Online` state using the connection returned by `f`. The reflection
+    /// client keeps its configuration (e.g. the host set by [`GrancClient::connect`]).
+    pub fn map_service<T, F>(self, f: F) -> GrancClient<Online<T>>
+    where
+        F: FnOnce(S) -> T,
+        T: tonic::client::GrpcService<tonic::body::Body> + Clone,
+        T::ResponseBody: HttpBody<Data = tonic::codegen::Bytes> + Send + 'static,
+        <T::ResponseBody as HttpBody>::Error: Into<BoxError> + Send,
+    {
+        let Online {
+            service,
+            reflection_client,
+            grpc_client,
+        } = self.state;
+        let service = f(service);
+
+        GrancClient {
+            state: Online {
+                reflection_client: reflection_client.with_service(service.clone()),
+                grpc_client: grpc_client.with_service(service.clone()),
+                service,
+            },
+        }
+    }
+
+    /// Configures the client used to resolve schemas through reflection.
+    ///
+    /// ```rust,no_run
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use granc_core::{client::GrancClient, reflection::client::ReflectionVersion};
+    ///
+    /// let client = GrancClient::connect("http://localhost:50051")
+    ///     .await?
+    ///     .map_reflection_client(|reflection| {
+    ///         reflection
+    ///             .with_version(ReflectionVersion::V1Alpha)
+    ///             .with_host("api.example.com")
+    ///     });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn map_reflection_client<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(ReflectionClient<S>) -> ReflectionClient<S>,
+    {
+        self.state.reflection_client = f(self.state.reflection_client);
+        self
+    }
+
     /// Lists all services exposed by the server using the Reflection Protocol.
     ///
     /// # Returns
@@ -159,19 +426,7 @@ where
         &mut self,
         symbol: &str,
     ) -> Result<Descriptor, GetDescriptorError> {
-        let fd_set = self
-            .state
-            .reflection_client
-            .file_descriptor_set_by_symbol(symbol)
-            .await
-            .map_err(|err| match err {
-                ReflectionResolveError::ServerStreamFailure(status)
-                    if status.code() == Code::NotFound =>
-                {
-                    GetDescriptorError::NotFound(symbol.to_string())
-                }
-                err => GetDescriptorError::ReflectionResolve(err),
-            })?;
+        let fd_set = self.fetch_file_descriptor_set(symbol).await?;
 
         let pool = DescriptorPool::from_file_descriptor_set(fd_set)?;
         let client = GrancClient::new(Offline::new(pool));
@@ -181,6 +436,94 @@ where
             .ok_or_else(|| GetDescriptorError::NotFound(symbol.to_string()))
     }
 
+    /// Resolves a method by its path, `package.Service/Method`, using Reflection.
+    ///
+    /// Only the schema of the service is fetched, see [`Self::get_descriptor_by_symbol`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The method path (see [`parse_method_path`](super::parse_method_path)).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(MethodDescriptor)` - The resolved method.
+    /// * `Err(GetDescriptorError)` - If the path is invalid, the service or the method is not found,
+    ///   or the reflection request fails.
+    pub async fn resolve_method(
+        &mut self,
+        path: &str,
+    ) -> Result<MethodDescriptor, GetDescriptorError> {
+        let (service, _) = parse_method_path(path)?;
+
+        let fd_set = self.fetch_file_descriptor_set(service).await?;
+        let pool = DescriptorPool::from_file_descriptor_set(fd_set)?;
+
+        Ok(GrancClient::new(Offline::new(pool)).resolve_method(path)?)
+    }
+
+    /// Resolves and fetches a proto file by name using Reflection.
+    ///
+    /// This will query the server for the file and recursively fetch all imported dependencies.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - The name of the file, as known by the server (e.g. `my/package/service.proto`).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(FileDescriptor)` - The resolved file, with all its services, messages and enums.
+    /// * `Err(GetDescriptorError)` - If the file is not found on the server or the reflection request fails.
+    pub async fn get_file_by_name(
+        &mut self,
+        filename: &str,
+    ) -> Result<FileDescriptor, GetDescriptorError> {
+        let fd_set = self
+            .state
+            .reflection_client
+            .file_descriptor_set_by_filename(filename)
+            .await
+            .map_err(not_found_as(filename))?;
+
+        let pool = DescriptorPool::from_file_descriptor_set(fd_set)?;
+
+        pool.get_file_by_name(filename)
+            .ok_or_else(|| GetDescriptorError::NotFound(filename.to_string()))
+    }
+
+    /// Resolves the complete `FileDescriptorSet` defining the given symbols using Reflection.
+    ///
+    /// Each symbol is resolved together with all its transitive dependencies, and the results are
+    /// merged into a single self-contained set (each file appears only once), so it can be saved
+    /// and used later with [`GrancClient::offline`] or [`Self::with_file_descriptor`].
+    ///
+    /// # Arguments
+    ///
+    /// * `symbols` - The fully qualified names of the symbols to resolve (e.g. every listed service).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(FileDescriptorSet)` - The merged set of files.
+    /// * `Err(GetDescriptorError)` - If a symbol is not found on the server or the reflection request fails.
+    pub async fn file_descriptor_set(
+        &mut self,
+        symbols: &[String],
+    ) -> Result<FileDescriptorSet, GetDescriptorError> {
+        let mut seen = HashSet::new();
+        let mut merged = FileDescriptorSet { file: vec![] };
+
+        for symbol in symbols {
+            let fd_set = self.fetch_file_descriptor_set(symbol).await?;
+
+            for file in fd_set.file {
+                if seen.insert(file.name().to_string()) {
+                    merged.file.push(file);
+                }
+            }
+        }
+
+        Ok(merged)
+    }
+
     /// Executes a dynamic gRPC request using Server Reflection for schema resolution.
     ///
     /// # Arguments
@@ -188,24 +531,34 @@ where
     /// * `request` - A [`DynamicRequest`] struct containing:
     ///   - `service`: The fully qualified name of the service (e.g., `my.package.MyService`).
     ///   - `method`: The name of the method to call (e.g., `MyMethod`).
-    ///   - `body`: The JSON payload (Object for Unary/ServerStreaming, Array for Client/BiDi Streaming).
+    ///   - `body`: The JSON payload (Object for Unary/ServerStreaming, Array for Client/BiDi Streaming),
+    ///     or messages built beforehand (see [`RequestBody`]).
     ///   - `headers`: Optional gRPC metadata/headers.
+    ///   - `timeout`: Optional deadline for the call.
+    ///   - `cancellation`: Optional token to abort a streaming call.
     ///
     /// # Returns
     ///
     /// * `Ok(DynamicResponse)` - The result of the call, which can be:
     ///   - [`DynamicResponse::Unary`]: For Unary and Client Streaming calls (single response).
     ///   - [`DynamicResponse::Streaming`]: For Server Streaming and Bidirectional calls (stream of responses).
+    ///
+    ///   Both carry the [`MethodKind`](super::MethodKind) of the method, see [`DynamicResponse::kind`].
     /// * `Err(DynamicCallError)` - If an error occurs during:
     ///   - Reflection resolution (e.g., Service not found).
     ///   - Schema parsing.
     ///   - Request serialization (JSON to Proto).
     ///   - Network transport.
     ///   - Response deserialization.
-    pub async fn dynamic(
+    pub async fn dynamic<B: RequestBody>(
         &mut self,
-        request: DynamicRequest,
+        request: DynamicRequest<B>,
     ) -> Result<DynamicResponse, DynamicCallError> {
+        tracing::debug!(
+            "Resolving the schema of '{}' via reflection",
+            request.service
+        );
+
         let fd_set = self
             .state
             .reflection_client
@@ -221,4 +574,133 @@ where
 
         Ok(client.dynamic(request).await?)
     }
+
+    /// Starts a Bidirectional Streaming call using Server Reflection for schema resolution,
+    /// sending the messages of `messages` as they come and returning the responses as they arrive.
+    ///
+    /// See [`GrancClient::<OnlineWithoutReflection<S>>::bidirectional_stream`](super::GrancClient::bidirectional_stream).
+    pub async fn bidirectional_stream<P>(
+        &mut self,
+        service: &str,
+        method: &str,
+        messages: P,
+        headers: Vec<(String, String)>,
+        timeout: Option<Duration>,
+        cancellation: Option<CancellationToken>,
+    ) -> Result<
+        Result<
+            impl Stream<Item = Result<serde_json::Value, tonic::Status>> + use<S, P>,
+            tonic::Status,
+        >,
+        DynamicCallError,
+    >
+    where
+        P: Stream<Item = serde_json::Value> + Send + 'static,
+    {
+        tracing::debug!("Resolving the schema of '{service}' via reflection");
+
+        let fd_set = self
+            .state
+            .reflection_client
+            .file_descriptor_set_by_symbol(service)
+            .await?;
+
+        let pool = DescriptorPool::from_file_descriptor_set(fd_set)?;
+
+        let mut client = GrancClient::new(OnlineWithoutReflection::new(
+            self.state.grpc_client.clone(),
+            pool,
+        ));
+
+        Ok(client
+            .bidirectional_stream(service, method, messages, headers, timeout, cancellation)
+            .await?)
+    }
+
+    /// Sends a pre-encoded Protobuf message to a unary method, returning the encoded response.
+    ///
+    /// The schema of the method is not resolved, so no reflection request is made.
+    /// See [`GrpcClient::unary_raw`].
+    pub async fn raw(
+        &mut self,
+        service: &str,
+        method: &str,
+        body: Bytes,
+        headers: Vec<(String, String)>,
+        timeout: Option<Duration>,
+    ) -> Result<Result<Bytes, tonic::Status>, GrpcRequestError> {
+        self.state
+            .grpc_client
+            .unary_raw(service, method, body, headers, timeout)
+            .await
+    }
+
+    async fn fetch_file_descriptor_set(
+        &mut self,
+        symbol: &str,
+    ) -> Result<FileDescriptorSet, GetDescriptorError> {
+        self.state
+            .reflection_client
+            .file_descriptor_set_by_symbol(symbol)
+            .await
+            .map_err(not_found_as(symbol))
+    }
+}
+
+// The authority (`host[:port]`) of the URI of `endpoint`, sent as the `host` of reflection requests
+// since some servers reject requests whose host doesn't match the connection
+fn authority(endpoint: &Endpoint) -> String {
+    endpoint
+        .uri()
+        .authority()
+        .map(|authority| authority.to_string())
+        .unwrap_or_default()
+}
+
+/// Resolves the host of `uri` to check that it exists, as the connector of `tonic` doesn't expose
+/// its resolution errors.
+///
+/// The connection still resolves the host itself. If resolving takes longer than `timeout`, the
+/// connection reports the timeout instead.
+async fn resolve(uri: &http::Uri, timeout: Option<Duration>) -> std::io::Result<()> {
+    let Some(host) = uri.host() else {
+        return Ok(());
+    };
+
+    // IPv6 addresses are bracketed in URIs
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let port = uri
+        .port_u16()
+        .unwrap_or(if uri.scheme_str() == Some("https") {
+            443
+        } else {
+            80
+        });
+    let lookup = tokio::net::lookup_host((host, port));
+
+    let addresses = match timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, lookup).await {
+            Ok(addresses) => addresses?,
+            Err(_) => return Ok(()),
+        },
+        None => lookup.await?,
+    };
+
+    match addresses.count() {
+        0 => Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("no address found for '{host}'"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+// Reports a `NotFound` status from the reflection server as a missing descriptor
+fn not_found_as(name: &str) -> impl FnOnce(ReflectionResolveError) -> GetDescriptorError + '_ {
+    move |err| match err {
+        ReflectionResolveError::ServerStreamFailure(status) if status.code() == Code::NotFound => {
+            GetDescriptorError::NotFound(name.to_string())
+        }
+        err => GetDescriptorError::ReflectionResolve(err),
+    }
 }
@@ -2,11 +2,23 @@
 //!
 //! This module defines the `GrancClient` behavior when it is connected to a server
 //! but uses a local, in-memory `DescriptorPool` (Static schema) to resolve messages.
-use super::{DynamicRequest, DynamicResponse, GrancClient, OnlineWithoutReflection};
-use crate::{BoxError, client::OfflineReflectionState, grpc::client::GrpcRequestError};
+use super::{
+    DynamicRequest, DynamicResponse, GrancClient, MethodKind, OnlineWithoutReflection, RequestBody,
+};
+use crate::{
+    BoxError,
+    client::OfflineReflectionState,
+    grpc::{
+        client::{GrpcRequestError, http_path},
+        codec::RequestMessage,
+    },
+};
+use bytes::Bytes;
 use futures_util::{Stream, StreamExt};
 use http_body::Body as HttpBody;
-use std::fmt::Debug;
+use prost_reflect::{MessageDescriptor, MethodDescriptor};
+use std::{fmt::Debug, time::Duration};
+use tokio_util::sync::CancellationToken;
 
 /// Errors that can occur during a dynamic call in OnlineWithoutReflection mode.
 #[derive(Debug, thiserror::Error)]
@@ -17,6 +29,8 @@ pub enum DynamicCallError {
     ServiceNotFound(String),
     #[error("Method '{0}' not found")]
     MethodNotFound(String),
+    #[error("Message '{0}' not found")]
+    MessageNotFound(String),
     #[error("gRPC client request error: '{0}'")]
     GrpcRequestError(#[from] GrpcRequestError),
 }
@@ -37,8 +51,13 @@ where
     /// * `request` - A [`DynamicRequest`] struct containing:
     ///   - `service`: The fully qualified name of the service (e.g., `my.package.MyService`).
     ///   - `method`: The name of the method to call (e.g., `MyMethod`).
-    ///   - `body`: The JSON payload.
+    ///   - `body`: The JSON payload, or messages built beforehand (see [`RequestBody`]).
     ///   - `headers`: Optional gRPC metadata.
+    ///   - `timeout`: Optional deadline for the call.
+    ///   - `cancellation`: Optional token to abort the call. Streams end with the messages received
+    ///     so far, other calls fail with a `Cancelled` status.
+    ///   - `strict`: Whether to list the valid fields of a message when the body has an unknown one.
+    ///   - `send_interval`: Optional delay between the messages of a client stream.
     ///
     /// # Returns
     ///
@@ -46,71 +65,306 @@ where
     /// * `Err(DynamicCallError)` - If validation fails or the network call errors. Specific errors include:
     ///   - [`DynamicCallError::ServiceNotFound`]: The service is not present in the local descriptor.
     ///   - [`DynamicCallError::MethodNotFound`]: The method does not exist in the service.
-    ///   - [`DynamicCallError::InvalidInput`]: The JSON body structure is invalid for the streaming mode (e.g. object provided for streaming call).
+    ///   - [`DynamicCallError::InvalidInput`]: The body structure is invalid for the streaming mode (a single message is expected,
+    ///     or an array of them for client streaming), or, in strict mode, it contains an unknown field. The error lists the valid fields.
+    ///     Without strict mode, unknown fields fail the call with an `InvalidArgument` status instead.
     ///   - [`DynamicCallError::GrpcRequestError`]: Transport-level errors (connection failed, timeout, etc).
-    pub async fn dynamic(
+    pub async fn dynamic<B: RequestBody>(
         &mut self,
-        request: DynamicRequest,
+        request: DynamicRequest<B>,
     ) -> Result<DynamicResponse, DynamicCallError> {
-        let method = self
-            .state
-            .descriptor_pool()
-            .get_service_by_name(&request.service)
-            .ok_or_else(|| DynamicCallError::ServiceNotFound(request.service.clone()))?
-            .methods()
-            .find(|m| m.name() == request.method)
-            .ok_or_else(|| DynamicCallError::MethodNotFound(request.method.clone()))?;
-
-        match (method.is_client_streaming(), method.is_server_streaming()) {
-            (false, false) => {
-                let result = self
-                    .state
-                    .grpc_client
-                    .unary(method, request.body, request.headers)
-                    .await?;
-                Ok(DynamicResponse::Unary(result))
+        let method = self.find_method(&request.service, &request.method)?;
+
+        tracing::debug!(
+            client_streaming = method.is_client_streaming(),
+            server_streaming = method.is_server_streaming(),
+            timeout = ?request.timeout,
+            "Sending request to {}",
+            http_path(&method)
+        );
+
+        let mut messages = request
+            .body
+            .into_messages(method.is_client_streaming())
+            .map_err(|expected| {
+                DynamicCallError::InvalidInput(format!(
+                    "'{}' expects {expected}",
+                    method.full_name()
+                ))
+            })?;
+
+        if request.strict {
+            check_known_fields(&method.input(), &messages)?;
+        }
+
+        let grpc_client = &mut self.state.grpc_client;
+
+        let kind = MethodKind::from(&method);
+
+        match kind {
+            MethodKind::Unary => {
+                // Not client streaming, so the body is a single message
+                let call =
+                    grpc_client.unary(method, messages.remove(0), request.headers, request.timeout);
+                let result = until_cancelled(call, request.cancellation).await?;
+                Ok(DynamicResponse::Unary(kind, result))
             }
-            (false, true) => match self
-                .state
-                .grpc_client
-                .server_streaming(method, request.body, request.headers)
+            MethodKind::ServerStreaming => match grpc_client
+                .server_streaming(
+                    method,
+                    messages.remove(0),
+                    request.headers,
+                    request.timeout,
+                    request.cancellation,
+                )
                 .await?
             {
-                Ok(stream) => Ok(DynamicResponse::Streaming(Ok(stream.collect().await))),
-                Err(status) => Ok(DynamicResponse::Streaming(Err(status))),
+                Ok(stream) => {
+                    let messages = collect_stream(stream, request.max_messages).await;
+                    Ok(DynamicResponse::Streaming(kind, Ok(messages)))
+                }
+                Err(status) => Ok(DynamicResponse::Streaming(kind, Err(status))),
             },
-            (true, false) => {
-                let input_stream =
-                    json_array_to_stream(request.body).map_err(DynamicCallError::InvalidInput)?;
-                let result = self
-                    .state
-                    .grpc_client
-                    .client_streaming(method, input_stream, request.headers)
-                    .await?;
-                Ok(DynamicResponse::Unary(result))
+            MethodKind::ClientStreaming => {
+                let input_stream = messages_to_stream(messages, request.send_interval);
+                let call = grpc_client.client_streaming(
+                    method,
+                    input_stream,
+                    request.headers,
+                    request.timeout,
+                );
+                let result = until_cancelled(call, request.cancellation).await?;
+                Ok(DynamicResponse::Unary(kind, result))
             }
-            (true, true) => {
-                let input_stream =
-                    json_array_to_stream(request.body).map_err(DynamicCallError::InvalidInput)?;
-                match self
-                    .state
-                    .grpc_client
-                    .bidirectional_streaming(method, input_stream, request.headers)
+            MethodKind::Bidirectional => {
+                let input_stream = messages_to_stream(messages, request.send_interval);
+                match grpc_client
+                    .bidirectional_streaming(
+                        method,
+                        input_stream,
+                        request.headers,
+                        request.timeout,
+                        request.cancellation,
+                    )
                     .await?
                 {
-                    Ok(stream) => Ok(DynamicResponse::Streaming(Ok(stream.collect().await))),
-                    Err(status) => Ok(DynamicResponse::Streaming(Err(status))),
+                    Ok(stream) => {
+                        let messages = collect_stream(stream, request.max_messages).await;
+                        Ok(DynamicResponse::Streaming(kind, Ok(messages)))
+                    }
+                    Err(status) => Ok(DynamicResponse::Streaming(kind, Err(status))),
                 }
             }
         }
     }
+
+    /// Executes a Unary call whose messages are of the types `request_type` and `response_type`,
+    /// given by their fully qualified names (e.g. `my.package.MyRequest`).
+    ///
+    /// Unlike [`Self::dynamic`], the service and method of `request` are not looked up in the
+    /// local descriptor set: the request is sent to `/{service}/{method}` as they are. This is an
+    /// escape hatch for descriptor sets that define the messages of a method but lack its service.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(DynamicResponse::Unary)` - The result of the call.
+    /// * `Err(DynamicCallError)` - If validation fails or the network call errors. Specific errors include:
+    ///   - [`DynamicCallError::MessageNotFound`]: A message type is not present in the local descriptor.
+    ///   - [`DynamicCallError::InvalidInput`]: The body is not a single message or, in strict mode,
+    ///     it contains an unknown field. The error lists the valid fields.
+    pub async fn dynamic_with_types<B: RequestBody>(
+        &mut self,
+        request: DynamicRequest<B>,
+        request_type: &str,
+        response_type: &str,
+    ) -> Result<DynamicResponse, DynamicCallError> {
+        let input = self.find_message(request_type)?;
+        let output = self.find_message(response_type)?;
+        let path = format!("/{}/{}", request.service, request.method);
+
+        let mut messages = request.body.into_messages(false).map_err(|expected| {
+            DynamicCallError::InvalidInput(format!("'{path}' expects {expected}"))
+        })?;
+
+        tracing::debug!(
+            timeout = ?request.timeout,
+            "Sending request to {path} ({} -> {})",
+            input.full_name(),
+            output.full_name()
+        );
+
+        if request.strict {
+            check_known_fields(&input, &messages)?;
+        }
+
+        let result = self
+            .state
+            .grpc_client
+            .unary_with_types(
+                &path,
+                input,
+                output,
+                messages.remove(0),
+                request.headers,
+                request.timeout,
+            )
+            .await?;
+
+        Ok(DynamicResponse::Unary(MethodKind::Unary, result))
+    }
+
+    /// Starts a Bidirectional Streaming call that sends the messages of `messages` as they come,
+    /// returning the responses as they arrive.
+    ///
+    /// Unlike [`Self::dynamic`], nothing is collected, which allows a genuine back-and-forth
+    /// (e.g. sending messages typed by a user and showing each response as soon as it arrives).
+    /// The call ends once `messages` ends and the server closes its side, or on `cancellation`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Ok(Stream))` - The call started.
+    /// * `Ok(Err(Status))` - The server rejected the call before sending any message.
+    /// * `Err(DynamicCallError)` - The method was not found, is not a bidirectional streaming
+    ///   method ([`DynamicCallError::InvalidInput`]), or the request could not be sent.
+    pub async fn bidirectional_stream<P>(
+        &mut self,
+        service: &str,
+        method: &str,
+        messages: P,
+        headers: Vec<(String, String)>,
+        timeout: Option<Duration>,
+        cancellation: Option<CancellationToken>,
+    ) -> Result<
+        Result<
+            impl Stream<Item = Result<serde_json::Value, tonic::Status>> + use<S, P>,
+            tonic::Status,
+        >,
+        DynamicCallError,
+    >
+    where
+        P: Stream<Item = serde_json::Value> + Send + 'static,
+    {
+        let method = self.find_method(service, method)?;
+
+        if MethodKind::from(&method) != MethodKind::Bidirectional {
+            return Err(DynamicCallError::InvalidInput(format!(
+                "'{}' is not a bidirectional streaming method",
+                method.full_name()
+            )));
+        }
+
+        tracing::debug!("Streaming to {}", http_path(&method));
+
+        Ok(self
+            .state
+            .grpc_client
+            .clone()
+            .bidirectional_streaming(method, messages, headers, timeout, cancellation)
+            .await?)
+    }
+
+    /// Sends a pre-encoded Protobuf message to a unary method, returning the encoded response.
+    ///
+    /// The method is not looked up in the local descriptor set, the bytes are sent as they are.
+    /// See [`GrpcClient::unary_raw`](crate::grpc::client::GrpcClient::unary_raw).
+    pub async fn raw(
+        &mut self,
+        service: &str,
+        method: &str,
+        body: Bytes,
+        headers: Vec<(String, String)>,
+        timeout: Option<Duration>,
+    ) -> Result<Result<Bytes, tonic::Status>, GrpcRequestError> {
+        self.state
+            .grpc_client
+            .unary_raw(service, method, body, headers, timeout)
+            .await
+    }
 }
 
-fn json_array_to_stream(
-    json: serde_json::Value,
-) -> Result<impl Stream<Item = serde_json::Value> + Send + 'static, String> {
-    match json {
-        serde_json::Value::Array(items) => Ok(tokio_stream::iter(items)),
-        _ => Err("Client streaming requires a JSON Array body".to_string()),
+impl<S> GrancClient<OnlineWithoutReflection<S>> {
+    fn find_message(&self, name: &str) -> Result<MessageDescriptor, DynamicCallError> {
+        self.state
+            .descriptor_pool()
+            .get_message_by_name(name)
+            .ok_or_else(|| DynamicCallError::MessageNotFound(name.to_string()))
+    }
+
+    fn find_method(
+        &self,
+        service: &str,
+        method: &str,
+    ) -> Result<MethodDescriptor, DynamicCallError> {
+        let method = self
+            .state
+            .descriptor_pool()
+            .get_service_by_name(service)
+            .ok_or_else(|| DynamicCallError::ServiceNotFound(service.to_string()))?
+            .methods()
+            .find(|m| m.name() == method)
+            .ok_or_else(|| DynamicCallError::MethodNotFound(method.to_string()))?;
+
+        // The path is built from the descriptor, so log it as sent to make `Unimplemented` errors
+        // easier to diagnose
+        let parent = method.parent_service();
+        if parent.full_name() != service {
+            tracing::warn!(
+                "Service '{service}' resolved to '{}', whose name is used on the wire",
+                parent.full_name()
+            );
+        }
+
+        Ok(method)
+    }
+}
+
+// Fails a call that returns a single response with `Cancelled` once `cancellation` is cancelled.
+// The call is dropped, which cancels the underlying request.
+async fn until_cancelled<T>(
+    call: impl Future<Output = Result<Result<T, tonic::Status>, GrpcRequestError>>,
+    cancellation: Option<CancellationToken>,
+) -> Result<Result<T, tonic::Status>, GrpcRequestError> {
+    match cancellation {
+        Some(token) => token
+            .run_until_cancelled(call)
+            .await
+            .unwrap_or_else(|| Ok(Err(tonic::Status::cancelled("The call was cancelled")))),
+        None => call.await,
+    }
+}
+
+// Collects the messages of a response stream, ending it after `max_messages` if set.
+// The stream is dropped once collected, which cancels the call if it was still going on.
+async fn collect_stream<T>(stream: impl Stream<Item = T>, max_messages: Option<usize>) -> Vec<T> {
+    stream
+        .take(max_messages.unwrap_or(usize::MAX))
+        .collect()
+        .await
+}
+
+// Fails on the first unknown field of any of the messages
+fn check_known_fields<M: RequestMessage>(
+    input: &MessageDescriptor,
+    messages: &[M],
+) -> Result<(), DynamicCallError> {
+    match messages
+        .iter()
+        .find_map(|message| message.find_unknown_field(input))
+    {
+        Some(err) => Err(DynamicCallError::InvalidInput(err.to_string())),
+        None => Ok(()),
+    }
+}
+
+// Messages are spaced out by `send_interval`, if any
+fn messages_to_stream<M: Send + 'static>(
+    messages: Vec<M>,
+    send_interval: Option<Duration>,
+) -> impl Stream<Item = M> + Send + 'static {
+    let stream = tokio_stream::iter(messages);
+
+    match send_interval {
+        Some(interval) => tokio_stream::StreamExt::throttle(stream, interval).boxed(),
+        None => stream.boxed(),
     }
 }
@@ -1,28 +1,268 @@
-use prost_reflect::{EnumDescriptor, MessageDescriptor, ServiceDescriptor};
-use std::fmt::Debug;
+use crate::grpc::codec::RequestMessage;
+use prost_reflect::{
+    DynamicMessage, EnumDescriptor, MessageDescriptor, MethodDescriptor, ServiceDescriptor,
+};
+use std::{fmt::Debug, time::Duration};
+use tokio_util::sync::CancellationToken;
 
 /// A request object encapsulating all necessary information to perform a dynamic gRPC call.
+///
+/// The body is JSON by default, or any other [`RequestBody`] (see [`DynamicRequest::with_body`]).
 #[derive(Debug, Clone)]
-pub struct DynamicRequest {
-    /// The JSON body of the request.
+pub struct DynamicRequest<B = serde_json::Value> {
+    /// The body of the request, JSON by default.
     /// - For Unary/ServerStreaming: An Object `{}`.
     /// - For ClientStreaming/Bidirectional: An Array of Objects `[{}]`.
-    pub body: serde_json::Value,
+    pub body: B,
     /// Custom gRPC metadata (headers) to attach to the request.
     pub headers: Vec<(String, String)>,
     /// The fully qualified name of the service (e.g., `my.package.Service`).
     pub service: String,
     /// The name of the method to call (e.g., `SayHello`).
     pub method: String,
+    /// Optional deadline for the whole call.
+    ///
+    /// It is sent to the server as the `grpc-timeout` header and enforced by the client,
+    /// for streaming calls too.
+    pub timeout: Option<Duration>,
+    /// Optional token to abort the call while it is in flight.
+    ///
+    /// Once cancelled, the response stream of a streaming call ends cleanly and the messages
+    /// received so far are returned. Unary and client streaming calls fail with `Cancelled`.
+    pub cancellation: Option<CancellationToken>,
+    /// Check the body for fields that are not part of the schema before sending it, and report
+    /// them with the valid fields of their message.
+    ///
+    /// Unknown fields are rejected either way, without it the call fails with an `InvalidArgument`
+    /// status that doesn't list the valid fields.
+    pub strict: bool,
+    /// Optional delay between the messages sent by a client or bidirectional streaming call.
+    ///
+    /// By default, all the messages are sent as fast as the server accepts them.
+    pub send_interval: Option<Duration>,
+    /// Optional number of messages after which a server or bidirectional stream is ended.
+    ///
+    /// Once reached, the request is dropped, which cancels the call on the server, and the
+    /// messages received so far are returned. Useful for streams that never end.
+    pub max_messages: Option<usize>,
+}
+
+impl DynamicRequest {
+    /// Starts building a request to `method` of `service`, with an empty object as body.
+    ///
+    /// ```
+    /// use granc_core::client::DynamicRequest;
+    /// use std::time::Duration;
+    ///
+    /// let request = DynamicRequest::builder("helloworld.Greeter", "SayHello")
+    ///     .body(serde_json::json!({ "name": "Ferris" }))
+    ///     .header("authorization", "Bearer token")
+    ///     .timeout(Duration::from_secs(5))
+    ///     .build();
+    ///
+    /// assert_eq!(request.headers.len(), 1);
+    /// ```
+    pub fn builder(service: impl Into<String>, method: impl Into<String>) -> DynamicRequestBuilder {
+        DynamicRequestBuilder(DynamicRequest {
+            body: serde_json::Value::Object(Default::default()),
+            headers: vec![],
+            service: service.into(),
+            method: method.into(),
+            timeout: None,
+            cancellation: None,
+            strict: false,
+            send_interval: None,
+            max_messages: None,
+        })
+    }
+}
+
+impl<B> DynamicRequest<B> {
+    /// Replaces the body of the request, e.g. with messages parsed from protobuf text format.
+    pub fn with_body<C>(self, body: C) -> DynamicRequest<C> {
+        DynamicRequest {
+            body,
+            headers: self.headers,
+            service: self.service,
+            method: self.method,
+            timeout: self.timeout,
+            cancellation: self.cancellation,
+            strict: self.strict,
+            send_interval: self.send_interval,
+            max_messages: self.max_messages,
+        }
+    }
+}
+
+/// The body of a [`DynamicRequest`]: a JSON value, or messages built beforehand.
+pub trait RequestBody: Send + 'static {
+    /// The type of the messages the body is made of.
+    type Message: RequestMessage;
+
+    /// Splits the body into its messages: a single one, or any number of them when
+    /// `client_streaming`.
+    ///
+    /// Fails with what was expected instead, e.g. `a JSON object, as it is not client streaming,
+    /// got an array`.
+    fn into_messages(self, client_streaming: bool) -> Result<Vec<Self::Message>, String>;
+}
+
+/// A JSON object, or an array of them for client streaming.
+impl RequestBody for serde_json::Value {
+    type Message = serde_json::Value;
+
+    fn into_messages(self, client_streaming: bool) -> Result<Vec<Self::Message>, String> {
+        match (client_streaming, self) {
+            (true, serde_json::Value::Array(items)) => Ok(items),
+            (false, body @ serde_json::Value::Object(_)) => Ok(vec![body]),
+            (true, body) => Err(format!(
+                "a JSON array of messages, as it is client streaming, got {}",
+                json_type(&body)
+            )),
+            (false, body) => Err(format!(
+                "a JSON object, as it is not client streaming, got {}",
+                json_type(&body)
+            )),
+        }
+    }
+}
+
+/// Exactly one message, or any number of them for client streaming.
+impl RequestBody for Vec<DynamicMessage> {
+    type Message = DynamicMessage;
+
+    fn into_messages(self, client_streaming: bool) -> Result<Vec<Self::Message>, String> {
+        if client_streaming || self.len() == 1 {
+            Ok(self)
+        } else {
+            Err(format!(
+                "a single message, as it is not client streaming, got {}",
+                self.len()
+            ))
+        }
+    }
+}
+
+fn json_type(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "a boolean",
+        serde_json::Value::Number(_) => "a number",
+        serde_json::Value::String(_) => "a string",
+        serde_json::Value::Array(_) => "an array",
+        serde_json::Value::Object(_) => "an object",
+    }
+}
+
+/// A fluent builder for [`DynamicRequest`], created with [`DynamicRequest::builder`].
+#[derive(Debug, Clone)]
+pub struct DynamicRequestBuilder(DynamicRequest);
+
+impl DynamicRequestBuilder {
+    /// Sets the JSON body of the request.
+    pub fn body(mut self, body: serde_json::Value) -> Self {
+        self.0.body = body;
+        self
+    }
+
+    /// Adds a header. Can be called multiple times.
+    pub fn header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.0.headers.push((key.into(), value.into()));
+        self
+    }
+
+    /// Adds every header of `headers`.
+    pub fn headers_from_iter<K, V>(mut self, headers: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.0
+            .headers
+            .extend(headers.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Sets the deadline of the call.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.0.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the token that aborts the call.
+    pub fn cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.0.cancellation = Some(cancellation);
+        self
+    }
+
+    /// Lists the valid fields of a message when the body has an unknown one.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.0.strict = strict;
+        self
+    }
+
+    /// Sets the delay between the messages of a client stream.
+    pub fn send_interval(mut self, send_interval: Duration) -> Self {
+        self.0.send_interval = Some(send_interval);
+        self
+    }
+
+    /// Sets the number of messages after which a response stream is ended.
+    pub fn max_messages(mut self, max_messages: usize) -> Self {
+        self.0.max_messages = Some(max_messages);
+        self
+    }
+
+    /// Returns the request.
+    pub fn build(self) -> DynamicRequest {
+        self.0
+    }
+}
+
+/// The kind of a gRPC method, given by which of its request and response are streams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MethodKind {
+    Unary,
+    ServerStreaming,
+    ClientStreaming,
+    Bidirectional,
+}
+
+impl From<&MethodDescriptor> for MethodKind {
+    fn from(method: &MethodDescriptor) -> Self {
+        match (method.is_client_streaming(), method.is_server_streaming()) {
+            (false, false) => MethodKind::Unary,
+            (false, true) => MethodKind::ServerStreaming,
+            (true, false) => MethodKind::ClientStreaming,
+            (true, true) => MethodKind::Bidirectional,
+        }
+    }
 }
 
-/// The result of a dynamic gRPC call.
+/// The result of a dynamic gRPC call, along with the kind of the method that produced it.
 #[derive(Debug, Clone)]
 pub enum DynamicResponse {
     /// A single response message (for Unary and Client Streaming calls).
-    Unary(Result<serde_json::Value, tonic::Status>),
+    Unary(MethodKind, Result<serde_json::Value, tonic::Status>),
     /// A stream of response messages (for Server Streaming and Bidirectional calls).
-    Streaming(Result<Vec<Result<serde_json::Value, tonic::Status>>, tonic::Status>),
+    ///
+    /// * `Ok(messages)` - The call started. If the server failed mid-way, the messages received
+    ///   before the failure are kept and its status is the last item. An empty list means the
+    ///   stream completed successfully without sending any message.
+    /// * `Err(status)` - The server rejected the call before sending any message.
+    Streaming(
+        MethodKind,
+        Result<Vec<Result<serde_json::Value, tonic::Status>>, tonic::Status>,
+    ),
+}
+
+impl DynamicResponse {
+    /// The kind of the method that was called.
+    pub fn kind(&self) -> MethodKind {
+        match self {
+            DynamicResponse::Unary(kind, _) | DynamicResponse::Streaming(kind, _) => *kind,
+        }
+    }
 }
 
 /// A generic wrapper for different types of Protobuf descriptors.
@@ -8,24 +8,36 @@
 //! The [`GrpcClient`] utilizes the [`super::codec::JsonCodec`] to handle serialization.
 //! It does not need to know the structure of the data it is sending; it simply ensures
 //! the connection is established and passes the `serde_json::Value` and `MethodDescriptor`
-//! to the codec.
+//! to the codec. Requests can also be messages built beforehand, see [`RequestMessage`].
 //!
 //! ## Features
 //!
 //! * **Dynamic Pathing**: Constructs the HTTP/2 path (e.g., `/package.Service/Method`) at runtime.
 //! * **Metadata Handling**: Converts standard Rust string tuples into Tonic's `MetadataMap` for headers.
+//! * **Deadlines**: An optional timeout is sent to the server as the `grpc-timeout` header and also enforced
+//!   client-side, for streaming calls too: once the deadline passes, the response stream yields a
+//!   `DeadlineExceeded` status and ends.
+//! * **Cancellation**: Streaming calls accept an optional [`CancellationToken`]. Cancelling it ends the
+//!   response stream cleanly and drops the underlying request.
 //! * **Access Patterns**: Provides specific methods for Unary, Server Streaming, Client Streaming,
 //!   and Bidirectional Streaming calls.
-use super::codec::JsonCodec;
+//! * **Raw Calls**: [`GrpcClient::unary_raw`] sends pre-encoded bytes with the [`RawCodec`], which needs no schema.
+//! * **Message Types Only**: [`GrpcClient::unary_with_types`] calls a method given its message types,
+//!   when the schema lacks its service.
+//! * **Fan-out**: [`GrpcClient::unary_many`] sends many unary calls at once over the same connection.
+use super::codec::{JsonCodec, RawCodec, RequestMessage};
 use crate::BoxError;
-use futures_util::Stream;
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt};
 use http_body::Body as HttpBody;
-use prost_reflect::MethodDescriptor;
-use std::str::FromStr;
+use prost_reflect::{DeserializeOptions, MessageDescriptor, MethodDescriptor};
+use std::{str::FromStr, time::Duration};
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
 use tonic::{
     client::GrpcService,
     metadata::{
-        MetadataKey, MetadataValue,
+        MetadataKey, MetadataMap, MetadataValue,
         errors::{InvalidMetadataKey, InvalidMetadataValue},
     },
     transport::Channel,
@@ -45,12 +57,16 @@ pub enum GrpcRequestError {
         key: String,
         source: InvalidMetadataValue,
     },
+    #[error("Invalid method path '{0}'")]
+    InvalidPath(String),
 }
 
 /// A generic client for the gRPC Server Reflection Protocol.
 #[derive(Debug, Clone)]
 pub struct GrpcClient<S = Channel> {
     client: tonic::client::Grpc<S>,
+    /// Passed to the codec of every call, see [`JsonCodec::with_deserialize_options`].
+    deserialize_options: Option<DeserializeOptions>,
 }
 
 impl<S> GrpcClient<S>
@@ -62,7 +78,39 @@ where
 {
     pub fn new(service: S) -> Self {
         let client = tonic::client::Grpc::new(service);
-        Self { client }
+        Self {
+            client,
+            deserialize_options: None,
+        }
+    }
+
+    /// Replaces the connection, keeping the deserialize options set so far.
+    pub fn with_service<T>(self, service: T) -> GrpcClient<T> {
+        GrpcClient {
+            client: tonic::client::Grpc::new(service),
+            deserialize_options: self.deserialize_options,
+        }
+    }
+
+    /// Sets how request bodies are deserialized, e.g. to ignore unknown fields.
+    pub fn with_deserialize_options(self, deserialize_options: DeserializeOptions) -> Self {
+        Self {
+            deserialize_options: Some(deserialize_options),
+            ..self
+        }
+    }
+
+    fn codec<T>(&self, method: &MethodDescriptor) -> JsonCodec<T> {
+        self.codec_for(method.input(), method.output())
+    }
+
+    fn codec_for<T>(&self, input: MessageDescriptor, output: MessageDescriptor) -> JsonCodec<T> {
+        let codec = JsonCodec::new(input, output);
+
+        match &self.deserialize_options {
+            Some(options) => codec.with_deserialize_options(options.clone()),
+            None => codec,
+        }
     }
 
     /// Performs a Unary gRPC call (Single Request -> Single Response).
@@ -71,22 +119,94 @@ where
     /// * `Ok(Ok(Value))` - Successful RPC execution.
     /// * `Ok(Err(Status))` - RPC executed, but server returned an error.
     /// * `Err(ClientError)` - Failed to send request or connect.
-    pub async fn unary(
+    pub async fn unary<T: RequestMessage>(
         &mut self,
         method: MethodDescriptor,
-        payload: serde_json::Value,
+        payload: T,
         headers: Vec<(String, String)>,
+        timeout: Option<Duration>,
     ) -> Result<Result<serde_json::Value, tonic::Status>, GrpcRequestError> {
         self.client
             .ready()
             .await
             .map_err(|e| GrpcRequestError::ClientNotReady(e.into()))?;
 
-        let codec = JsonCodec::new(method.input(), method.output());
+        let codec = self.codec(&method);
         let path = http_path(&method);
-        let request = build_request(payload, headers)?;
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let request = build_request(payload, headers, timeout)?;
+
+        match with_deadline(self.client.unary(request, path, codec), deadline).await {
+            Ok(response) => Ok(Ok(response.into_inner())),
+            Err(status) => Ok(Err(status)),
+        }
+    }
+
+    /// Performs a Unary gRPC call to `path` (`/package.Service/Method`), whose messages are of the
+    /// given types.
+    ///
+    /// Unlike [`GrpcClient::unary`], no `MethodDescriptor` is needed, for schemas that define the
+    /// messages of a method but not its service.
+    ///
+    /// # Returns
+    /// * `Ok(Ok(Value))` - Successful RPC execution.
+    /// * `Ok(Err(Status))` - RPC executed, but server returned an error.
+    /// * `Err(ClientError)` - Failed to send request or connect.
+    pub async fn unary_with_types<T: RequestMessage>(
+        &mut self,
+        path: &str,
+        input: MessageDescriptor,
+        output: MessageDescriptor,
+        payload: T,
+        headers: Vec<(String, String)>,
+        timeout: Option<Duration>,
+    ) -> Result<Result<serde_json::Value, tonic::Status>, GrpcRequestError> {
+        self.client
+            .ready()
+            .await
+            .map_err(|e| GrpcRequestError::ClientNotReady(e.into()))?;
+
+        let codec = self.codec_for(input, output);
+        let path = http::uri::PathAndQuery::from_str(path)
+            .map_err(|_| GrpcRequestError::InvalidPath(path.to_string()))?;
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let request = build_request(payload, headers, timeout)?;
 
-        match self.client.unary(request, path, codec).await {
+        match with_deadline(self.client.unary(request, path, codec), deadline).await {
+            Ok(response) => Ok(Ok(response.into_inner())),
+            Err(status) => Ok(Err(status)),
+        }
+    }
+
+    /// Performs a Unary gRPC call with a pre-encoded Protobuf message, returning the encoded response.
+    ///
+    /// No schema is involved: the bytes are sent and returned as they are, see [`RawCodec`].
+    ///
+    /// # Returns
+    /// * `Ok(Ok(Bytes))` - Successful RPC execution.
+    /// * `Ok(Err(Status))` - RPC executed, but server returned an error.
+    /// * `Err(ClientError)` - Failed to send request or connect.
+    pub async fn unary_raw(
+        &mut self,
+        service: &str,
+        method: &str,
+        payload: Bytes,
+        headers: Vec<(String, String)>,
+        timeout: Option<Duration>,
+    ) -> Result<Result<Bytes, tonic::Status>, GrpcRequestError> {
+        self.client
+            .ready()
+            .await
+            .map_err(|e| GrpcRequestError::ClientNotReady(e.into()))?;
+
+        let path = format!("/{service}/{method}");
+        let path = http::uri::PathAndQuery::from_str(&path)
+            .map_err(|_| GrpcRequestError::InvalidPath(path))?;
+        tracing::debug!("Sending raw request to {path}");
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let request = build_request(payload, headers, timeout)?;
+
+        match with_deadline(self.client.unary(request, path, RawCodec), deadline).await {
             Ok(response) => Ok(Ok(response.into_inner())),
             Err(status) => Ok(Err(status)),
         }
@@ -94,16 +214,20 @@ where
 
     /// Performs a Server Streaming gRPC call (Single Request -> Stream of Responses).
     ///
+    /// Cancelling `cancellation` ends the returned stream cleanly and drops the request.
+    ///
     /// # Returns
     ///
     /// * `Ok(Ok(Stream))` - Successful RPC execution.
     /// * `Ok(Err(Status))` - RPC executed, but server returned an error.
     /// * `Err(ClientError)` - Failed to send request or connect.
-    pub async fn server_streaming(
+    pub async fn server_streaming<T: RequestMessage>(
         &mut self,
         method: MethodDescriptor,
-        payload: serde_json::Value,
+        payload: T,
         headers: Vec<(String, String)>,
+        timeout: Option<Duration>,
+        cancellation: Option<CancellationToken>,
     ) -> Result<
         Result<impl Stream<Item = Result<serde_json::Value, tonic::Status>>, tonic::Status>,
         GrpcRequestError,
@@ -113,12 +237,17 @@ where
             .await
             .map_err(|e| GrpcRequestError::ClientNotReady(e.into()))?;
 
-        let codec = JsonCodec::new(method.input(), method.output());
+        let codec = self.codec(&method);
         let path = http_path(&method);
-        let request = build_request(payload, headers)?;
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let request = build_request(payload, headers, timeout)?;
 
-        match self.client.server_streaming(request, path, codec).await {
-            Ok(response) => Ok(Ok(response.into_inner())),
+        match with_deadline(self.client.server_streaming(request, path, codec), deadline).await {
+            Ok(response) => Ok(Ok(guard_stream(
+                response.into_inner(),
+                deadline,
+                cancellation,
+            ))),
             Err(status) => Ok(Err(status)),
         }
     }
@@ -130,22 +259,24 @@ where
     /// * `Ok(Ok(Value))` - Successful RPC execution.
     /// * `Ok(Err(Status))` - RPC executed, but server returned an error.
     /// * `Err(ClientError)` - Failed to send request or connect.
-    pub async fn client_streaming(
+    pub async fn client_streaming<T: RequestMessage>(
         &mut self,
         method: MethodDescriptor,
-        payload_stream: impl Stream<Item = serde_json::Value> + Send + 'static,
+        payload_stream: impl Stream<Item = T> + Send + 'static,
         headers: Vec<(String, String)>,
+        timeout: Option<Duration>,
     ) -> Result<Result<serde_json::Value, tonic::Status>, GrpcRequestError> {
         self.client
             .ready()
             .await
             .map_err(|e| GrpcRequestError::ClientNotReady(e.into()))?;
 
-        let codec = JsonCodec::new(method.input(), method.output());
+        let codec = self.codec(&method);
         let path = http_path(&method);
-        let request = build_request(payload_stream, headers)?;
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let request = build_request(payload_stream, headers, timeout)?;
 
-        match self.client.client_streaming(request, path, codec).await {
+        match with_deadline(self.client.client_streaming(request, path, codec), deadline).await {
             Ok(response) => Ok(Ok(response.into_inner())),
             Err(status) => Ok(Err(status)),
         }
@@ -153,37 +284,92 @@ where
 
     /// Performs a Bidirectional Streaming gRPC call (Stream of Requests -> Stream of Responses).
     ///
+    /// Cancelling `cancellation` ends the returned stream cleanly and drops the request.
+    /// The returned stream doesn't borrow the client, so it can outlive it.
+    ///
     /// # Returns
     ///
     /// * `Ok(Ok(Stream))` - Successful RPC execution.
     /// * `Ok(Err(Status))` - RPC executed, but server returned an error.
     /// * `Err(ClientError)` - Failed to send request or connect.
-    pub async fn bidirectional_streaming(
+    pub async fn bidirectional_streaming<T, P>(
         &mut self,
         method: MethodDescriptor,
-        payload_stream: impl Stream<Item = serde_json::Value> + Send + 'static,
+        payload_stream: P,
         headers: Vec<(String, String)>,
+        timeout: Option<Duration>,
+        cancellation: Option<CancellationToken>,
     ) -> Result<
-        Result<impl Stream<Item = Result<serde_json::Value, tonic::Status>>, tonic::Status>,
+        Result<
+            impl Stream<Item = Result<serde_json::Value, tonic::Status>> + use<S, T, P>,
+            tonic::Status,
+        >,
         GrpcRequestError,
-    > {
+    >
+    where
+        T: RequestMessage,
+        P: Stream<Item = T> + Send + 'static,
+    {
         self.client
             .ready()
             .await
             .map_err(|e| GrpcRequestError::ClientNotReady(e.into()))?;
 
-        let codec = JsonCodec::new(method.input(), method.output());
+        let codec = self.codec(&method);
         let path = http_path(&method);
-        let request = build_request(payload_stream, headers)?;
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let request = build_request(payload_stream, headers, timeout)?;
 
-        match self.client.streaming(request, path, codec).await {
-            Ok(response) => Ok(Ok(response.into_inner())),
+        match with_deadline(self.client.streaming(request, path, codec), deadline).await {
+            Ok(response) => Ok(Ok(guard_stream(
+                response.into_inner(),
+                deadline,
+                cancellation,
+            ))),
             Err(status) => Ok(Err(status)),
         }
     }
 }
 
-fn http_path(method: &MethodDescriptor) -> http::uri::PathAndQuery {
+impl<S> GrpcClient<S>
+where
+    S: GrpcService<tonic::body::Body> + Clone,
+    S::Error: Into<BoxError>,
+    S::ResponseBody: HttpBody<Data = tonic::codegen::Bytes> + Send + 'static,
+    <S::ResponseBody as HttpBody>::Error: Into<BoxError> + Send,
+{
+    /// Performs a Unary gRPC call for each payload, with at most `concurrency` of them in flight at once.
+    ///
+    /// Every call is sent on a clone of the client, so they share its connection. A `concurrency`
+    /// of 0 is treated as 1.
+    ///
+    /// # Returns
+    ///
+    /// The result of each call, in the order of `payloads` (see [`GrpcClient::unary`]).
+    pub async fn unary_many(
+        &self,
+        method: MethodDescriptor,
+        payloads: Vec<serde_json::Value>,
+        headers: Vec<(String, String)>,
+        timeout: Option<Duration>,
+        concurrency: usize,
+    ) -> Vec<Result<Result<serde_json::Value, tonic::Status>, GrpcRequestError>> {
+        futures_util::stream::iter(payloads)
+            .map(|payload| {
+                let mut client = self.clone();
+                let method = method.clone();
+                let headers = headers.clone();
+
+                async move { client.unary(method, payload, headers, timeout).await }
+            })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+}
+
+/// The path of a method on the wire: `/{parent service full name}/{method name}`.
+pub(crate) fn http_path(method: &MethodDescriptor) -> http::uri::PathAndQuery {
     let path = format!("/{}/{}", method.parent_service().full_name(), method.name());
     http::uri::PathAndQuery::from_str(&path).expect("valid gRPC path")
 }
@@ -191,8 +377,24 @@ fn http_path(method: &MethodDescriptor) -> http::uri::PathAndQuery {
 fn build_request<T>(
     payload: T,
     headers: Vec<(String, String)>,
+    timeout: Option<Duration>,
 ) -> Result<tonic::Request<T>, GrpcRequestError> {
-    let mut request = tonic::Request::new(payload);
+    let metadata = metadata_from_headers(headers)?;
+    let mut request = tonic::Request::from_parts(metadata, Default::default(), payload);
+    if let Some(timeout) = timeout {
+        // Sets the `grpc-timeout` header so the server knows the deadline
+        request.set_timeout(timeout);
+    }
+    Ok(request)
+}
+
+/// Converts headers into request metadata, validating their keys and values.
+///
+/// A header given more than once keeps its last value.
+pub fn metadata_from_headers(
+    headers: Vec<(String, String)>,
+) -> Result<MetadataMap, GrpcRequestError> {
+    let mut metadata = MetadataMap::new();
     for (k, v) in headers {
         let key =
             MetadataKey::from_str(&k).map_err(|source| GrpcRequestError::InvalidMetadataKey {
@@ -201,7 +403,67 @@ fn build_request<T>(
             })?;
         let val = MetadataValue::from_str(&v)
             .map_err(|source| GrpcRequestError::InvalidMetadataValue { key: k, source })?;
-        request.metadata_mut().insert(key, val);
+        metadata.insert(key, val);
     }
-    Ok(request)
+    Ok(metadata)
+}
+
+fn deadline_exceeded() -> tonic::Status {
+    tonic::Status::deadline_exceeded("Deadline exceeded")
+}
+
+/// Fails the call with `DeadlineExceeded` if it doesn't complete before the deadline.
+async fn with_deadline<T>(
+    call: impl Future<Output = Result<T, tonic::Status>>,
+    deadline: Option<Instant>,
+) -> Result<T, tonic::Status> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, call)
+            .await
+            .unwrap_or_else(|_| Err(deadline_exceeded())),
+        None => call.await,
+    }
+}
+
+/// Guards a response stream with the deadline and the cancellation token of the call.
+///
+/// * Once the deadline passes, the stream yields a `DeadlineExceeded` status and ends.
+/// * Once the token is cancelled, the stream ends without yielding an error.
+///
+/// Dropping the inner stream cancels the underlying request, so the server stops producing.
+fn guard_stream<S>(
+    stream: S,
+    deadline: Option<Instant>,
+    cancellation: Option<CancellationToken>,
+) -> impl Stream<Item = Result<serde_json::Value, tonic::Status>>
+where
+    S: Stream<Item = Result<serde_json::Value, tonic::Status>> + Unpin,
+{
+    futures_util::stream::unfold(Some(stream), move |stream| {
+        let cancellation = cancellation.clone();
+
+        async move {
+            let mut stream = stream?;
+
+            // Yields the next item, and whether the stream may continue after it
+            let next = async {
+                match deadline {
+                    Some(deadline) => {
+                        match tokio::time::timeout_at(deadline, stream.next()).await {
+                            Ok(item) => item.map(|item| (item, true)),
+                            Err(_) => Some((Err(deadline_exceeded()), false)),
+                        }
+                    }
+                    None => stream.next().await.map(|item| (item, true)),
+                }
+            };
+
+            let next = match cancellation {
+                Some(token) => token.run_until_cancelled(next).await.flatten(),
+                None => next.await,
+            };
+
+            next.map(|(item, more)| (item, more.then_some(stream)))
+        }
+    })
 }
@@ -9,49 +9,198 @@
 //!    - Takes a `serde_json::Value`.
 //!    - Uses `prost_reflect::DynamicMessage` to validate the JSON against the input `MessageDescriptor`.
 //!    - Serializes the valid message into the generic gRPC byte buffer.
+//!    - Messages built beforehand (e.g. parsed from protobuf text format) skip the JSON step,
+//!      see [`RequestMessage`].
 //!
 //! 2. **Decoder (Proto -> JSON)**:
 //!    - Reads raw bytes from the wire.
 //!    - Decodes them into a `DynamicMessage` using the output `MessageDescriptor`.
 //!    - Converts the message back into a `serde_json::Value` for the CLI to print.
-use prost::Message;
-use prost_reflect::{DynamicMessage, MessageDescriptor};
+//!      Proto3 `optional` fields that are set to their default value are kept, absent ones are omitted.
+//!
+//! ## `google.protobuf.Any`
+//!
+//! `Any` values are expanded into their full JSON representation (`{"@type": "...", ...fields}`)
+//! when the embedded type URL resolves within the descriptor pool of the response message.
+//! Otherwise they are kept opaque as `{"@type": "...", "value": "<base64>"}` instead of failing
+//! the whole response.
+//!
+//! ## Invalid UTF-8
+//!
+//! Protobuf requires `string` fields to be valid UTF-8, but misbehaving servers may still send
+//! other bytes. Rather than failing the whole response, invalid sequences are replaced with
+//! `U+FFFD` (`�`) and a warning is logged. Control characters are valid UTF-8 and kept as they
+//! are; they are escaped when the JSON is printed, so they can't garble the terminal.
+//!
+//! ## Unknown fields
+//!
+//! Fields of a request body that are not part of the schema are rejected by default, as they are
+//! usually typos. [`find_unknown_field`] tells which field is wrong and lists the valid ones.
+//!
+//! ## Hex bytes
+//!
+//! `bytes` fields are base64 encoded in JSON. [`bytes_as_hex`] rewrites them as hex strings in a
+//! decoded response, which is often easier to read when debugging binary fields.
+//!
+//! ## Raw bytes
+//!
+//! [`RawCodec`] skips transcoding altogether: requests are pre-encoded Protobuf messages that are
+//! sent as they are, and responses are returned as the bytes read from the wire.
+//!
+//! ## Message sizes
+//!
+//! Both codecs log the encoded size of every message they write or read, as debug events of the
+//! [`SIZES_TARGET`] target (e.g. to diagnose "message too large" errors).
+use base64::{Engine, engine::general_purpose::STANDARD};
+use bytes::{Buf, BufMut, Bytes};
+use prost::{
+    Message,
+    encoding::{WireType, decode_varint, encode_varint},
+};
+use prost_reflect::{
+    DescriptorPool, DeserializeOptions, DynamicMessage, Kind, MapKey, MessageDescriptor,
+    ReflectMessage, SerializeOptions, Value,
+};
+use std::marker::PhantomData;
 use tonic::{
     Status,
     codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder},
 };
 
+/// The `tracing` target of the events that report the size of every message, with a `bytes` field.
+pub const SIZES_TARGET: &str = "granc_core::sizes";
+
+/// A request that [`JsonCodec`] can encode.
+///
+/// Implemented for JSON bodies, which are deserialized with the schema of the input message, and
+/// for a [`DynamicMessage`] built beforehand, which is sent as it is.
+pub trait RequestMessage: Send + Sync + 'static {
+    /// Turns the request into a message of type `desc`.
+    fn into_message(
+        self,
+        desc: &MessageDescriptor,
+        options: &DeserializeOptions,
+    ) -> Result<DynamicMessage, Status>;
+
+    /// The first field of the request that is not part of `desc`, see [`find_unknown_field`].
+    fn find_unknown_field(&self, desc: &MessageDescriptor) -> Option<UnknownFieldError>;
+}
+
+impl RequestMessage for serde_json::Value {
+    fn into_message(
+        self,
+        desc: &MessageDescriptor,
+        options: &DeserializeOptions,
+    ) -> Result<DynamicMessage, Status> {
+        // As in the proto3 JSON mapping, fields can be named either as declared (`user_id`) or by
+        // their JSON name (`userId`). Unknown fields are rejected unless the options allow them.
+        // serde_json::Value implements IntoDeserializer, so we can pass it directly.
+        DynamicMessage::deserialize_with_options(desc.clone(), self, options).map_err(|e| {
+            Status::invalid_argument(format!(
+                "JSON structure does not match Protobuf schema: {}",
+                e
+            ))
+        })
+    }
+
+    fn find_unknown_field(&self, desc: &MessageDescriptor) -> Option<UnknownFieldError> {
+        find_unknown_field(desc, self)
+    }
+}
+
+impl RequestMessage for DynamicMessage {
+    fn into_message(
+        self,
+        desc: &MessageDescriptor,
+        _options: &DeserializeOptions,
+    ) -> Result<DynamicMessage, Status> {
+        if self.descriptor().full_name() != desc.full_name() {
+            return Err(Status::invalid_argument(format!(
+                "Expected a message of type '{}', got '{}'",
+                desc.full_name(),
+                self.descriptor().full_name()
+            )));
+        }
+
+        Ok(self)
+    }
+
+    // A message only holds the fields of its own type
+    fn find_unknown_field(&self, _desc: &MessageDescriptor) -> Option<UnknownFieldError> {
+        None
+    }
+}
+
 /// A custom Codec that bridges `serde_json::Value` and Protobuf binary format.
 ///
 /// It holds the descriptors (schemas) for both the request and the response messages,
-/// allowing it to perform dynamic serialization.
-pub struct JsonCodec {
+/// allowing it to perform dynamic serialization. Requests are JSON bodies by default, or any
+/// other [`RequestMessage`].
+pub struct JsonCodec<T = serde_json::Value> {
     /// Schema for the input message.
     req_desc: MessageDescriptor,
     /// Schema for the output message.
     res_desc: MessageDescriptor,
+    /// How request bodies are turned into messages.
+    deserialize_options: DeserializeOptions,
+    request: PhantomData<fn(T)>,
 }
 
-impl JsonCodec {
+impl<T> JsonCodec<T> {
     /// Creates a new `JsonCodec`.
     ///
     /// # Arguments
     /// * `req_desc` - Descriptor for the request message type.
-    /// * `res_desc` - Descriptor for the response message type.    
+    /// * `res_desc` - Descriptor for the response message type.
+    ///
+    /// Unknown fields in request bodies are rejected, see [`JsonCodec::with_deserialize_options`].
     pub fn new(req_desc: MessageDescriptor, res_desc: MessageDescriptor) -> Self {
-        Self { req_desc, res_desc }
+        Self {
+            req_desc,
+            res_desc,
+            deserialize_options: DeserializeOptions::new().deny_unknown_fields(true),
+            request: PhantomData,
+        }
+    }
+
+    /// Sets how request bodies are deserialized, e.g. to ignore unknown fields with
+    /// `DeserializeOptions::new().deny_unknown_fields(false)`.
+    pub fn with_deserialize_options(self, deserialize_options: DeserializeOptions) -> Self {
+        Self {
+            deserialize_options,
+            ..self
+        }
+    }
+
+    /// Resolves both messages (and the `Any` values within them) through the given pool.
+    ///
+    /// By default, the pool the descriptors were created from is used. Messages that are not
+    /// part of `pool` keep their original descriptor.
+    pub fn with_descriptor_pool(self, pool: &DescriptorPool) -> Self {
+        let resolve =
+            |desc: MessageDescriptor| pool.get_message_by_name(desc.full_name()).unwrap_or(desc);
+
+        Self {
+            req_desc: resolve(self.req_desc),
+            res_desc: resolve(self.res_desc),
+            ..self
+        }
     }
 }
 
-impl Codec for JsonCodec {
-    type Encode = serde_json::Value;
+impl<T: RequestMessage> Codec for JsonCodec<T> {
+    type Encode = T;
     type Decode = serde_json::Value;
 
-    type Encoder = JsonEncoder;
+    type Encoder = JsonEncoder<T>;
     type Decoder = JsonDecoder;
 
     fn encoder(&mut self) -> Self::Encoder {
-        JsonEncoder(self.req_desc.clone())
+        JsonEncoder(
+            self.req_desc.clone(),
+            self.deserialize_options.clone(),
+            PhantomData,
+        )
     }
 
     fn decoder(&mut self) -> Self::Decoder {
@@ -59,23 +208,199 @@ impl Codec for JsonCodec {
     }
 }
 
-/// Responsible for encoding a JSON value into Protobuf bytes.
-pub struct JsonEncoder(MessageDescriptor);
+/// A Codec that passes pre-encoded Protobuf messages through, without any schema.
+///
+/// Useful to send a payload exactly as it was encoded elsewhere, or to inspect the bytes of a
+/// response when debugging encoding issues.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RawCodec;
+
+impl Codec for RawCodec {
+    type Encode = Bytes;
+    type Decode = Bytes;
 
-impl Encoder for JsonEncoder {
-    type Item = serde_json::Value;
+    type Encoder = RawEncoder;
+    type Decoder = RawDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        RawEncoder
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        RawDecoder
+    }
+}
+
+/// Writes an encoded message as is.
+pub struct RawEncoder;
+
+impl Encoder for RawEncoder {
+    type Item = Bytes;
     type Error = Status;
 
     fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
-        // DynamicMessage::deserialize accepts any Serde Deserializer.
-        // serde_json::Value implements IntoDeserializer, so we can pass it directly.
-        let msg = DynamicMessage::deserialize(self.0.clone(), item).map_err(|e| {
-            Status::invalid_argument(format!(
-                "JSON structure does not match Protobuf schema: {}",
-                e
-            ))
-        })?;
+        tracing::debug!(target: SIZES_TARGET, bytes = item.len(), "Request message");
+        dst.put(item);
+        Ok(())
+    }
+}
+
+/// Reads the bytes of a message as is.
+pub struct RawDecoder;
+
+impl Decoder for RawDecoder {
+    type Item = Bytes;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        tracing::debug!(target: SIZES_TARGET, bytes = src.remaining(), "Response message");
+        Ok(Some(src.copy_to_bytes(src.remaining())))
+    }
+}
+
+/// A field of a JSON body that doesn't exist in the message it belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "Unknown field '{field}' in message '{message}', expected one of: {}",
+    expected.join(", ")
+)]
+pub struct UnknownFieldError {
+    /// The key of the unknown field, as given in the JSON body.
+    pub field: String,
+    /// The full name of the message missing the field.
+    pub message: String,
+    /// The fields of the message, with their JSON name when it differs (e.g. `user_id (userId)`).
+    pub expected: Vec<String>,
+}
+
+/// Looks for the first field of `value`, or of the messages nested within it, that is not part of
+/// the schema described by `desc`.
+///
+/// Fields can be named either as declared or by their JSON name. Well-known types, which have
+/// their own JSON representation, are not inspected.
+pub fn find_unknown_field(
+    desc: &MessageDescriptor,
+    value: &serde_json::Value,
+) -> Option<UnknownFieldError> {
+    let serde_json::Value::Object(object) = value else {
+        return None;
+    };
+
+    if desc.package_name() == "google.protobuf" {
+        return None;
+    }
+
+    for (key, value) in object {
+        let Some(field) = desc
+            .get_field_by_json_name(key)
+            .or_else(|| desc.get_field_by_name(key))
+        else {
+            return Some(UnknownFieldError {
+                field: key.clone(),
+                message: desc.full_name().to_string(),
+                expected: desc
+                    .fields()
+                    .map(|f| match f.json_name() == f.name() {
+                        true => f.name().to_string(),
+                        false => format!("{} ({})", f.name(), f.json_name()),
+                    })
+                    .collect(),
+            });
+        };
 
+        let unknown = match (field.kind(), value) {
+            (Kind::Message(entry), serde_json::Value::Object(map)) if field.is_map() => match entry
+                .map_entry_value_field()
+                .kind()
+            {
+                Kind::Message(inner) => map.values().find_map(|v| find_unknown_field(&inner, v)),
+                _ => None,
+            },
+            (Kind::Message(inner), serde_json::Value::Array(items)) if field.is_list() => items
+                .iter()
+                .find_map(|item| find_unknown_field(&inner, item)),
+            (Kind::Message(inner), value) => find_unknown_field(&inner, value),
+            _ => None,
+        };
+
+        if unknown.is_some() {
+            return unknown;
+        }
+    }
+
+    None
+}
+
+/// Rewrites the `bytes` fields of `value`, a message described by `desc`, from base64 (as in the
+/// proto3 JSON mapping) to lowercase hex strings.
+///
+/// Values are matched with their field through the schema, so strings that merely look like
+/// base64 are left alone. Nested messages, lists, map values and `google.protobuf.BytesValue`
+/// wrappers are rewritten too.
+pub fn bytes_as_hex(desc: &MessageDescriptor, value: &mut serde_json::Value) {
+    let serde_json::Value::Object(object) = value else {
+        return;
+    };
+
+    for (key, value) in object.iter_mut() {
+        let Some(field) = desc
+            .get_field_by_json_name(key)
+            .or_else(|| desc.get_field_by_name(key))
+        else {
+            continue;
+        };
+
+        let kind = match field.kind() {
+            Kind::Message(entry) if field.is_map() => entry.map_entry_value_field().kind(),
+            kind => kind,
+        };
+
+        let items: Vec<&mut serde_json::Value> = match value {
+            serde_json::Value::Array(items) if field.is_list() => items.iter_mut().collect(),
+            serde_json::Value::Object(map) if field.is_map() => map.values_mut().collect(),
+            value => vec![value],
+        };
+
+        for item in items {
+            match &kind {
+                Kind::Bytes => base64_to_hex(item),
+                Kind::Message(inner) if inner.full_name() == "google.protobuf.BytesValue" => {
+                    base64_to_hex(item)
+                }
+                Kind::Message(inner) if inner.package_name() != "google.protobuf" => {
+                    bytes_as_hex(inner, item)
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn base64_to_hex(value: &mut serde_json::Value) {
+    let serde_json::Value::String(encoded) = value else {
+        return;
+    };
+
+    if let Ok(bytes) = STANDARD.decode(encoded.as_bytes()) {
+        *encoded = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    }
+}
+
+/// Responsible for encoding a JSON value (or another [`RequestMessage`]) into Protobuf bytes.
+pub struct JsonEncoder<T = serde_json::Value>(
+    MessageDescriptor,
+    DeserializeOptions,
+    PhantomData<fn(T)>,
+);
+
+impl<T: RequestMessage> Encoder for JsonEncoder<T> {
+    type Item = T;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        let msg = item.into_message(&self.0, &self.1)?;
+
+        tracing::debug!(target: SIZES_TARGET, bytes = msg.encoded_len(), "Request message");
         msg.encode_raw(dst);
         Ok(())
     }
@@ -90,16 +415,284 @@ impl Decoder for JsonDecoder {
 
     fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
         // 1. Decode Bytes -> DynamicMessage
-        let mut msg = DynamicMessage::new(self.0.clone());
-        msg.merge(src)
-            .map_err(|e| Status::internal(format!("Failed to decode Protobuf bytes: {}", e)))?;
+        let bytes = src.copy_to_bytes(src.remaining());
+        tracing::debug!(target: SIZES_TARGET, bytes = bytes.len(), "Response message");
+        let mut msg = match DynamicMessage::decode(self.0.clone(), bytes.clone()) {
+            Ok(msg) => msg,
+            Err(e) => match replace_invalid_utf8(&self.0, &bytes) {
+                Some(fixed) => {
+                    tracing::warn!("Replaced invalid UTF-8 in the string fields of the response");
+                    DynamicMessage::decode(self.0.clone(), fixed.as_slice()).map_err(|e| {
+                        Status::internal(format!("Failed to decode Protobuf bytes: {}", e))
+                    })?
+                }
+                None => {
+                    return Err(Status::internal(format!(
+                        "Failed to decode Protobuf bytes: {}",
+                        e
+                    )));
+                }
+            },
+        };
+
+        // 2. Set aside the `Any` values that can't be expanded, so they don't fail serialization
+        if let Some(value) = unresolved_any(&msg) {
+            return Ok(Some(value));
+        }
+
+        let mut unresolved = Vec::new();
+        take_unresolved_any(&mut msg, &mut Vec::new(), &mut unresolved);
 
-        // 2. DynamicMessage -> serde_json::Value
+        // 3. DynamicMessage -> serde_json::Value
         // We convert the DynamicMessage into a Value structure.
         // This is efficient and keeps the Client working with structured data.
-        let value = serde_json::to_value(&msg)
+        //
+        // Default values are skipped based on field presence: fields with explicit presence
+        // (proto3 `optional`, proto2 fields, messages) are emitted whenever they are set,
+        // even to their default value, while implicit presence fields are omitted when default.
+        let options = SerializeOptions::new().skip_default_fields(true);
+        let mut value = msg
+            .serialize_with_options(serde_json::value::Serializer, &options)
             .map_err(|e| Status::internal(format!("Failed to map response to JSON: {}", e)))?;
 
+        // 4. Put the opaque `Any` values back where they were
+        for (path, any) in unresolved {
+            insert_at(&mut value, &path, any);
+        }
+
         Ok(Some(value))
     }
 }
+
+/// Re-encodes a message, replacing the invalid UTF-8 sequences of its `string` fields (nested ones
+/// included) with `U+FFFD`.
+///
+/// Returns `None` if no string had to be replaced, or if the bytes are not a valid message.
+fn replace_invalid_utf8(desc: &MessageDescriptor, mut buf: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(buf.len());
+    let mut replaced = false;
+
+    rewrite_strings(desc, &mut buf, &mut out, &mut replaced, None)?;
+
+    replaced.then_some(out)
+}
+
+// Copies the fields of a message from `buf` to `out`, until the end of `buf` or, for a group,
+// until its end tag
+fn rewrite_strings(
+    desc: &MessageDescriptor,
+    buf: &mut &[u8],
+    out: &mut Vec<u8>,
+    replaced: &mut bool,
+    group: Option<u32>,
+) -> Option<()> {
+    while !buf.is_empty() {
+        let key = decode_varint(buf).ok()?;
+        let number = u32::try_from(key >> 3).ok()?;
+        let kind = desc.get_field(number).map(|field| field.kind());
+        encode_varint(key, out);
+
+        match WireType::try_from(key & 0x7).ok()? {
+            WireType::Varint => encode_varint(decode_varint(buf).ok()?, out),
+            WireType::SixtyFourBit => copy_bytes(buf, out, 8)?,
+            WireType::ThirtyTwoBit => copy_bytes(buf, out, 4)?,
+            WireType::LengthDelimited => {
+                let len = usize::try_from(decode_varint(buf).ok()?).ok()?;
+                let payload = buf.get(..len)?;
+                *buf = &buf[len..];
+
+                let rewritten = match kind {
+                    Some(Kind::String) if std::str::from_utf8(payload).is_err() => {
+                        *replaced = true;
+                        Some(String::from_utf8_lossy(payload).into_owned().into_bytes())
+                    }
+                    Some(Kind::Message(inner)) => {
+                        let mut nested = Vec::with_capacity(len);
+                        rewrite_strings(&inner, &mut &payload[..], &mut nested, replaced, None)?;
+                        Some(nested)
+                    }
+                    _ => None,
+                };
+
+                let payload = rewritten.as_deref().unwrap_or(payload);
+                encode_varint(payload.len() as u64, out);
+                out.extend_from_slice(payload);
+            }
+            WireType::StartGroup => match kind {
+                Some(Kind::Message(inner)) => {
+                    rewrite_strings(&inner, buf, out, replaced, Some(number))?
+                }
+                // Without a schema, the end of the group is unknown
+                _ => return None,
+            },
+            WireType::EndGroup => return (group == Some(number)).then_some(()),
+        }
+    }
+
+    group.is_none().then_some(())
+}
+
+fn copy_bytes(buf: &mut &[u8], out: &mut Vec<u8>, len: usize) -> Option<()> {
+    out.extend_from_slice(buf.get(..len)?);
+    *buf = &buf[len..];
+    Some(())
+}
+
+/// A step from a message to one of its nested values, as it appears in the JSON output.
+#[derive(Clone)]
+enum PathSegment {
+    Field(String),
+    Index(usize),
+    Key(String),
+}
+
+/// Returns the opaque JSON representation of `msg` if it is an `Any` whose type is not in the pool.
+fn unresolved_any(msg: &DynamicMessage) -> Option<serde_json::Value> {
+    if msg.descriptor().full_name() != "google.protobuf.Any" {
+        return None;
+    }
+
+    let any: prost_types::Any = msg.transcode_to().ok()?;
+    let (_, message_name) = any.type_url.rsplit_once('/')?;
+
+    if msg
+        .descriptor()
+        .parent_pool()
+        .get_message_by_name(message_name)
+        .is_some()
+    {
+        return None;
+    }
+
+    Some(serde_json::json!({
+        "@type": any.type_url,
+        "value": STANDARD.encode(any.value),
+    }))
+}
+
+/// Removes every unresolvable `Any` nested in `msg`, collecting its JSON path and opaque value.
+///
+/// Resolved `Any` values are not inspected: their payload is expanded by `prost_reflect`.
+fn take_unresolved_any(
+    msg: &mut DynamicMessage,
+    path: &mut Vec<PathSegment>,
+    found: &mut Vec<(Vec<PathSegment>, serde_json::Value)>,
+) {
+    let mut cleared = Vec::new();
+
+    for (field, value) in msg.fields_mut() {
+        path.push(PathSegment::Field(field.json_name().to_string()));
+
+        match value {
+            Value::Message(nested) => match unresolved_any(nested) {
+                Some(any) => {
+                    found.push((path.clone(), any));
+                    cleared.push(field);
+                }
+                None => take_unresolved_any(nested, path, found),
+            },
+            Value::List(items) => {
+                let mut index = 0;
+                items.retain_mut(|item| {
+                    path.push(PathSegment::Index(index));
+                    index += 1;
+
+                    let keep = take_from_value(item, path, found);
+                    path.pop();
+                    keep
+                });
+            }
+            Value::Map(entries) => {
+                entries.retain(|key, item| {
+                    path.push(PathSegment::Key(map_key_to_string(key)));
+
+                    let keep = take_from_value(item, path, found);
+                    path.pop();
+                    keep
+                });
+            }
+            _ => {}
+        }
+
+        path.pop();
+    }
+
+    for field in cleared {
+        msg.clear_field(&field);
+    }
+}
+
+// Returns whether the list element or map value should be kept
+fn take_from_value(
+    value: &mut Value,
+    path: &mut Vec<PathSegment>,
+    found: &mut Vec<(Vec<PathSegment>, serde_json::Value)>,
+) -> bool {
+    let Value::Message(nested) = value else {
+        return true;
+    };
+
+    match unresolved_any(nested) {
+        Some(any) => {
+            found.push((path.clone(), any));
+            false
+        }
+        None => {
+            take_unresolved_any(nested, path, found);
+            true
+        }
+    }
+}
+
+fn map_key_to_string(key: &MapKey) -> String {
+    match key {
+        MapKey::Bool(b) => b.to_string(),
+        MapKey::I32(i) => i.to_string(),
+        MapKey::I64(i) => i.to_string(),
+        MapKey::U32(u) => u.to_string(),
+        MapKey::U64(u) => u.to_string(),
+        MapKey::String(s) => s.clone(),
+    }
+}
+
+// Paths are inserted in the order they were collected, so list indices land where they were
+fn insert_at(root: &mut serde_json::Value, path: &[PathSegment], any: serde_json::Value) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+
+    let mut current = root;
+
+    for (i, segment) in parents.iter().enumerate() {
+        // Containers left empty are skipped in the output, so they may need to be recreated
+        let empty = match path[i + 1] {
+            PathSegment::Index(_) => serde_json::Value::Array(Vec::new()),
+            _ => serde_json::Value::Object(Default::default()),
+        };
+
+        let next = match (segment, current) {
+            (PathSegment::Field(key) | PathSegment::Key(key), serde_json::Value::Object(map)) => {
+                map.entry(key.clone()).or_insert(empty)
+            }
+            (PathSegment::Index(index), serde_json::Value::Array(items)) => {
+                match items.get_mut(*index) {
+                    Some(item) => item,
+                    None => return,
+                }
+            }
+            _ => return,
+        };
+
+        current = next;
+    }
+
+    match (last, current) {
+        (PathSegment::Field(key) | PathSegment::Key(key), serde_json::Value::Object(map)) => {
+            map.insert(key.clone(), any);
+        }
+        (PathSegment::Index(index), serde_json::Value::Array(items)) => {
+            items.insert((*index).min(items.len()), any);
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,26 @@
+//! # gRPC-Web Transport
+//!
+//! A client transport for servers that only speak gRPC-Web, such as browser-facing services
+//! behind Envoy. It is `tonic_web`'s client layer around a regular [`Channel`], so the connection
+//! is established the same way as for gRPC, and reflection and the JSON codec work unchanged.
+//!
+//! ## Limitations
+//!
+//! gRPC-Web only defines unary and server streaming calls. Client and bidirectional streams may be
+//! rejected by the server, or only answered once every message has been sent.
+use http::Request;
+use tonic::{body::Body, transport::Channel};
+use tonic_web::{GrpcWebCall, GrpcWebClientLayer, GrpcWebClientService};
+use tower::{Layer, util::MapRequest};
+
+/// A [`Channel`] whose requests and responses are translated to and from gRPC-Web.
+pub type GrpcWebChannel = GrpcWebClientService<MapRequest<Channel, BoxWebCall>>;
+
+/// Turns the gRPC-Web body of a request back into the body type a [`Channel`] sends.
+type BoxWebCall = fn(Request<GrpcWebCall<Body>>) -> Request<Body>;
+
+/// Wraps `channel` with the gRPC-Web client layer.
+pub fn grpc_web_channel(channel: Channel) -> GrpcWebChannel {
+    let box_web_call: BoxWebCall = |request| request.map(Body::new);
+    GrpcWebClientLayer::new().layer(MapRequest::new(channel, box_web_call))
+}
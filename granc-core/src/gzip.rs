@@ -0,0 +1,134 @@
+//! # Gzip
+//!
+//! Descriptor sets may be stored gzipped (RFC 1952). They are recognized by their magic bytes and
+//! decompressed with `flate2`, member after member. As with `gzip -d`, zeros padding the data after
+//! the last member are ignored.
+//!
+//! The decompressed size is capped at [`MAX_DECOMPRESSED_SIZE`], so a small but highly compressed
+//! file can't exhaust the memory.
+use flate2::bufread::GzDecoder;
+use std::io::Read;
+
+/// The two bytes every gzip member starts with.
+const MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Largest decompressed descriptor set accepted, far above any real one.
+pub(crate) const MAX_DECOMPRESSED_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Returns `true` if `bytes` start like a gzip file.
+///
+/// A `FileDescriptorSet` can't start with these bytes, so the check is unambiguous.
+pub(crate) fn is_gzip(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+/// Decompresses gzip data, made of one or more members.
+///
+/// # Returns
+///
+/// * `Ok(Vec<u8>)` - The decompressed bytes of every member, one after the other.
+/// * `Err(String)` - The data is corrupt, or larger than [`MAX_DECOMPRESSED_SIZE`] once decompressed.
+pub(crate) fn decompress(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    decompress_with_limit(bytes, MAX_DECOMPRESSED_SIZE)
+}
+
+fn decompress_with_limit(bytes: &[u8], limit: u64) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let mut rest = bytes;
+
+    while rest.iter().any(|&b| b != 0) {
+        // One byte more than the limit tells data that fits exactly from larger data
+        let remaining = limit + 1 - out.len() as u64;
+
+        // Reads a single member, leaving `rest` right after it
+        GzDecoder::new(&mut rest)
+            .take(remaining)
+            .read_to_end(&mut out)
+            .map_err(|e| e.to_string())?;
+
+        if out.len() as u64 > limit {
+            return Err(format!(
+                "The decompressed data is larger than {limit} bytes"
+            ));
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{Compression, write::GzEncoder};
+    use std::io::Write;
+
+    fn text(n: usize) -> Vec<u8> {
+        (0..n)
+            .flat_map(|i| {
+                format!("message Message{i} {{ string field_{i} = {i}; }}\n").into_bytes()
+            })
+            .collect()
+    }
+
+    fn compress(data: &[u8], level: u32) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::new(level));
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_every_compression_level() {
+        let data = text(20);
+
+        for level in [0, 1, 6, 9] {
+            let compressed = compress(&data, level);
+            assert!(is_gzip(&compressed));
+            assert_eq!(decompress(&compressed).unwrap(), data, "level {level}");
+        }
+    }
+
+    #[test]
+    fn test_multiple_members() {
+        let mut compressed = compress(&text(3), 9);
+        compressed.extend(compress(&text(5), 1));
+
+        let mut expected = text(3);
+        expected.extend(text(5));
+        assert_eq!(decompress(&compressed).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_padding_after_the_last_member() {
+        let mut compressed = compress(&text(3), 9);
+        compressed.extend([0; 16]);
+
+        assert_eq!(decompress(&compressed).unwrap(), text(3));
+    }
+
+    #[test]
+    fn test_corrupt_data() {
+        let compressed = compress(&text(3), 9);
+
+        let truncated = &compressed[..compressed.len() - 4];
+        assert!(decompress(truncated).is_err());
+
+        // The CRC-32 of the trailer no longer matches
+        let mut corrupt = compressed.clone();
+        let crc = corrupt.len() - 8;
+        corrupt[crc] ^= 0xff;
+        assert!(decompress(&corrupt).is_err());
+
+        assert!(decompress(&MAGIC).is_err());
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let data = vec![0; 4096];
+        let compressed = compress(&data, 9);
+
+        assert_eq!(decompress_with_limit(&compressed, 4096).unwrap(), data);
+
+        let err = decompress_with_limit(&compressed, 4095).unwrap_err();
+        assert_eq!(err, "The decompressed data is larger than 4095 bytes");
+    }
+}
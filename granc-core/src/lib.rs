@@ -29,17 +29,19 @@
 //!
 //! ## Re-exports
 //!
-//! This crate re-exports `prost`, `prost-reflect`, and `tonic` to ensure that consumers
+//! This crate re-exports `prost`, `prost-reflect`, `tonic` and `tokio-util` to ensure that consumers
 //! use compatible versions of these underlying dependencies.
 //!
 //! See the README.md for more details about usage.
 pub mod client;
 pub mod grpc;
+mod gzip;
 pub mod reflection;
 
 // Re-exports
 pub use prost;
 pub use prost_reflect;
+pub use tokio_util;
 pub use tonic;
 
 /// Type alias for the standard boxed error used in generic bounds.
@@ -13,22 +13,33 @@
 //! This client is designed to be resilient and handles the recursive graph traversal required to reconstruct
 //! the full proto set from individual file descriptors.
 //!
+//! ## Versions
+//!
+//! Both `grpc.reflection.v1` and the older `grpc.reflection.v1alpha` are supported. Their messages
+//! are identical on the wire, only the path of the service differs.
+//!
+//! By default, v1 is tried first and v1alpha when the server doesn't implement v1. The version that
+//! worked is remembered for the following requests. See [`ReflectionClient::with_version`] to
+//! always use one of them.
+//!
 //! ## References
 //!
 //! * [gRPC Server Reflection Protocol](https://github.com/grpc/grpc/blob/master/doc/server-reflection.md)
 use crate::BoxError;
-use futures_util::stream::once;
+use futures_util::{Stream, future::ready, stream::once};
 use http_body::Body as HttpBody;
 use prost::Message;
 use prost_types::{FileDescriptorProto, FileDescriptorSet};
-use std::collections::{HashMap, HashSet};
-use tokio::sync::mpsc;
+use std::collections::{HashMap, HashSet, VecDeque};
+use tokio::sync::mpsc::{self, error::TrySendError};
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::transport::Channel;
-use tonic::{Streaming, client::GrpcService};
+use tonic::{
+    Code, Streaming, client::GrpcService, codegen::http::uri::PathAndQuery, metadata::MetadataMap,
+};
+use tonic_prost::ProstCodec;
 use tonic_reflection::pb::v1::{
-    ServerReflectionRequest, ServerReflectionResponse,
-    server_reflection_client::ServerReflectionClient, server_reflection_request::MessageRequest,
+    ServerReflectionRequest, ServerReflectionResponse, server_reflection_request::MessageRequest,
     server_reflection_response::MessageResponse,
 };
 
@@ -59,15 +70,46 @@ pub enum ReflectionResolveError {
     DecodeError(#[from] prost::DecodeError),
 }
 
-// The host defined in the reflection requests doesn't seem to be a mandatory field
-// and there is no documentation about what it is about.
-// So we won't enforce it from the user.
-const EMPTY_HOST: &str = "";
+/// Default number of requests that can be queued on a reflection stream.
+pub const DEFAULT_BUFFER_SIZE: usize = 100;
+
+/// Default number of dependency requests that can await a response at once.
+pub const DEFAULT_MAX_INFLIGHT: usize = 32;
+
+/// The version of the reflection service exposed by the server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReflectionVersion {
+    /// `grpc.reflection.v1.ServerReflection`.
+    #[default]
+    V1,
+    /// `grpc.reflection.v1alpha.ServerReflection`, still the only one exposed by older servers.
+    V1Alpha,
+}
+
+impl ReflectionVersion {
+    fn path(self) -> PathAndQuery {
+        match self {
+            ReflectionVersion::V1 => PathAndQuery::from_static(
+                "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo",
+            ),
+            ReflectionVersion::V1Alpha => PathAndQuery::from_static(
+                "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo",
+            ),
+        }
+    }
+}
 
 /// A client for interacting with the gRPC Server Reflection Service.
 #[derive(Debug, Clone)]
 pub struct ReflectionClient<T = Channel> {
-    client: ServerReflectionClient<T>,
+    client: tonic::client::Grpc<T>,
+    buffer_size: usize,
+    max_inflight: usize,
+    host: String,
+    version: ReflectionVersion,
+    /// Whether the version is still unknown, so v1alpha is tried when v1 is not implemented.
+    discover_version: bool,
+    metadata: MetadataMap,
 }
 
 impl<S> ReflectionClient<S>
@@ -79,8 +121,93 @@ where
 {
     /// Creates a new `ReflectionClient` using the provided gRPC service (e.g., a `Channel`).
     pub fn new(channel: S) -> Self {
-        let client = ServerReflectionClient::new(channel);
-        Self { client }
+        let client = tonic::client::Grpc::new(channel);
+        Self {
+            client,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            max_inflight: DEFAULT_MAX_INFLIGHT,
+            // Most servers ignore the host, so it is left empty unless it is required
+            host: String::new(),
+            version: ReflectionVersion::default(),
+            discover_version: true,
+            metadata: MetadataMap::new(),
+        }
+    }
+
+    /// Sets the `host` sent in every reflection request, for servers that serve a different schema
+    /// per virtual host or that check it against the connection.
+    ///
+    /// It is empty by default, but clients created with [`crate::client::GrancClient::connect`]
+    /// send the authority of the URI (e.g. `localhost:50051`).
+    pub fn with_host(self, host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            ..self
+        }
+    }
+
+    /// Sets the metadata (headers) sent with every reflection stream, for servers that require
+    /// authentication on reflection too (empty by default).
+    ///
+    /// See [`crate::grpc::client::metadata_from_headers`] to build it from `key: value` pairs.
+    pub fn with_metadata(self, metadata: MetadataMap) -> Self {
+        Self { metadata, ..self }
+    }
+
+    /// Sets the version of the reflection service to talk to, without falling back to the other one.
+    ///
+    /// By default, [`ReflectionVersion::V1`] is tried first and [`ReflectionVersion::V1Alpha`] when
+    /// the server answers `Unimplemented`.
+    pub fn with_version(self, version: ReflectionVersion) -> Self {
+        Self {
+            version,
+            discover_version: false,
+            ..self
+        }
+    }
+
+    /// Replaces the connection, keeping the host, metadata, version and limits set so far.
+    pub fn with_service<T>(self, service: T) -> ReflectionClient<T> {
+        ReflectionClient {
+            client: tonic::client::Grpc::new(service),
+            buffer_size: self.buffer_size,
+            max_inflight: self.max_inflight,
+            host: self.host,
+            version: self.version,
+            discover_version: self.discover_version,
+            metadata: self.metadata,
+        }
+    }
+
+    /// The version of the reflection service in use: the one that worked once discovered, or the
+    /// one that is tried first.
+    pub fn version(&self) -> ReflectionVersion {
+        self.version
+    }
+
+    /// Sets how many requests can be queued on the stream while resolving dependencies
+    /// (defaults to [`DEFAULT_BUFFER_SIZE`]).
+    ///
+    /// Dependency requests that don't fit in the buffer are kept until the server takes the
+    /// queued ones. A size of zero is treated as one.
+    pub fn with_buffer_size(self, buffer_size: usize) -> Self {
+        Self {
+            buffer_size: buffer_size.max(1),
+            ..self
+        }
+    }
+
+    /// Sets how many requests can await a response at once while resolving dependencies
+    /// (defaults to [`DEFAULT_MAX_INFLIGHT`]).
+    ///
+    /// Schemas with thousands of files would otherwise send as many `FileByFilename` requests
+    /// at once, which some servers rate limit or run out of memory on. Requests beyond the limit
+    /// are sent as responses arrive. A limit of zero is treated as one.
+    pub fn with_max_inflight(self, max_inflight: usize) -> Self {
+        Self {
+            max_inflight: max_inflight.max(1),
+            ..self
+        }
     }
 
     /// Fetches the complete `FileDescriptorSet` containing the definition for the given symbol.
@@ -89,7 +216,8 @@ where
     /// 1. It asks the server for the file defining `service_name`.
     /// 2. It parses the response and identifies any imported files (dependencies).
     /// 3. It recursively requests those dependencies if they haven't been fetched yet.
-    /// 4. It aggregates all fetched files into a single `FileDescriptorSet`.
+    /// 4. It aggregates all fetched files into a single `FileDescriptorSet`, sorted topologically
+    ///    (every file comes after its dependencies, ties are broken by file name).
     ///
     /// This ensures that the returned set is self-contained and can be used to build a
     /// `prost_reflect::DescriptorPool`.
@@ -106,20 +234,49 @@ where
         &mut self,
         symbol: &str,
     ) -> Result<FileDescriptorSet, ReflectionResolveError> {
-        // Initialize Stream
-        let (tx, rx) = mpsc::channel(100);
+        self.file_descriptor_set(MessageRequest::FileContainingSymbol(symbol.to_string()))
+            .await
+    }
 
-        let mut response_stream = self
-            .client
-            .server_reflection_info(ReceiverStream::new(rx))
+    /// Fetches the complete `FileDescriptorSet` of the file with the given name.
+    ///
+    /// This works like [`Self::file_descriptor_set_by_symbol`], but seeds the lookup with the
+    /// name of a proto file, which is useful when no symbol of the file is known.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - The name of the file, as known by the server (e.g., `my/package/service.proto`).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(FileDescriptorSet)` - A set containing the file and all its transitive dependencies.
+    /// * `Err(ReflectionResolveError)` - If the file is not found, the server doesn't support reflection, or a protocol error occurs.
+    pub async fn file_descriptor_set_by_filename(
+        &mut self,
+        filename: &str,
+    ) -> Result<FileDescriptorSet, ReflectionResolveError> {
+        self.file_descriptor_set(MessageRequest::FileByFilename(filename.to_string()))
+            .await
+    }
+
+    async fn file_descriptor_set(
+        &mut self,
+        message_request: MessageRequest,
+    ) -> Result<FileDescriptorSet, ReflectionResolveError> {
+        // Initialize Stream
+        let buffer_size = self.buffer_size;
+        let (mut response_stream, tx) = self
+            .open_stream(|| {
+                let (tx, rx) = mpsc::channel(buffer_size);
+                (ReceiverStream::new(rx), tx)
+            })
             .await
-            .map_err(ReflectionResolveError::ServerStreamInitFailed)?
-            .into_inner();
+            .map_err(ReflectionResolveError::ServerStreamInitFailed)?;
 
         // Send Initial Request
         let req = ServerReflectionRequest {
-            host: EMPTY_HOST.to_string(),
-            message_request: Some(MessageRequest::FileContainingSymbol(symbol.to_string())),
+            host: self.host.clone(),
+            message_request: Some(message_request),
         };
 
         tx.send(req)
@@ -127,11 +284,14 @@ where
             .map_err(|_| ReflectionResolveError::SendFailed)?;
 
         // Fetch all transitive dependencies
-        let file_map = collect_descriptors(&mut response_stream, tx).await?;
+        let file_map =
+            collect_descriptors(&mut response_stream, tx, &self.host, self.max_inflight).await?;
 
-        // Build Registry directly
+        tracing::debug!("Resolved {} files via reflection", file_map.len());
+
+        // Build the set in a deterministic order, with dependencies before dependents
         let fd_set = FileDescriptorSet {
-            file: file_map.into_values().collect(),
+            file: sort_topologically(file_map),
         };
 
         Ok(fd_set)
@@ -147,16 +307,14 @@ where
     /// * `Err(ReflectionResolveError)` - If the server doesn't support reflection or a protocol error occurs.
     pub async fn list_services(&mut self) -> Result<Vec<String>, ReflectionResolveError> {
         let req = ServerReflectionRequest {
-            host: EMPTY_HOST.to_string(),
+            host: self.host.clone(),
             message_request: Some(MessageRequest::ListServices(String::new())),
         };
 
-        let mut response_stream = self
-            .client
-            .server_reflection_info(once(async { req }))
+        let (mut response_stream, ()) = self
+            .open_stream(|| (once(ready(req.clone())), ()))
             .await
-            .map_err(ReflectionResolveError::ServerStreamInitFailed)?
-            .into_inner();
+            .map_err(ReflectionResolveError::ServerStreamInitFailed)?;
 
         let response = response_stream
             .message()
@@ -181,104 +339,226 @@ where
             )),
         }
     }
+
+    // Opens the reflection stream with the requests built by `requests`, which is called again
+    // to retry on v1alpha while the version is being discovered.
+    async fn open_stream<R, T>(
+        &mut self,
+        mut requests: impl FnMut() -> (R, T),
+    ) -> Result<(Streaming<ServerReflectionResponse>, T), tonic::Status>
+    where
+        R: Stream<Item = ServerReflectionRequest> + Send + 'static,
+    {
+        let (stream, handle) = requests();
+
+        match self.server_reflection_info(stream).await {
+            Err(status) if status.code() == Code::Unimplemented && self.discover_version => {
+                tracing::debug!("Reflection v1 is not implemented, trying v1alpha");
+                self.version = ReflectionVersion::V1Alpha;
+
+                let (stream, handle) = requests();
+                match self.server_reflection_info(stream).await {
+                    Ok(response) => {
+                        self.discover_version = false;
+                        Ok((response, handle))
+                    }
+                    // Neither is implemented, report the error of the standard version
+                    Err(_) => {
+                        self.version = ReflectionVersion::V1;
+                        Err(status)
+                    }
+                }
+            }
+            result => {
+                let response = result?;
+                self.discover_version = false;
+                Ok((response, handle))
+            }
+        }
+    }
+
+    // Opens the bidirectional reflection stream, on the path of the configured version
+    async fn server_reflection_info(
+        &mut self,
+        requests: impl Stream<Item = ServerReflectionRequest> + Send + 'static,
+    ) -> Result<Streaming<ServerReflectionResponse>, tonic::Status> {
+        self.client
+            .ready()
+            .await
+            .map_err(|e| tonic::Status::unknown(format!("Service was not ready: {}", e.into())))?;
+
+        let mut request = tonic::Request::new(requests);
+        *request.metadata_mut() = self.metadata.clone();
+
+        let response = self
+            .client
+            .streaming(request, self.version.path(), ProstCodec::default())
+            .await?;
+
+        Ok(response.into_inner())
+    }
 }
 
 async fn collect_descriptors(
     response_stream: &mut Streaming<ServerReflectionResponse>,
     request_channel: mpsc::Sender<ServerReflectionRequest>,
+    host: &str,
+    max_inflight: usize,
 ) -> Result<HashMap<String, FileDescriptorProto>, ReflectionResolveError> {
     let mut inflight = 1;
     let mut collected_files = HashMap::new();
     let mut requested = HashSet::new();
+    // Dependency requests that didn't fit in the channel or under the in-flight limit yet
+    let mut pending = VecDeque::new();
+
+    while inflight > 0 || !pending.is_empty() {
+        // With nothing in flight the server has taken every request, so there is room
+        if inflight == 0
+            && let Some(req) = pending.pop_front()
+        {
+            request_channel
+                .send(req)
+                .await
+                .map_err(|_| ReflectionResolveError::SendFailed)?;
+            inflight += 1;
+        }
+
+        // Waiting for room otherwise could deadlock with a server that only takes the next
+        // request once its previous response has been read
+        while inflight < max_inflight
+            && let Some(req) = pending.pop_front()
+        {
+            match request_channel.try_send(req) {
+                Ok(()) => inflight += 1,
+                Err(TrySendError::Full(req)) => {
+                    pending.push_front(req);
+                    break;
+                }
+                Err(TrySendError::Closed(_)) => return Err(ReflectionResolveError::SendFailed),
+            }
+        }
 
-    while inflight > 0 {
         let response = response_stream
             .message()
             .await
             .map_err(ReflectionResolveError::ServerStreamFailure)?
             .ok_or(ReflectionResolveError::StreamClosed)?;
 
+        // Some servers send empty frames (e.g. as keepalives), which don't answer any request
+        let Some(message_response) = response.message_response else {
+            tracing::trace!("Skipping an empty reflection response");
+            continue;
+        };
+
         inflight -= 1;
 
-        match response.message_response {
-            Some(MessageResponse::FileDescriptorResponse(res)) => {
-                let sent_count = process_descriptor_batch(
+        match message_response {
+            MessageResponse::FileDescriptorResponse(res) => {
+                process_descriptor_batch(
                     res.file_descriptor_proto,
                     &mut collected_files,
                     &mut requested,
-                    &request_channel,
-                )
-                .await?;
-
-                inflight += sent_count;
+                    &mut pending,
+                    host,
+                )?;
             }
-            Some(MessageResponse::ErrorResponse(e)) => {
+            MessageResponse::ErrorResponse(e) => {
                 return Err(ReflectionResolveError::ServerError {
                     message: e.error_message,
                     code: e.error_code,
                 });
             }
-            Some(other) => {
+            other => {
                 return Err(ReflectionResolveError::UnexpectedResponseType(format!(
                     "{:?}",
                     other
                 )));
             }
-            None => {
-                return Err(ReflectionResolveError::UnexpectedResponseType(
-                    "Empty Message".into(),
-                ));
-            }
         }
     }
 
     Ok(collected_files)
 }
 
-async fn process_descriptor_batch(
+fn process_descriptor_batch(
     raw_protos: Vec<Vec<u8>>,
     collected_files: &mut HashMap<String, FileDescriptorProto>,
     requested: &mut HashSet<String>,
-    tx: &mpsc::Sender<ServerReflectionRequest>,
-) -> Result<usize, ReflectionResolveError> {
-    let mut sent_count = 0;
+    pending: &mut VecDeque<ServerReflectionRequest>,
+    host: &str,
+) -> Result<(), ReflectionResolveError> {
+    let mut new_files = Vec::new();
 
+    // Collect the whole batch first: a file may import another file of the same batch
+    // (or itself), which must not be requested again.
     for raw in raw_protos {
         let fd = FileDescriptorProto::decode(raw.as_ref())?;
 
         if let Some(name) = &fd.name
             && !collected_files.contains_key(name)
         {
-            sent_count += queue_dependencies(&fd, collected_files, requested, tx).await?;
-
+            new_files.push(name.clone());
             collected_files.insert(name.clone(), fd);
         }
     }
 
-    Ok(sent_count)
+    for name in new_files {
+        let fd = &collected_files[&name];
+        queue_dependencies(fd, collected_files, requested, pending, host);
+    }
+
+    Ok(())
 }
 
-async fn queue_dependencies(
+fn queue_dependencies(
     fd: &FileDescriptorProto,
     collected_files: &HashMap<String, FileDescriptorProto>,
     requested: &mut HashSet<String>,
-    tx: &mpsc::Sender<ServerReflectionRequest>,
-) -> Result<usize, ReflectionResolveError> {
-    let mut count = 0;
-
+    pending: &mut VecDeque<ServerReflectionRequest>,
+    host: &str,
+) {
     for dep in &fd.dependency {
         if !collected_files.contains_key(dep) && requested.insert(dep.clone()) {
-            let req = ServerReflectionRequest {
-                host: EMPTY_HOST.to_string(),
+            tracing::trace!("Requesting dependency '{dep}' of '{}'", fd.name());
+
+            pending.push_back(ServerReflectionRequest {
+                host: host.to_string(),
                 message_request: Some(MessageRequest::FileByFilename(dep.clone())),
-            };
+            });
+        }
+    }
+}
 
-            tx.send(req)
-                .await
-                .map_err(|_| ReflectionResolveError::SendFailed)?;
-            count += 1;
+/// Orders the collected files so that every file comes after its dependencies.
+///
+/// The order is deterministic: files are visited by name, and dependencies in the order they are imported.
+/// Dependencies that were not collected are ignored, and import cycles are broken at the first revisit.
+fn sort_topologically(mut files: HashMap<String, FileDescriptorProto>) -> Vec<FileDescriptorProto> {
+    fn visit(
+        name: &str,
+        files: &mut HashMap<String, FileDescriptorProto>,
+        sorted: &mut Vec<FileDescriptorProto>,
+    ) {
+        // Removing the file marks it as visited
+        let Some(file) = files.remove(name) else {
+            return;
+        };
+
+        for dep in &file.dependency {
+            visit(dep, files, sorted);
         }
+
+        sorted.push(file);
+    }
+
+    let mut names: Vec<_> = files.keys().cloned().collect();
+    names.sort();
+
+    let mut sorted = Vec::with_capacity(files.len());
+
+    for name in names {
+        visit(&name, &mut files, &mut sorted);
     }
 
-    Ok(count)
+    sorted
 }
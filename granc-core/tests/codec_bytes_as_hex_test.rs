@@ -0,0 +1,72 @@
+use granc_core::grpc::codec::bytes_as_hex;
+use granc_core::prost_reflect::{DescriptorPool, MessageDescriptor};
+use granc_test_support::compiler;
+use serde_json::json;
+
+const BLOBS_PROTO: &str = r#"
+syntax = "proto3";
+package blobs;
+
+import "google/protobuf/wrappers.proto";
+
+message Inner {
+    bytes data = 1;
+}
+
+message Blobs {
+    bytes raw_data = 1;
+    repeated bytes chunks = 2;
+    map<string, bytes> by_key = 3;
+    Inner inner = 4;
+    repeated Inner inners = 5;
+    google.protobuf.BytesValue wrapped = 6;
+    string label = 7;
+}
+"#;
+
+fn blobs_descriptor() -> MessageDescriptor {
+    let files = compiler::compile_protos(&[("blobs.proto", BLOBS_PROTO)]);
+
+    DescriptorPool::from_file_descriptor_set(files)
+        .expect("Failed to build descriptor pool")
+        .get_message_by_name("blobs.Blobs")
+        .expect("Message not found")
+}
+
+#[test]
+fn test_bytes_fields_are_rewritten_as_hex() {
+    // "AAEC/w==" is [0x00, 0x01, 0x02, 0xff]
+    let mut value = json!({
+        "rawData": "AAEC/w==",
+        "chunks": ["AAEC/w==", "aGk="],
+        "byKey": { "a": "aGk=" },
+        "inner": { "data": "aGk=" },
+        "inners": [{ "data": "AAEC/w==" }],
+        "wrapped": "aGk=",
+        "label": "aGk="
+    });
+
+    bytes_as_hex(&blobs_descriptor(), &mut value);
+
+    assert_eq!(
+        value,
+        json!({
+            "rawData": "000102ff",
+            "chunks": ["000102ff", "6869"],
+            "byKey": { "a": "6869" },
+            "inner": { "data": "6869" },
+            "inners": [{ "data": "000102ff" }],
+            "wrapped": "6869",
+            "label": "aGk="
+        })
+    );
+}
+
+#[test]
+fn test_fields_named_as_declared_are_rewritten() {
+    let mut value = json!({ "raw_data": "aGk=", "unknown": "aGk=" });
+
+    bytes_as_hex(&blobs_descriptor(), &mut value);
+
+    assert_eq!(value, json!({ "raw_data": "6869", "unknown": "aGk=" }));
+}
@@ -0,0 +1,49 @@
+use granc_core::client::DynamicRequest;
+use granc_core::tokio_util::sync::CancellationToken;
+use std::time::Duration;
+
+#[test]
+fn test_builder_defaults() {
+    let request = DynamicRequest::builder("echo.EchoService", "UnaryEcho").build();
+
+    assert_eq!(request.service, "echo.EchoService");
+    assert_eq!(request.method, "UnaryEcho");
+    assert_eq!(request.body, serde_json::json!({}));
+    assert!(request.headers.is_empty());
+    assert_eq!(request.timeout, None);
+    assert!(request.cancellation.is_none());
+    assert!(!request.strict);
+    assert_eq!(request.send_interval, None);
+    assert_eq!(request.max_messages, None);
+}
+
+#[test]
+fn test_builder_sets_every_field() {
+    let request = DynamicRequest::builder("echo.EchoService", "ClientStreamingEcho")
+        .body(serde_json::json!([{ "message": "a" }]))
+        .header("x-first", "1")
+        .headers_from_iter([("x-second", "2"), ("x-third", "3")])
+        .header("x-fourth", String::from("4"))
+        .timeout(Duration::from_secs(5))
+        .cancellation(CancellationToken::new())
+        .strict(true)
+        .send_interval(Duration::from_millis(100))
+        .max_messages(10)
+        .build();
+
+    assert_eq!(request.body, serde_json::json!([{ "message": "a" }]));
+    assert_eq!(
+        request.headers,
+        vec![
+            ("x-first".to_string(), "1".to_string()),
+            ("x-second".to_string(), "2".to_string()),
+            ("x-third".to_string(), "3".to_string()),
+            ("x-fourth".to_string(), "4".to_string()),
+        ]
+    );
+    assert_eq!(request.timeout, Some(Duration::from_secs(5)));
+    assert!(request.cancellation.is_some());
+    assert!(request.strict);
+    assert_eq!(request.send_interval, Some(Duration::from_millis(100)));
+    assert_eq!(request.max_messages, Some(10));
+}
@@ -0,0 +1,58 @@
+use futures_util::Stream;
+use granc_test_support::echo_service::EchoService;
+use granc_test_support::echo_service::pb::{EchoRequest, EchoResponse};
+use std::pin::Pin;
+use tonic::{Request, Response, Status, Streaming};
+
+/// An echo service whose streams send two messages and then fail.
+#[derive(Debug)]
+pub struct FailingEchoServiceImpl;
+
+type EchoStream = Pin<Box<dyn Stream<Item = Result<EchoResponse, Status>> + Send>>;
+
+fn failing_stream(message: String) -> EchoStream {
+    let items = vec![
+        Ok(EchoResponse {
+            message: format!("{message} - seq 0"),
+        }),
+        Ok(EchoResponse {
+            message: format!("{message} - seq 1"),
+        }),
+        Err(Status::aborted("stream broke")),
+    ];
+    Box::pin(futures_util::stream::iter(items))
+}
+
+#[tonic::async_trait]
+impl EchoService for FailingEchoServiceImpl {
+    type BidirectionalEchoStream = EchoStream;
+    type ServerStreamingEchoStream = EchoStream;
+
+    async fn unary_echo(
+        &self,
+        _request: Request<EchoRequest>,
+    ) -> Result<Response<EchoResponse>, Status> {
+        Err(Status::unimplemented("not needed"))
+    }
+
+    async fn server_streaming_echo(
+        &self,
+        request: Request<EchoRequest>,
+    ) -> Result<Response<Self::ServerStreamingEchoStream>, Status> {
+        Ok(Response::new(failing_stream(request.into_inner().message)))
+    }
+
+    async fn client_streaming_echo(
+        &self,
+        _request: Request<Streaming<EchoRequest>>,
+    ) -> Result<Response<EchoResponse>, Status> {
+        Err(Status::unimplemented("not needed"))
+    }
+
+    async fn bidirectional_echo(
+        &self,
+        _request: Request<Streaming<EchoRequest>>,
+    ) -> Result<Response<Self::BidirectionalEchoStream>, Status> {
+        Ok(Response::new(failing_stream("echo".to_string())))
+    }
+}
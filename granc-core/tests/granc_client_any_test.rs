@@ -0,0 +1,136 @@
+use granc_core::client::{DynamicRequest, DynamicResponse, GrancClient};
+use granc_test_support::compiler;
+use raw_echo_service::RawEchoService;
+use serde_json::json;
+
+mod raw_echo_service;
+
+const ANY_PROTO: &str = r#"
+syntax = "proto3";
+package wrapped;
+
+import "google/protobuf/any.proto";
+
+service Echo {
+    rpc Echo(Envelope) returns (Envelope);
+    // Sends the raw fields of any `Any`, so clients can send types they don't know about
+    rpc Opaque(RawEnvelope) returns (Envelope);
+}
+
+message Payload {
+    string name = 1;
+    int32 count = 2;
+}
+
+message Envelope {
+    google.protobuf.Any payload = 1;
+    repeated google.protobuf.Any items = 2;
+    map<string, google.protobuf.Any> by_key = 3;
+}
+
+message RawAny {
+    string type_url = 1;
+    bytes value = 2;
+}
+
+message RawEnvelope {
+    RawAny payload = 1;
+    repeated RawAny items = 2;
+    map<string, RawAny> by_key = 3;
+}
+"#;
+
+async fn call(method: &str, body: serde_json::Value) -> serde_json::Value {
+    let files = compiler::compile_protos(&[("wrapped.proto", ANY_PROTO)]);
+    let bytes = granc_core::prost::Message::encode_to_vec(&files);
+
+    let mut client = GrancClient::from(RawEchoService)
+        .with_file_descriptor(bytes)
+        .expect("Failed to load file descriptor set");
+
+    let req = DynamicRequest {
+        service: "wrapped.Echo".to_string(),
+        method: method.to_string(),
+        body,
+        headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
+    };
+
+    match client.dynamic(req).await.unwrap() {
+        DynamicResponse::Unary(_, Ok(value)) => value,
+        other => panic!("Expected a successful unary response, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_any_is_expanded_when_type_is_in_pool() {
+    let body = json!({
+        "payload": {
+            "@type": "type.googleapis.com/wrapped.Payload",
+            "name": "granc",
+            "count": 2
+        }
+    });
+
+    let value = call("Echo", body.clone()).await;
+
+    assert_eq!(value, body);
+}
+
+#[tokio::test]
+async fn test_unresolved_any_is_kept_opaque() {
+    // `CgFh` is `wrapped.Payload { name: "a" }`
+    let body = json!({
+        "payload": { "typeUrl": "type.googleapis.com/missing.Type", "value": "AQID" },
+        "items": [
+            { "typeUrl": "type.googleapis.com/missing.Type", "value": "AQID" },
+            { "typeUrl": "type.googleapis.com/wrapped.Payload", "value": "CgFh" },
+            { "typeUrl": "type.googleapis.com/missing.Other", "value": "BAU=" }
+        ],
+        "byKey": {
+            "k": { "typeUrl": "type.googleapis.com/missing.Type", "value": "AQID" }
+        }
+    });
+
+    let value = call("Opaque", body).await;
+
+    let opaque = json!({ "@type": "type.googleapis.com/missing.Type", "value": "AQID" });
+    assert_eq!(
+        value,
+        json!({
+            "payload": opaque,
+            "items": [
+                opaque,
+                { "@type": "type.googleapis.com/wrapped.Payload", "name": "a" },
+                { "@type": "type.googleapis.com/missing.Other", "value": "BAU=" }
+            ],
+            "byKey": { "k": opaque }
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_list_of_only_unresolved_any_is_kept() {
+    let body = json!({
+        "items": [
+            { "typeUrl": "type.googleapis.com/missing.Type", "value": "AQID" },
+            { "typeUrl": "type.googleapis.com/missing.Type", "value": "BAU=" }
+        ]
+    });
+
+    let value = call("Opaque", body).await;
+
+    assert_eq!(
+        value,
+        json!({
+            "items": [
+                { "@type": "type.googleapis.com/missing.Type", "value": "AQID" },
+                { "@type": "type.googleapis.com/missing.Type", "value": "BAU=" }
+            ]
+        })
+    );
+}
@@ -0,0 +1,96 @@
+use echo_service_impl::EchoServiceImpl;
+use futures_util::StreamExt;
+use granc_core::client::{GrancClient, online, online_without_reflection};
+use granc_test_support::echo_service::{EchoServiceServer, FILE_DESCRIPTOR_SET};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::service::Routes;
+
+mod echo_service_impl;
+
+fn setup_routes() -> Routes {
+    let reflection_service = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .build_v1()
+        .unwrap();
+
+    Routes::new(reflection_service).add_service(EchoServiceServer::new(EchoServiceImpl))
+}
+
+#[tokio::test]
+async fn test_responses_arrive_while_sending() {
+    let mut client = GrancClient::from(setup_routes());
+    let (tx, rx) = mpsc::channel(1);
+
+    let responses = client
+        .bidirectional_stream(
+            "echo.EchoService",
+            "BidirectionalEcho",
+            ReceiverStream::new(rx),
+            vec![],
+            None,
+            None,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    let mut responses = std::pin::pin!(responses);
+
+    // Each response is received before the next message is even sent
+    for message in ["ping", "pong"] {
+        tx.send(serde_json::json!({ "message": message }))
+            .await
+            .unwrap();
+
+        let response = responses.next().await.unwrap().unwrap();
+        assert_eq!(response["message"], format!("echo: {message}"));
+    }
+
+    // Closing the input ends the call
+    drop(tx);
+    assert!(responses.next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_only_bidirectional_methods_can_be_streamed() {
+    let mut client = GrancClient::from(setup_routes())
+        .with_file_descriptor(FILE_DESCRIPTOR_SET.to_vec())
+        .unwrap();
+
+    let result = client
+        .bidirectional_stream(
+            "echo.EchoService",
+            "UnaryEcho",
+            futures_util::stream::empty(),
+            vec![],
+            None,
+            None,
+        )
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(online_without_reflection::DynamicCallError::InvalidInput(msg))
+            if msg == "'echo.EchoService.UnaryEcho' is not a bidirectional streaming method"
+    ));
+
+    let mut client = GrancClient::from(setup_routes());
+
+    let result = client
+        .bidirectional_stream(
+            "echo.EchoService",
+            "Missing",
+            futures_util::stream::empty(),
+            vec![],
+            None,
+            None,
+        )
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(online::DynamicCallError::DynamicCallError(
+            online_without_reflection::DynamicCallError::MethodNotFound(_)
+        ))
+    ));
+}
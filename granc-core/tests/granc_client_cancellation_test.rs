@@ -0,0 +1,156 @@
+use granc_core::client::{DynamicRequest, DynamicResponse, GrancClient, OnlineWithoutReflection};
+use granc_core::tokio_util::sync::CancellationToken;
+use granc_test_support::echo_service::{EchoServiceServer, FILE_DESCRIPTOR_SET};
+use slow_echo_service_impl::SlowEchoServiceImpl;
+use std::time::Duration;
+
+mod slow_echo_service_impl;
+
+fn setup_client() -> GrancClient<OnlineWithoutReflection<EchoServiceServer<SlowEchoServiceImpl>>> {
+    GrancClient::from(EchoServiceServer::new(SlowEchoServiceImpl))
+        .with_file_descriptor(FILE_DESCRIPTOR_SET.to_vec())
+        .expect("Failed to load file descriptor set")
+}
+
+// Cancels the token after a short delay, while the stream is stalled
+fn cancel_later(token: &CancellationToken) {
+    let token = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        token.cancel();
+    });
+}
+
+#[tokio::test]
+async fn test_cancel_server_streaming() {
+    let mut client = setup_client();
+    let token = CancellationToken::new();
+
+    let req = DynamicRequest {
+        service: "echo.EchoService".to_string(),
+        method: "ServerStreamingEcho".to_string(),
+        body: serde_json::json!({ "message": "first" }),
+        headers: vec![],
+        // The server requires a deadline; it is long enough to never be hit
+        timeout: Some(Duration::from_secs(60)),
+        cancellation: Some(token.clone()),
+        strict: false,
+        send_interval: None,
+        max_messages: None,
+    };
+
+    cancel_later(&token);
+    let res = client.dynamic(req).await.unwrap();
+
+    match res {
+        DynamicResponse::Streaming(_, Ok(stream)) => {
+            // The stream ends cleanly, keeping what was received before cancelling
+            assert_eq!(stream.len(), 1);
+            assert_eq!(stream[0].as_ref().unwrap()["message"], "first");
+        }
+        _ => panic!("Expected Streaming response"),
+    }
+}
+
+#[tokio::test]
+async fn test_cancel_bidirectional_streaming() {
+    let mut client = setup_client();
+    let token = CancellationToken::new();
+
+    let req = DynamicRequest {
+        service: "echo.EchoService".to_string(),
+        method: "BidirectionalEcho".to_string(),
+        body: serde_json::json!([{ "message": "A" }]),
+        headers: vec![],
+        timeout: None,
+        cancellation: Some(token.clone()),
+        strict: false,
+        send_interval: None,
+        max_messages: None,
+    };
+
+    cancel_later(&token);
+    let res = client.dynamic(req).await.unwrap();
+
+    match res {
+        DynamicResponse::Streaming(_, Ok(stream)) => {
+            assert_eq!(stream.len(), 1);
+            assert!(stream[0].is_ok());
+        }
+        _ => panic!("Expected Streaming response"),
+    }
+}
+
+#[tokio::test]
+async fn test_cancel_unary() {
+    let mut client = setup_client();
+    let token = CancellationToken::new();
+
+    // The server takes a minute to answer
+    let req = DynamicRequest::builder("echo.EchoService", "UnaryEcho")
+        .body(serde_json::json!({ "message": "hi" }))
+        .cancellation(token.clone())
+        .build();
+
+    cancel_later(&token);
+    let res = tokio::time::timeout(Duration::from_secs(5), client.dynamic(req))
+        .await
+        .expect("The call must end once cancelled")
+        .unwrap();
+
+    match res {
+        DynamicResponse::Unary(_, Err(status)) => {
+            assert_eq!(status.code(), tonic::Code::Cancelled)
+        }
+        _ => panic!("Expected a Cancelled status"),
+    }
+}
+
+#[tokio::test]
+async fn test_cancelled_before_call_returns_empty_stream() {
+    let mut client = setup_client();
+    let token = CancellationToken::new();
+    token.cancel();
+
+    let req = DynamicRequest {
+        service: "echo.EchoService".to_string(),
+        method: "BidirectionalEcho".to_string(),
+        body: serde_json::json!([{ "message": "A" }]),
+        headers: vec![],
+        timeout: None,
+        cancellation: Some(token),
+        strict: false,
+        send_interval: None,
+        max_messages: None,
+    };
+
+    let res = client.dynamic(req).await.unwrap();
+
+    assert!(matches!(res, DynamicResponse::Streaming(_, Ok(stream)) if stream.is_empty()));
+}
+
+#[tokio::test]
+async fn test_max_messages_ends_a_stalled_stream() {
+    let mut client = setup_client();
+
+    let req = DynamicRequest::builder("echo.EchoService", "ServerStreamingEcho")
+        .body(serde_json::json!({ "message": "first" }))
+        // The server requires a deadline; it is long enough to never be hit
+        .timeout(Duration::from_secs(60))
+        .max_messages(1)
+        .build();
+
+    // The stream stalls after its first message, so only the limit can end it
+    let res = tokio::time::timeout(Duration::from_secs(5), client.dynamic(req))
+        .await
+        .expect("The stream must end after max_messages")
+        .unwrap();
+
+    match res {
+        DynamicResponse::Streaming(_, Ok(stream)) => {
+            assert_eq!(stream.len(), 1);
+            assert_eq!(stream[0].as_ref().unwrap()["message"], "first");
+        }
+        _ => panic!("Expected Streaming response"),
+    }
+}
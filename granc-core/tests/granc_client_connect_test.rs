@@ -0,0 +1,184 @@
+use granc_core::client::{GrancClient, online::ClientConnectError};
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_connect_reports_connection_refused() {
+    // Bind and release a port, so nothing is listening on it
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let uri = format!("http://{addr}");
+
+    let result = GrancClient::connect_with_timeout(&uri, Duration::from_millis(200)).await;
+
+    assert!(matches!(
+        result,
+        Err(ClientConnectError::ConnectionRefused(failed, _)) if failed == uri
+    ));
+}
+
+#[tokio::test]
+async fn test_connect_reports_unknown_host() {
+    // The `.invalid` top-level domain is reserved and never resolves
+    let uri = "http://granc.invalid:50051";
+
+    let result = GrancClient::connect_with_timeout(uri, Duration::from_secs(5)).await;
+
+    assert!(matches!(
+        result,
+        Err(ClientConnectError::DnsResolution(failed, _)) if failed == uri
+    ));
+}
+
+#[tokio::test]
+async fn test_connect_resolves_names_and_ip_literals() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    for uri in [
+        format!("http://localhost:{port}"),
+        format!("http://[::1]:{port}"),
+    ] {
+        let result = GrancClient::connect_with_timeout(&uri, Duration::from_millis(200)).await;
+
+        // Nothing is listening, but the host itself is valid
+        assert!(
+            !matches!(result, Ok(_) | Err(ClientConnectError::DnsResolution(..))),
+            "{uri}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_connect_with_timeout_rejects_invalid_uri() {
+    let result = GrancClient::connect_with_timeout("not a uri", Duration::from_secs(1)).await;
+
+    assert!(matches!(result, Err(ClientConnectError::InvalidUri(..))));
+}
+
+// A reflection server that only answers requests whose host is the address it listens on
+mod host_checking_reflection {
+    use futures_util::StreamExt;
+    use futures_util::stream::BoxStream;
+    use tonic::{Request, Response, Status, Streaming};
+    use tonic_reflection::pb::v1::{
+        ErrorResponse, ListServiceResponse, ServerReflectionRequest, ServerReflectionResponse,
+        ServiceResponse, server_reflection_response::MessageResponse,
+        server_reflection_server::ServerReflection,
+    };
+
+    pub struct HostChecking(pub String);
+
+    #[tonic::async_trait]
+    impl ServerReflection for HostChecking {
+        type ServerReflectionInfoStream =
+            BoxStream<'static, Result<ServerReflectionResponse, Status>>;
+
+        async fn server_reflection_info(
+            &self,
+            request: Request<Streaming<ServerReflectionRequest>>,
+        ) -> Result<Response<Self::ServerReflectionInfoStream>, Status> {
+            let expected = self.0.clone();
+
+            let responses = request.into_inner().map(move |request| {
+                let request = request?;
+                let message_response = if request.host == expected {
+                    MessageResponse::ListServicesResponse(ListServiceResponse {
+                        service: vec![ServiceResponse {
+                            name: "echo.EchoService".to_string(),
+                        }],
+                    })
+                } else {
+                    MessageResponse::ErrorResponse(ErrorResponse {
+                        error_code: tonic::Code::InvalidArgument as i32,
+                        error_message: format!("unexpected host '{}'", request.host),
+                    })
+                };
+
+                Ok(ServerReflectionResponse {
+                    valid_host: request.host.clone(),
+                    original_request: Some(request),
+                    message_response: Some(message_response),
+                })
+            });
+
+            Ok(Response::new(responses.boxed()))
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_reflection_host_is_the_authority_of_the_uri() {
+    use host_checking_reflection::HostChecking;
+    use tonic_reflection::pb::v1::server_reflection_server::ServerReflectionServer;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let incoming = futures_util::stream::unfold(listener, |listener| async move {
+        let result = listener.accept().await.map(|(stream, _)| stream);
+        Some((result, listener))
+    });
+
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(ServerReflectionServer::new(HostChecking(addr.to_string())))
+            .serve_with_incoming(incoming),
+    );
+
+    let mut client = GrancClient::connect(&format!("http://{addr}"))
+        .await
+        .unwrap();
+    assert_eq!(client.list_services().await.unwrap(), ["echo.EchoService"]);
+
+    // The host is kept when the connection is replaced or wrapped
+    let mut client = client.map_service(|channel| channel);
+    assert_eq!(client.list_services().await.unwrap(), ["echo.EchoService"]);
+
+    let mut client = client.with_interceptor(Ok);
+    assert_eq!(client.list_services().await.unwrap(), ["echo.EchoService"]);
+
+    // The default can still be overridden
+    let mut client = client.map_reflection_client(|reflection| reflection.with_host("other"));
+    assert!(client.list_services().await.is_err());
+}
+
+#[tokio::test]
+async fn test_http1_server_is_reported_as_not_http2() {
+    use granc_core::{client::online::is_not_http2, reflection::client::ReflectionResolveError};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // A REST server, answering every connection with an HTTP/1.1 response
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut buffer = [0; 1024];
+            let _ = stream.read(&mut buffer).await;
+            let _ = stream
+                .write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n")
+                .await;
+        }
+    });
+
+    // The connection is only checked by the first request
+    let mut client = GrancClient::connect(&format!("http://{addr}"))
+        .await
+        .unwrap();
+
+    match client.list_services().await {
+        Err(ReflectionResolveError::ServerStreamInitFailed(status)) => {
+            assert!(is_not_http2(&status))
+        }
+        other => panic!("Expected the stream to fail, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_other_errors_are_not_reported_as_not_http2() {
+    let err = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset");
+    assert!(!granc_core::client::online::is_not_http2(&err));
+}
@@ -0,0 +1,98 @@
+use granc_core::client::{DynamicRequest, DynamicResponse, GrancClient, OnlineWithoutReflection};
+use granc_test_support::echo_service::{EchoServiceServer, FILE_DESCRIPTOR_SET};
+use slow_echo_service_impl::SlowEchoServiceImpl;
+use std::time::Duration;
+use tonic::Code;
+
+mod slow_echo_service_impl;
+
+fn setup_client() -> GrancClient<OnlineWithoutReflection<EchoServiceServer<SlowEchoServiceImpl>>> {
+    GrancClient::from(EchoServiceServer::new(SlowEchoServiceImpl))
+        .with_file_descriptor(FILE_DESCRIPTOR_SET.to_vec())
+        .expect("Failed to load file descriptor set")
+}
+
+#[tokio::test]
+async fn test_unary_deadline_exceeded() {
+    let mut client = setup_client();
+
+    let req = DynamicRequest {
+        service: "echo.EchoService".to_string(),
+        method: "UnaryEcho".to_string(),
+        body: serde_json::json!({ "message": "slow" }),
+        headers: vec![],
+        timeout: Some(Duration::from_millis(50)),
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
+    };
+
+    let res = client.dynamic(req).await.unwrap();
+
+    assert!(matches!(
+        res,
+        DynamicResponse::Unary(_, Err(status)) if status.code() == Code::DeadlineExceeded
+    ));
+}
+
+#[tokio::test]
+async fn test_server_streaming_deadline_is_a_stream_error() {
+    let mut client = setup_client();
+
+    let req = DynamicRequest {
+        service: "echo.EchoService".to_string(),
+        method: "ServerStreamingEcho".to_string(),
+        body: serde_json::json!({ "message": "first" }),
+        headers: vec![],
+        timeout: Some(Duration::from_millis(50)),
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
+    };
+
+    let res = client.dynamic(req).await.unwrap();
+
+    match res {
+        DynamicResponse::Streaming(_, Ok(stream)) => {
+            assert_eq!(stream.len(), 2);
+            assert_eq!(stream[0].as_ref().unwrap()["message"], "first");
+            assert!(matches!(
+                &stream[1],
+                Err(status) if status.code() == Code::DeadlineExceeded
+            ));
+        }
+        _ => panic!("Expected Streaming response"),
+    }
+}
+
+#[tokio::test]
+async fn test_bidirectional_deadline_is_a_stream_error() {
+    let mut client = setup_client();
+
+    let req = DynamicRequest {
+        service: "echo.EchoService".to_string(),
+        method: "BidirectionalEcho".to_string(),
+        body: serde_json::json!([{ "message": "A" }]),
+        headers: vec![],
+        timeout: Some(Duration::from_millis(50)),
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
+    };
+
+    let res = client.dynamic(req).await.unwrap();
+
+    match res {
+        DynamicResponse::Streaming(_, Ok(stream)) => {
+            assert_eq!(stream.len(), 2);
+            assert!(matches!(
+                &stream[1],
+                Err(status) if status.code() == Code::DeadlineExceeded
+            ));
+        }
+        _ => panic!("Expected Streaming response"),
+    }
+}
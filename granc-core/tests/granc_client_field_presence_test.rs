@@ -0,0 +1,70 @@
+use granc_core::client::{DynamicRequest, DynamicResponse, GrancClient};
+use granc_test_support::compiler;
+use raw_echo_service::RawEchoService;
+
+mod raw_echo_service;
+
+const PRESENCE_PROTO: &str = r#"
+syntax = "proto3";
+package presence;
+
+service Echo {
+    rpc Echo(Counter) returns (Counter);
+}
+
+message Counter {
+    optional int32 explicit = 1;
+    int32 implicit = 2;
+}
+"#;
+
+async fn echo(body: serde_json::Value) -> serde_json::Value {
+    let files = compiler::compile_protos(&[("presence.proto", PRESENCE_PROTO)]);
+    let bytes = granc_core::prost::Message::encode_to_vec(&files);
+
+    let mut client = GrancClient::from(RawEchoService)
+        .with_file_descriptor(bytes)
+        .expect("Failed to load file descriptor set");
+
+    let req = DynamicRequest {
+        service: "presence.Echo".to_string(),
+        method: "Echo".to_string(),
+        body,
+        headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
+    };
+
+    match client.dynamic(req).await.unwrap() {
+        DynamicResponse::Unary(_, Ok(value)) => value,
+        other => panic!("Expected a successful unary response, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_optional_field_set_to_default_is_serialized() {
+    let value = echo(serde_json::json!({ "explicit": 0 })).await;
+
+    assert_eq!(value, serde_json::json!({ "explicit": 0 }));
+}
+
+#[tokio::test]
+async fn test_absent_optional_field_is_omitted() {
+    let value = echo(serde_json::json!({})).await;
+
+    assert_eq!(value, serde_json::json!({}));
+}
+
+#[tokio::test]
+async fn test_implicit_presence_field_with_default_is_omitted() {
+    let value = echo(serde_json::json!({ "implicit": 0 })).await;
+
+    assert_eq!(value, serde_json::json!({}));
+
+    let value = echo(serde_json::json!({ "explicit": 5, "implicit": 7 })).await;
+
+    assert_eq!(value, serde_json::json!({ "explicit": 5, "implicit": 7 }));
+}
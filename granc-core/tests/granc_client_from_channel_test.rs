@@ -0,0 +1,64 @@
+use echo_service_impl::EchoServiceImpl;
+use granc_core::client::{DynamicRequest, DynamicResponse, GrancClient};
+use granc_test_support::echo_service::{EchoServiceServer, FILE_DESCRIPTOR_SET};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::net::TcpListener;
+use tonic::transport::{Channel, Server};
+
+mod echo_service_impl;
+
+// Serves the echo service over TCP, counting the connections accepted
+async fn serve(connections: Arc<AtomicUsize>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let incoming = futures_util::stream::unfold(listener, move |listener| {
+        let connections = connections.clone();
+        async move {
+            let result = listener.accept().await.map(|(stream, _)| stream);
+            connections.fetch_add(1, Ordering::SeqCst);
+            Some((result, listener))
+        }
+    });
+
+    let reflection_service = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .build_v1()
+        .unwrap();
+
+    tokio::spawn(
+        Server::builder()
+            .add_service(reflection_service)
+            .add_service(EchoServiceServer::new(EchoServiceImpl))
+            .serve_with_incoming(incoming),
+    );
+
+    format!("http://{addr}")
+}
+
+fn echo(message: &str) -> DynamicRequest {
+    DynamicRequest::builder("echo.EchoService", "UnaryEcho")
+        .body(serde_json::json!({ "message": message }))
+        .build()
+}
+
+#[tokio::test]
+async fn test_clients_share_the_channel() {
+    let connections = Arc::new(AtomicUsize::new(0));
+    let uri = serve(connections.clone()).await;
+
+    let channel = Channel::from_shared(uri).unwrap().connect().await.unwrap();
+
+    let mut first = GrancClient::from_channel(channel.clone());
+    let mut second = GrancClient::from_channel(channel);
+
+    for (client, message) in [(&mut first, "one"), (&mut second, "two")] {
+        match client.dynamic(echo(message)).await.unwrap() {
+            DynamicResponse::Unary(_, Ok(value)) => assert_eq!(value["message"], message),
+            other => panic!("Expected a successful unary response, got {other:?}"),
+        }
+    }
+
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+}
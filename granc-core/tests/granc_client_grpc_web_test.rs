@@ -0,0 +1,119 @@
+use granc_core::client::{DynamicRequest, DynamicResponse, GrancClient};
+use granc_core::tonic::Code;
+use granc_test_support::echo_service::FILE_DESCRIPTOR_SET;
+use http_body_util::{BodyExt, Full};
+use hyper::{
+    Request, Response, body::Bytes, body::Incoming, server::conn::http2, service::service_fn,
+};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use std::convert::Infallible;
+use tokio::net::TcpListener;
+
+// A gRPC-Web server that answers with the request message (twice for server streaming), then the
+// trailers frame. A "missing" message is answered with a `NotFound` status.
+async fn handle(request: Request<Incoming>) -> Result<Response<Full<Bytes>>, Infallible> {
+    let content_type = request.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert_eq!(content_type, "application/grpc-web");
+
+    let path = request.uri().path().to_string();
+    let message = request.into_body().collect().await.unwrap().to_bytes();
+
+    let missing = message.ends_with(b"missing");
+    let (messages, trailers) = match path.as_str() {
+        _ if missing => (
+            Vec::new(),
+            "grpc-status: 5\r\ngrpc-message: nothing here\r\n",
+        ),
+        "/echo.EchoService/UnaryEcho" => (message.to_vec(), "grpc-status: 0\r\n"),
+        "/echo.EchoService/ServerStreamingEcho" => {
+            ([message.clone(), message].concat(), "grpc-status: 0\r\n")
+        }
+        _ => (Vec::new(), "grpc-status: 12\r\n"),
+    };
+
+    let mut body = messages;
+    body.push(0x80);
+    body.extend_from_slice(&(trailers.len() as u32).to_be_bytes());
+    body.extend_from_slice(trailers.as_bytes());
+
+    Ok(Response::builder()
+        .header("content-type", "application/grpc-web+proto")
+        .body(Full::new(Bytes::from(body)))
+        .unwrap())
+}
+
+async fn serve() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(
+                http2::Builder::new(TokioExecutor::new())
+                    .serve_connection(TokioIo::new(stream), service_fn(handle)),
+            );
+        }
+    });
+
+    format!("http://{addr}")
+}
+
+async fn call(method: &str, message: &str) -> DynamicResponse {
+    let uri = serve().await;
+
+    let mut client = GrancClient::grpc_web(&uri, None)
+        .await
+        .unwrap()
+        .with_file_descriptor(FILE_DESCRIPTOR_SET.to_vec())
+        .unwrap();
+
+    let request = DynamicRequest {
+        service: "echo.EchoService".to_string(),
+        method: method.to_string(),
+        body: serde_json::json!({ "message": message }),
+        headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
+    };
+
+    client.dynamic(request).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_grpc_web_unary() {
+    match call("UnaryEcho", "hello").await {
+        DynamicResponse::Unary(_, Ok(value)) => {
+            assert_eq!(value, serde_json::json!({ "message": "hello" }))
+        }
+        other => panic!("Expected a successful unary response, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_grpc_web_server_streaming() {
+    match call("ServerStreamingEcho", "hello").await {
+        DynamicResponse::Streaming(_, Ok(values)) => {
+            let values: Vec<_> = values.into_iter().map(Result::unwrap).collect();
+            assert_eq!(values, vec![serde_json::json!({ "message": "hello" }); 2]);
+        }
+        other => panic!("Expected a successful stream, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_grpc_web_status_from_trailers() {
+    match call("UnaryEcho", "missing").await {
+        DynamicResponse::Unary(_, Err(status)) => {
+            assert_eq!(status.code(), Code::NotFound);
+            assert_eq!(status.message(), "nothing here");
+        }
+        other => panic!("Expected an error status, got {other:?}"),
+    }
+}
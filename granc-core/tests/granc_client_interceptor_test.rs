@@ -0,0 +1,99 @@
+use echo_service_impl::EchoServiceImpl;
+use granc_core::client::{DynamicRequest, DynamicResponse, GrancClient, Online};
+use granc_core::reflection::client::ReflectionResolveError;
+use granc_test_support::echo_service::{EchoServiceServer, FILE_DESCRIPTOR_SET};
+use tonic::service::Routes;
+use tonic::{Code, Request, Status};
+
+mod echo_service_impl;
+
+const TRACE_ID_HEADER: &str = "x-trace-id";
+
+// Server-side interceptor: rejects every echo request without a trace id
+fn require_trace_id(request: Request<()>) -> Result<Request<()>, Status> {
+    match request.metadata().get(TRACE_ID_HEADER) {
+        Some(_) => Ok(request),
+        None => Err(Status::unauthenticated("missing trace id")),
+    }
+}
+
+// Client-side interceptor: adds a trace id to every request
+fn add_trace_id(mut request: Request<()>) -> Result<Request<()>, Status> {
+    request
+        .metadata_mut()
+        .insert(TRACE_ID_HEADER, "trace-1234".parse().unwrap());
+    Ok(request)
+}
+
+fn setup_client() -> GrancClient<Online<Routes>> {
+    let reflection_service = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .build_v1()
+        .unwrap();
+
+    let echo_service = EchoServiceServer::with_interceptor(EchoServiceImpl, require_trace_id);
+
+    let service = Routes::new(reflection_service).add_service(echo_service);
+
+    GrancClient::from(service)
+}
+
+fn unary_request() -> DynamicRequest {
+    DynamicRequest {
+        service: "echo.EchoService".to_string(),
+        method: "UnaryEcho".to_string(),
+        body: serde_json::json!({ "message": "intercepted" }),
+        headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
+    }
+}
+
+#[tokio::test]
+async fn test_interceptor_adds_trace_id() {
+    let mut client = setup_client().with_interceptor(add_trace_id);
+
+    let res = client.dynamic(unary_request()).await.unwrap();
+
+    assert!(matches!(res, DynamicResponse::Unary(_, Ok(val)) if val["message"] == "intercepted"));
+}
+
+#[tokio::test]
+async fn test_request_without_interceptor_is_rejected() {
+    let mut client = setup_client();
+
+    let res = client.dynamic(unary_request()).await.unwrap();
+
+    assert!(matches!(
+        res,
+        DynamicResponse::Unary(_, Err(status)) if status.code() == Code::Unauthenticated
+    ));
+}
+
+#[tokio::test]
+async fn test_interceptor_applies_to_reflection_and_can_cancel() {
+    let mut client = setup_client()
+        .with_interceptor(|_: Request<()>| Err(Status::permission_denied("no reflection")));
+
+    let result = client.list_services().await;
+
+    assert!(matches!(
+        result,
+        Err(ReflectionResolveError::ServerStreamInitFailed(status)) if status.code() == Code::PermissionDenied
+    ));
+}
+
+#[tokio::test]
+async fn test_interceptor_with_file_descriptor() {
+    let mut client = setup_client()
+        .with_interceptor(add_trace_id)
+        .with_file_descriptor(FILE_DESCRIPTOR_SET.to_vec())
+        .expect("Failed to load file descriptor set");
+
+    let res = client.dynamic(unary_request()).await.unwrap();
+
+    assert!(matches!(res, DynamicResponse::Unary(_, Ok(val)) if val["message"] == "intercepted"));
+}
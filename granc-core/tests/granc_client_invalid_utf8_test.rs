@@ -0,0 +1,102 @@
+use granc_core::client::{DynamicRequest, DynamicResponse, GrancClient};
+use granc_test_support::compiler;
+use raw_echo_service::RawEchoService;
+use serde_json::json;
+
+mod raw_echo_service;
+
+// `Blob` and `Text` share their field numbers, so the echoed bytes of a `Blob` are read as the
+// strings of a `Text`
+const TEXT_PROTO: &str = r#"
+syntax = "proto3";
+package text;
+
+service Echo {
+    rpc Echo(Blob) returns (Text);
+}
+
+message InnerBlob {
+    bytes data = 1;
+}
+
+message Blob {
+    bytes data = 1;
+    repeated bytes items = 2;
+    InnerBlob inner = 3;
+    map<string, bytes> by_key = 4;
+    int32 count = 5;
+}
+
+message InnerText {
+    string data = 1;
+}
+
+message Text {
+    string data = 1;
+    repeated string items = 2;
+    InnerText inner = 3;
+    map<string, string> by_key = 4;
+    int32 count = 5;
+}
+"#;
+
+async fn call(body: serde_json::Value) -> DynamicResponse {
+    let files = compiler::compile_protos(&[("text.proto", TEXT_PROTO)]);
+    let bytes = granc_core::prost::Message::encode_to_vec(&files);
+
+    let mut client = GrancClient::from(RawEchoService)
+        .with_file_descriptor(bytes)
+        .expect("Failed to load file descriptor set");
+
+    let req = DynamicRequest {
+        service: "text.Echo".to_string(),
+        method: "Echo".to_string(),
+        body,
+        headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
+    };
+
+    client.dynamic(req).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_invalid_utf8_is_replaced() {
+    // `aP9i` is `h\xFFb`, `/w==` is `\xFF`
+    let body = json!({
+        "data": "aP9i",
+        "items": ["b2s=", "/w=="],
+        "inner": { "data": "/w==" },
+        "byKey": { "k": "/w==" },
+        "count": 3
+    });
+
+    match call(body).await {
+        DynamicResponse::Unary(_, Ok(value)) => assert_eq!(
+            value,
+            json!({
+                "data": "h\u{FFFD}b",
+                "items": ["ok", "\u{FFFD}"],
+                "inner": { "data": "\u{FFFD}" },
+                "byKey": { "k": "\u{FFFD}" },
+                "count": 3
+            })
+        ),
+        other => panic!("Expected a successful unary response, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_control_characters_are_kept() {
+    // `G1szMW0=` is `\x1b[31m`, a terminal color escape
+    match call(json!({ "data": "G1szMW0=" })).await {
+        DynamicResponse::Unary(_, Ok(value)) => {
+            assert_eq!(value, json!({ "data": "\u{1b}[31m" }));
+            assert_eq!(value.to_string(), r#"{"data":"\u001b[31m"}"#);
+        }
+        other => panic!("Expected a successful unary response, got {other:?}"),
+    }
+}
@@ -0,0 +1,111 @@
+use granc_core::client::{
+    DynamicRequest, DynamicResponse, GrancClient, online_without_reflection::DynamicCallError,
+};
+use granc_test_support::compiler;
+use raw_echo_service::RawEchoService;
+
+mod raw_echo_service;
+
+const USERS_PROTO: &str = r#"
+syntax = "proto3";
+package users;
+
+service Echo {
+    rpc Echo(User) returns (User);
+}
+
+message User {
+    string user_id = 1;
+    string email = 2 [json_name = "mail"];
+}
+"#;
+
+async fn echo(body: serde_json::Value, strict: bool) -> Result<DynamicResponse, DynamicCallError> {
+    let files = compiler::compile_protos(&[("users.proto", USERS_PROTO)]);
+    let bytes = granc_core::prost::Message::encode_to_vec(&files);
+
+    let mut client = GrancClient::from(RawEchoService)
+        .with_file_descriptor(bytes)
+        .expect("Failed to load file descriptor set");
+
+    let req = DynamicRequest {
+        service: "users.Echo".to_string(),
+        method: "Echo".to_string(),
+        body,
+        headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict,
+        send_interval: None,
+        max_messages: None,
+    };
+
+    client.dynamic(req).await
+}
+
+async fn echo_ok(body: serde_json::Value) -> serde_json::Value {
+    match echo(body, false).await.unwrap() {
+        DynamicResponse::Unary(_, Ok(value)) => value,
+        other => panic!("Expected a successful unary response, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_accepts_proto_field_names() {
+    let value = echo_ok(serde_json::json!({ "user_id": "1", "email": "a@b.c" })).await;
+
+    assert_eq!(value, serde_json::json!({ "userId": "1", "mail": "a@b.c" }));
+}
+
+#[tokio::test]
+async fn test_accepts_json_field_names() {
+    let value = echo_ok(serde_json::json!({ "userId": "1", "mail": "a@b.c" })).await;
+
+    assert_eq!(value, serde_json::json!({ "userId": "1", "mail": "a@b.c" }));
+}
+
+#[tokio::test]
+async fn test_rejects_unknown_fields() {
+    match echo(serde_json::json!({ "userID": "1" }), false)
+        .await
+        .unwrap()
+    {
+        DynamicResponse::Unary(_, Err(status)) => {
+            assert!(
+                status
+                    .message()
+                    .contains("JSON structure does not match Protobuf schema")
+            );
+        }
+        other => panic!("Expected an encoding error, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_strict_mode_lists_valid_fields() {
+    let err = echo(serde_json::json!({ "userID": "1" }), true)
+        .await
+        .unwrap_err();
+
+    match err {
+        DynamicCallError::InvalidInput(message) => assert_eq!(
+            message,
+            "Unknown field 'userID' in message 'users.User', expected one of: user_id (userId), email (mail)"
+        ),
+        other => panic!("Expected an invalid input error, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_strict_mode_accepts_known_fields() {
+    let response = echo(serde_json::json!({ "user_id": "1", "mail": "a@b.c" }), true)
+        .await
+        .unwrap();
+
+    match response {
+        DynamicResponse::Unary(_, Ok(value)) => {
+            assert_eq!(value, serde_json::json!({ "userId": "1", "mail": "a@b.c" }))
+        }
+        other => panic!("Expected a successful unary response, got {other:?}"),
+    }
+}
@@ -1,4 +1,4 @@
-use granc_core::client::{Descriptor, GrancClient};
+use granc_core::client::{Descriptor, DescriptorSetError, GrancClient};
 use granc_test_support::echo_service::FILE_DESCRIPTOR_SET;
 
 #[test]
@@ -47,3 +47,74 @@ fn test_offline_creation_error() {
     let result = GrancClient::offline(vec![0, 1, 2, 3]);
     assert!(result.is_err());
 }
+
+#[test]
+fn test_offline_get_file_by_name() {
+    let client = GrancClient::offline(FILE_DESCRIPTOR_SET.to_vec())
+        .expect("Failed to load file descriptor set");
+
+    let file = client
+        .get_file_by_name("echo.proto")
+        .expect("File not found");
+
+    assert_eq!(file.package_name(), "echo");
+    assert_eq!(
+        file.services()
+            .map(|s| s.name().to_string())
+            .collect::<Vec<_>>(),
+        ["EchoService"]
+    );
+
+    assert!(client.get_file_by_name("missing.proto").is_none());
+}
+
+/// `echo.proto`'s descriptor set, compressed with `gzip -9`.
+const GZIPPED_FILE_DESCRIPTOR_SET: &[u8] = include_bytes!("fixtures/echo.bin.gz");
+
+#[test]
+fn test_offline_gzipped_descriptor_set() {
+    let client = GrancClient::offline(GZIPPED_FILE_DESCRIPTOR_SET.to_vec())
+        .expect("Failed to load gzipped file descriptor set");
+
+    assert_eq!(client.list_services(), vec!["echo.EchoService"]);
+}
+
+#[test]
+fn test_offline_corrupt_gzip_error() {
+    let mut corrupt = GZIPPED_FILE_DESCRIPTOR_SET.to_vec();
+    corrupt[40] ^= 0xff;
+
+    let result = GrancClient::offline(corrupt);
+    assert!(matches!(result, Err(DescriptorSetError::Gzip(_))));
+
+    let truncated = GZIPPED_FILE_DESCRIPTOR_SET[..100].to_vec();
+
+    let err = GrancClient::offline(truncated).unwrap_err();
+    assert!(
+        err.to_string().contains("incomplete deflate stream"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_offline_invalid_set_reports_the_file() {
+    use granc_core::prost::Message;
+    use prost_types::{FileDescriptorProto, FileDescriptorSet};
+
+    let fd_set = FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("broken.proto".to_string()),
+            package: Some("broken".to_string()),
+            dependency: vec!["missing.proto".to_string()],
+            ..Default::default()
+        }],
+    };
+
+    let err = GrancClient::offline(fd_set.encode_to_vec()).unwrap_err();
+
+    assert!(matches!(err, DescriptorSetError::Decode(_)));
+    assert_eq!(
+        err.to_string(),
+        "imported file 'missing.proto' has not been added (in 'broken.proto')"
+    );
+}
@@ -42,10 +42,15 @@ async fn test_reflection_unary_success() {
         method: "UnaryEcho".to_string(),
         body: serde_json::json!({ "message": "reflection" }),
         headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
     };
 
     let res = client.dynamic(req).await.unwrap();
-    assert!(matches!(res, DynamicResponse::Unary(Ok(val)) if val["message"] == "reflection"));
+    assert!(matches!(res, DynamicResponse::Unary(_, Ok(val)) if val["message"] == "reflection"));
 }
 
 #[tokio::test]
@@ -57,12 +62,17 @@ async fn test_reflection_server_streaming_success() {
         method: "ServerStreamingEcho".to_string(),
         body: serde_json::json!({ "message": "stream" }),
         headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
     };
 
     let res = client.dynamic(req).await.unwrap();
 
     match res {
-        DynamicResponse::Streaming(Ok(stream)) => {
+        DynamicResponse::Streaming(_, Ok(stream)) => {
             assert_eq!(stream.len(), 3);
             assert_eq!(stream[0].as_ref().unwrap()["message"], "stream - seq 0");
             assert_eq!(stream[1].as_ref().unwrap()["message"], "stream - seq 1");
@@ -81,10 +91,15 @@ async fn test_reflection_client_streaming_success() {
         method: "ClientStreamingEcho".to_string(),
         body: serde_json::json!([{ "message": "A" }, { "message": "B" }]),
         headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
     };
 
     let res = client.dynamic(req).await.unwrap();
-    assert!(matches!(res, DynamicResponse::Unary(Ok(val)) if val["message"] == "AB"));
+    assert!(matches!(res, DynamicResponse::Unary(_, Ok(val)) if val["message"] == "AB"));
 }
 
 #[tokio::test]
@@ -98,6 +113,11 @@ async fn test_reflection_service_not_found() {
         method: "UnaryEcho".to_string(),
         body: serde_json::json!({}),
         headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
     };
 
     let result = client.dynamic(req).await;
@@ -121,6 +141,11 @@ async fn test_reflection_method_not_found() {
         method: "GhostMethod".to_string(),
         body: serde_json::json!({}),
         headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
     };
 
     let result = client.dynamic(req).await;
@@ -143,6 +168,11 @@ async fn test_reflection_invalid_input_structure() {
         method: "ClientStreamingEcho".to_string(),
         body: serde_json::json!({ "msg": "not array" }),
         headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
     };
 
     let result = client.dynamic(req).await;
@@ -166,12 +196,85 @@ async fn test_reflection_schema_mismatch() {
         method: "UnaryEcho".to_string(),
         body: serde_json::json!({ "wrong_field": "val" }),
         headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
     };
 
     let result = client.dynamic(req).await;
 
     assert!(matches!(
         result,
-        Ok(DynamicResponse::Unary(Err(status))) if status.code() == Code::Internal
+        Ok(DynamicResponse::Unary(_, Err(status))) if status.code() == Code::Internal
+    ));
+}
+
+#[tokio::test]
+async fn test_reflection_file_descriptor_set_can_be_used_offline() {
+    let mut client = setup_client().await;
+
+    let fd_set = client
+        .file_descriptor_set(&["echo.EchoService".to_string()])
+        .await
+        .unwrap();
+
+    let bytes = granc_core::prost::Message::encode_to_vec(&fd_set);
+    let offline = GrancClient::offline(bytes).expect("The set must be self-contained");
+
+    assert_eq!(offline.list_services(), ["echo.EchoService"]);
+}
+
+#[tokio::test]
+async fn test_reflection_file_descriptor_set_merges_symbols() {
+    let mut client = setup_client().await;
+    let services = client.list_services().await.unwrap();
+
+    let fd_set = client.file_descriptor_set(&services).await.unwrap();
+
+    let mut names: Vec<_> = fd_set.file.iter().map(|f| f.name()).collect();
+    let total = names.len();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), total, "Each file must appear only once");
+
+    let bytes = granc_core::prost::Message::encode_to_vec(&fd_set);
+    let mut offline = GrancClient::offline(bytes).unwrap().list_services();
+    offline.sort();
+    assert_eq!(
+        offline.as_slice(),
+        ["echo.EchoService", "grpc.reflection.v1.ServerReflection"]
+    );
+}
+
+#[tokio::test]
+async fn test_reflection_file_descriptor_set_not_found() {
+    let mut client = setup_client().await;
+
+    let result = client
+        .file_descriptor_set(&["echo.NonExistent".to_string()])
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(online::GetDescriptorError::NotFound(name)) if name == "echo.NonExistent"
+    ));
+}
+
+#[tokio::test]
+async fn test_reflection_get_file_by_name() {
+    let mut client = setup_client().await;
+
+    let file = client.get_file_by_name("echo.proto").await.unwrap();
+
+    assert_eq!(file.package_name(), "echo");
+    assert!(file.messages().any(|m| m.name() == "EchoRequest"));
+
+    let result = client.get_file_by_name("missing.proto").await;
+
+    assert!(matches!(
+        result,
+        Err(online::GetDescriptorError::NotFound(name)) if name == "missing.proto"
     ));
 }
@@ -1,13 +1,23 @@
 use echo_service_impl::EchoServiceImpl;
-use granc_core::client::{
-    DynamicRequest, DynamicResponse, GrancClient, OnlineWithoutReflection,
-    online_without_reflection,
+use granc_core::{
+    client::{
+        DynamicRequest, DynamicResponse, GrancClient, MethodKind, OnlineWithoutReflection,
+        online_without_reflection,
+    },
+    prost_reflect::{DescriptorPool, DynamicMessage},
 };
 use granc_test_support::echo_service::{EchoServiceServer, FILE_DESCRIPTOR_SET};
 use tonic::Code;
 
 mod echo_service_impl;
 
+// Parses `text` as a message of the echo service, in protobuf text format
+fn text_message(name: &str, text: &str) -> DynamicMessage {
+    let pool = DescriptorPool::decode(FILE_DESCRIPTOR_SET).unwrap();
+    let desc = pool.get_message_by_name(name).unwrap();
+    DynamicMessage::parse_text_format(desc, text).unwrap()
+}
+
 fn setup_client() -> GrancClient<OnlineWithoutReflection<EchoServiceServer<EchoServiceImpl>>> {
     let service = EchoServiceServer::new(EchoServiceImpl);
     let client_reflection = GrancClient::from(service);
@@ -26,13 +36,18 @@ async fn test_dynamic_unary_success() {
         method: "UnaryEcho".to_string(),
         body: serde_json::json!({ "message": "hello" }),
         headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
     };
 
     let res = client.dynamic(req).await.unwrap();
 
     assert!(matches!(
         res,
-        DynamicResponse::Unary(Ok(val)) if val["message"] == "hello"
+        DynamicResponse::Unary(MethodKind::Unary, Ok(val)) if val["message"] == "hello"
     ));
 }
 
@@ -45,12 +60,17 @@ async fn test_dynamic_server_streaming_success() {
         method: "ServerStreamingEcho".to_string(),
         body: serde_json::json!({ "message": "stream" }),
         headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
     };
 
     let res = client.dynamic(req).await.unwrap();
 
     match res {
-        DynamicResponse::Streaming(Ok(stream)) => {
+        DynamicResponse::Streaming(MethodKind::ServerStreaming, Ok(stream)) => {
             assert_eq!(stream.len(), 3);
             assert_eq!(stream[0].as_ref().unwrap()["message"], "stream - seq 0");
             assert_eq!(stream[1].as_ref().unwrap()["message"], "stream - seq 1");
@@ -74,13 +94,18 @@ async fn test_dynamic_client_streaming_success() {
             { "message": "C" }
         ]),
         headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
     };
 
     let res = client.dynamic(req).await.unwrap();
 
     assert!(matches!(
         res,
-        DynamicResponse::Unary(Ok(val)) if val["message"] == "ABC"
+        DynamicResponse::Unary(MethodKind::ClientStreaming, Ok(val)) if val["message"] == "ABC"
     ));
 }
 
@@ -96,12 +121,17 @@ async fn test_dynamic_bidirectional_streaming_success() {
             { "message": "Pong" }
         ]),
         headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
     };
 
     let res = client.dynamic(req).await.unwrap();
 
     match res {
-        DynamicResponse::Streaming(Ok(stream)) => {
+        DynamicResponse::Streaming(MethodKind::Bidirectional, Ok(stream)) => {
             assert_eq!(stream.len(), 2);
             assert_eq!(stream[0].as_ref().unwrap()["message"], "echo: Ping");
             assert_eq!(stream[1].as_ref().unwrap()["message"], "echo: Pong");
@@ -119,6 +149,11 @@ async fn test_error_service_not_found() {
         method: "UnaryEcho".to_string(),
         body: serde_json::json!({}),
         headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
     };
 
     let result = client.dynamic(req).await;
@@ -138,6 +173,11 @@ async fn test_error_method_not_found() {
         method: "GhostMethod".to_string(),
         body: serde_json::json!({}),
         headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
     };
 
     let result = client.dynamic(req).await;
@@ -158,13 +198,98 @@ async fn test_error_invalid_input_structure() {
         method: "ClientStreamingEcho".to_string(),
         body: serde_json::json!({ "message": "I should be an array" }),
         headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
     };
 
     let result = client.dynamic(req).await;
 
     assert!(matches!(
         result,
-        Err(online_without_reflection::DynamicCallError::InvalidInput(_))
+        Err(online_without_reflection::DynamicCallError::InvalidInput(msg))
+            if msg == "'echo.EchoService.ClientStreamingEcho' expects a JSON array of messages, as it is client streaming, got an object"
+    ));
+}
+
+#[tokio::test]
+async fn test_error_array_body_for_unary_method() {
+    let mut client = setup_client();
+
+    let req = DynamicRequest {
+        service: "echo.EchoService".to_string(),
+        method: "UnaryEcho".to_string(),
+        body: serde_json::json!([{ "message": "I should be an object" }]),
+        headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
+    };
+
+    let result = client.dynamic(req).await;
+
+    assert!(matches!(
+        result,
+        Err(online_without_reflection::DynamicCallError::InvalidInput(msg))
+            if msg == "'echo.EchoService.UnaryEcho' expects a JSON object, as it is not client streaming, got an array"
+    ));
+}
+
+#[tokio::test]
+async fn test_dynamic_with_types_success() {
+    let mut client = setup_client();
+
+    let req = DynamicRequest {
+        service: "echo.EchoService".to_string(),
+        method: "UnaryEcho".to_string(),
+        body: serde_json::json!({ "message": "typed" }),
+        headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
+    };
+
+    let res = client
+        .dynamic_with_types(req, "echo.EchoRequest", "echo.EchoResponse")
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        res,
+        DynamicResponse::Unary(MethodKind::Unary, Ok(val)) if val["message"] == "typed"
+    ));
+}
+
+#[tokio::test]
+async fn test_error_dynamic_with_types_message_not_found() {
+    let mut client = setup_client();
+
+    let req = DynamicRequest {
+        service: "echo.EchoService".to_string(),
+        method: "UnaryEcho".to_string(),
+        body: serde_json::json!({ "message": "typed" }),
+        headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
+    };
+
+    let result = client
+        .dynamic_with_types(req, "echo.GhostRequest", "echo.EchoResponse")
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(online_without_reflection::DynamicCallError::MessageNotFound(name))
+            if name == "echo.GhostRequest"
     ));
 }
 
@@ -179,6 +304,11 @@ async fn test_error_schema_mismatch() {
         method: "UnaryEcho".to_string(),
         body: serde_json::json!({ "unknown_field": 123 }),
         headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
     };
 
     let result = client.dynamic(req).await;
@@ -187,8 +317,62 @@ async fn test_error_schema_mismatch() {
     // a successful Result<DynamicResponse> containing an Err(Status).
     assert!(matches!(
         result,
-        Ok(DynamicResponse::Unary(Err(status)))
+        Ok(DynamicResponse::Unary(_, Err(status)))
             if status.code() == Code::Internal
             && status.message().contains("JSON structure does not match Protobuf schema")
     ));
 }
+
+#[tokio::test]
+async fn test_dynamic_client_streaming_single_message() {
+    let mut client = setup_client();
+
+    // A message built beforehand is sent as it is, a single one is still a stream
+    let req = DynamicRequest::builder("echo.EchoService", "ClientStreamingEcho")
+        .build()
+        .with_body(vec![text_message("echo.EchoRequest", r#"message: "A""#)]);
+
+    let res = client.dynamic(req).await.unwrap();
+
+    assert!(matches!(
+        res,
+        DynamicResponse::Unary(MethodKind::ClientStreaming, Ok(val)) if val["message"] == "A"
+    ));
+}
+
+#[tokio::test]
+async fn test_error_several_messages_for_unary_method() {
+    let mut client = setup_client();
+
+    let req = DynamicRequest::builder("echo.EchoService", "UnaryEcho")
+        .build()
+        .with_body(vec![
+            text_message("echo.EchoRequest", r#"message: "A""#),
+            text_message("echo.EchoRequest", r#"message: "B""#),
+        ]);
+
+    let result = client.dynamic(req).await;
+
+    assert!(matches!(
+        result,
+        Err(online_without_reflection::DynamicCallError::InvalidInput(msg))
+            if msg == "'echo.EchoService.UnaryEcho' expects a single message, as it is not client streaming, got 2"
+    ));
+}
+
+#[tokio::test]
+async fn test_error_message_of_another_type() {
+    let mut client = setup_client();
+
+    let req = DynamicRequest::builder("echo.EchoService", "UnaryEcho")
+        .build()
+        .with_body(vec![text_message("echo.EchoResponse", r#"message: "A""#)]);
+
+    let result = client.dynamic(req).await;
+
+    assert!(matches!(
+        result,
+        Ok(DynamicResponse::Unary(_, Err(status)))
+            if status.message().contains("Expected a message of type 'echo.EchoRequest', got 'echo.EchoResponse'")
+    ));
+}
@@ -0,0 +1,73 @@
+use failing_echo_service_impl::FailingEchoServiceImpl;
+use granc_core::client::{DynamicRequest, DynamicResponse, GrancClient, OnlineWithoutReflection};
+use granc_test_support::echo_service::{EchoServiceServer, FILE_DESCRIPTOR_SET};
+use tonic::Code;
+
+mod failing_echo_service_impl;
+
+fn setup_client() -> GrancClient<OnlineWithoutReflection<EchoServiceServer<FailingEchoServiceImpl>>>
+{
+    GrancClient::from(EchoServiceServer::new(FailingEchoServiceImpl))
+        .with_file_descriptor(FILE_DESCRIPTOR_SET.to_vec())
+        .expect("Failed to load file descriptor set")
+}
+
+fn request(method: &str, body: serde_json::Value) -> DynamicRequest {
+    DynamicRequest {
+        service: "echo.EchoService".to_string(),
+        method: method.to_string(),
+        body,
+        headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
+    }
+}
+
+// The messages received before the error are returned, followed by the terminal status
+fn assert_partial_stream(res: DynamicResponse, prefix: &str) {
+    match res {
+        DynamicResponse::Streaming(_, Ok(stream)) => {
+            assert_eq!(stream.len(), 3);
+            assert_eq!(
+                stream[0].as_ref().unwrap()["message"],
+                format!("{prefix} - seq 0")
+            );
+            assert_eq!(
+                stream[1].as_ref().unwrap()["message"],
+                format!("{prefix} - seq 1")
+            );
+            assert!(matches!(
+                &stream[2],
+                Err(status) if status.code() == Code::Aborted && status.message() == "stream broke"
+            ));
+        }
+        other => panic!("Expected a partial stream, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_server_streaming_keeps_messages_received_before_an_error() {
+    let mut client = setup_client();
+
+    let req = request(
+        "ServerStreamingEcho",
+        serde_json::json!({ "message": "hi" }),
+    );
+
+    assert_partial_stream(client.dynamic(req).await.unwrap(), "hi");
+}
+
+#[tokio::test]
+async fn test_bidirectional_streaming_keeps_messages_received_before_an_error() {
+    let mut client = setup_client();
+
+    let req = request(
+        "BidirectionalEcho",
+        serde_json::json!([{ "message": "hi" }]),
+    );
+
+    assert_partial_stream(client.dynamic(req).await.unwrap(), "echo");
+}
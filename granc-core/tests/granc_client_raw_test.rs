@@ -0,0 +1,74 @@
+use echo_service_impl::EchoServiceImpl;
+use granc_core::client::GrancClient;
+use granc_test_support::echo_service::{
+    EchoServiceServer, FILE_DESCRIPTOR_SET,
+    pb::{EchoRequest, EchoResponse},
+};
+use prost::Message;
+use prost::bytes::Bytes;
+use tonic::Code;
+
+mod echo_service_impl;
+
+fn encoded_request(message: &str) -> Bytes {
+    EchoRequest {
+        message: message.to_string(),
+    }
+    .encode_to_vec()
+    .into()
+}
+
+#[tokio::test]
+async fn test_raw_unary_without_schema() {
+    // The server has no reflection service: raw calls don't need the schema
+    let mut client = GrancClient::from(EchoServiceServer::new(EchoServiceImpl));
+
+    let response = client
+        .raw(
+            "echo.EchoService",
+            "UnaryEcho",
+            encoded_request("raw"),
+            vec![],
+            None,
+        )
+        .await
+        .unwrap()
+        .expect("The call must succeed");
+
+    let response = EchoResponse::decode(response).unwrap();
+    assert_eq!(response.message, "raw");
+}
+
+#[tokio::test]
+async fn test_raw_unary_with_file_descriptor() {
+    let mut client = GrancClient::from(EchoServiceServer::new(EchoServiceImpl))
+        .with_file_descriptor(FILE_DESCRIPTOR_SET.to_vec())
+        .unwrap();
+
+    let response = client
+        .raw(
+            "echo.EchoService",
+            "UnaryEcho",
+            encoded_request("bytes"),
+            vec![],
+            None,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(response, encoded_request("bytes"));
+}
+
+#[tokio::test]
+async fn test_raw_unknown_method_returns_status() {
+    let mut client = GrancClient::from(EchoServiceServer::new(EchoServiceImpl));
+
+    let status = client
+        .raw("echo.EchoService", "Ghost", Bytes::new(), vec![], None)
+        .await
+        .unwrap()
+        .unwrap_err();
+
+    assert_eq!(status.code(), Code::Unimplemented);
+}
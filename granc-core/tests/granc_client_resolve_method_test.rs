@@ -0,0 +1,82 @@
+use echo_service_impl::EchoServiceImpl;
+use granc_core::client::{GrancClient, ResolveMethodError, online, parse_method_path};
+use granc_test_support::echo_service::{EchoServiceServer, FILE_DESCRIPTOR_SET};
+use tonic::service::Routes;
+
+mod echo_service_impl;
+
+#[test]
+fn test_parse_method_path() {
+    assert_eq!(
+        parse_method_path("echo.EchoService/UnaryEcho").unwrap(),
+        ("echo.EchoService", "UnaryEcho")
+    );
+    assert_eq!(
+        parse_method_path("/echo.EchoService/UnaryEcho").unwrap(),
+        ("echo.EchoService", "UnaryEcho")
+    );
+
+    assert!(matches!(
+        parse_method_path("echo.EchoService"),
+        Err(ResolveMethodError::InvalidPath(_))
+    ));
+    assert!(matches!(
+        parse_method_path("echo.EchoService/UnaryEcho/Extra"),
+        Err(ResolveMethodError::TooManySeparators(_))
+    ));
+    assert!(matches!(
+        parse_method_path("echo.EchoService/"),
+        Err(ResolveMethodError::EmptyName)
+    ));
+}
+
+#[test]
+fn test_offline_resolve_method() {
+    let client = GrancClient::offline(FILE_DESCRIPTOR_SET.to_vec())
+        .expect("Failed to load file descriptor set");
+
+    let method = client
+        .resolve_method("echo.EchoService/ServerStreamingEcho")
+        .unwrap();
+    assert_eq!(method.full_name(), "echo.EchoService.ServerStreamingEcho");
+    assert!(method.is_server_streaming());
+
+    assert!(matches!(
+        client.resolve_method("echo.GhostService/UnaryEcho"),
+        Err(ResolveMethodError::ServiceNotFound(name)) if name == "echo.GhostService"
+    ));
+    assert!(matches!(
+        client.resolve_method("echo.EchoService/GhostMethod"),
+        Err(ResolveMethodError::MethodNotFound(name)) if name == "GhostMethod"
+    ));
+}
+
+#[tokio::test]
+async fn test_reflection_resolve_method() {
+    let reflection_service = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .build_v1()
+        .unwrap();
+    let service =
+        Routes::new(reflection_service).add_service(EchoServiceServer::new(EchoServiceImpl));
+    let mut client = GrancClient::from(service);
+
+    let method = client
+        .resolve_method("/echo.EchoService/UnaryEcho")
+        .await
+        .unwrap();
+    assert_eq!(method.full_name(), "echo.EchoService.UnaryEcho");
+
+    assert!(matches!(
+        client.resolve_method("echo.EchoService/GhostMethod").await,
+        Err(online::GetDescriptorError::ResolveMethod(
+            ResolveMethodError::MethodNotFound(_)
+        ))
+    ));
+    assert!(matches!(
+        client.resolve_method("no-slash").await,
+        Err(online::GetDescriptorError::ResolveMethod(
+            ResolveMethodError::InvalidPath(_)
+        ))
+    ));
+}
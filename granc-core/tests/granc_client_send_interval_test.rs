@@ -0,0 +1,39 @@
+use echo_service_impl::EchoServiceImpl;
+use granc_core::client::{DynamicRequest, DynamicResponse, GrancClient};
+use granc_test_support::echo_service::{EchoServiceServer, FILE_DESCRIPTOR_SET};
+use std::time::{Duration, Instant};
+
+mod echo_service_impl;
+
+#[tokio::test]
+async fn test_client_stream_messages_are_spaced_out() {
+    let mut client = GrancClient::from(EchoServiceServer::new(EchoServiceImpl))
+        .with_file_descriptor(FILE_DESCRIPTOR_SET.to_vec())
+        .expect("Failed to load file descriptor set");
+
+    let req = DynamicRequest {
+        service: "echo.EchoService".to_string(),
+        method: "ClientStreamingEcho".to_string(),
+        body: serde_json::json!([
+            { "message": "A" },
+            { "message": "B" },
+            { "message": "C" }
+        ]),
+        headers: vec![],
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: Some(Duration::from_millis(50)),
+        max_messages: None,
+    };
+
+    let start = Instant::now();
+    let res = client.dynamic(req).await.unwrap();
+
+    // The first message is sent right away, each of the others after the interval
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert!(matches!(
+        res,
+        DynamicResponse::Unary(_, Ok(val)) if val["message"] == "ABC"
+    ));
+}
@@ -0,0 +1,120 @@
+use granc_core::grpc::client::GrpcClient;
+use granc_core::prost_reflect::{DescriptorPool, MethodDescriptor};
+use granc_test_support::echo_service::pb::{EchoRequest, EchoResponse};
+use granc_test_support::echo_service::{EchoService, EchoServiceServer, FILE_DESCRIPTOR_SET};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tonic::{Request, Response, Status, Streaming};
+
+type EchoStream = Pin<Box<dyn futures_util::Stream<Item = Result<EchoResponse, Status>> + Send>>;
+
+/// An echo service that waits as many milliseconds as the message says before answering,
+/// keeping track of the most calls it had in flight at once.
+#[derive(Debug, Default)]
+struct DelayedEchoService {
+    in_flight: AtomicUsize,
+    max_in_flight: Arc<AtomicUsize>,
+}
+
+#[tonic::async_trait]
+impl EchoService for DelayedEchoService {
+    type BidirectionalEchoStream = EchoStream;
+    type ServerStreamingEchoStream = EchoStream;
+
+    async fn unary_echo(
+        &self,
+        request: Request<EchoRequest>,
+    ) -> Result<Response<EchoResponse>, Status> {
+        let message = request.into_inner().message;
+        let delay = message
+            .parse()
+            .map_err(|_| Status::invalid_argument("not a delay"))?;
+
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+        Ok(Response::new(EchoResponse { message }))
+    }
+
+    async fn server_streaming_echo(
+        &self,
+        _request: Request<EchoRequest>,
+    ) -> Result<Response<Self::ServerStreamingEchoStream>, Status> {
+        Err(Status::unimplemented("not needed"))
+    }
+
+    async fn client_streaming_echo(
+        &self,
+        _request: Request<Streaming<EchoRequest>>,
+    ) -> Result<Response<EchoResponse>, Status> {
+        Err(Status::unimplemented("not needed"))
+    }
+
+    async fn bidirectional_echo(
+        &self,
+        _request: Request<Streaming<EchoRequest>>,
+    ) -> Result<Response<Self::BidirectionalEchoStream>, Status> {
+        Err(Status::unimplemented("not needed"))
+    }
+}
+
+fn unary_echo() -> MethodDescriptor {
+    DescriptorPool::decode(FILE_DESCRIPTOR_SET)
+        .expect("Failed to decode file descriptor set")
+        .get_service_by_name("echo.EchoService")
+        .expect("Service not found")
+        .methods()
+        .find(|method| method.name() == "UnaryEcho")
+        .expect("Method not found")
+}
+
+#[tokio::test]
+async fn test_unary_many_preserves_order_and_bounds_concurrency() {
+    let service = DelayedEchoService::default();
+    let max_in_flight = service.max_in_flight.clone();
+    let client = GrpcClient::new(EchoServiceServer::new(service));
+
+    // Later calls answer first
+    let payloads = ["40", "30", "20", "10", "0"]
+        .map(|delay| serde_json::json!({ "message": delay }))
+        .to_vec();
+
+    let results = client
+        .unary_many(unary_echo(), payloads, vec![], None, 2)
+        .await;
+
+    let messages: Vec<_> = results
+        .into_iter()
+        .map(|result| result.unwrap().unwrap()["message"].clone())
+        .collect();
+
+    assert_eq!(messages, ["40", "30", "20", "10", "0"]);
+    assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_unary_many_reports_each_status() {
+    let client = GrpcClient::new(EchoServiceServer::new(DelayedEchoService::default()));
+
+    let payloads = vec![
+        serde_json::json!({ "message": "0" }),
+        serde_json::json!({ "message": "not a number" }),
+    ];
+
+    let results = client
+        .unary_many(unary_echo(), payloads, vec![], None, 0)
+        .await;
+
+    assert_eq!(
+        results[0].as_ref().unwrap().as_ref().unwrap()["message"],
+        "0"
+    );
+    assert_eq!(
+        results[1].as_ref().unwrap().as_ref().unwrap_err().code(),
+        tonic::Code::InvalidArgument
+    );
+}
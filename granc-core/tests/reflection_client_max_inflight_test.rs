@@ -0,0 +1,139 @@
+use futures_util::{Stream, StreamExt};
+use granc_core::reflection::client::ReflectionClient;
+use prost::Message;
+use prost_types::FileDescriptorProto;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tonic::{Request, Response, Status, Streaming};
+use tonic_reflection::pb::v1::{
+    FileDescriptorResponse, ServerReflectionRequest, ServerReflectionResponse,
+    server_reflection_request::MessageRequest, server_reflection_response::MessageResponse,
+};
+use tonic_reflection::server::v1::{ServerReflection, ServerReflectionServer};
+
+const DEPENDENCIES: usize = 10;
+
+/// A reflection server taking every request as soon as it is sent, and answering each of them
+/// after a delay, while recording how many requests were awaiting a response at most.
+///
+/// `root.proto` (returned for any symbol) imports `dep0.proto` to `dep9.proto`, which import nothing.
+#[derive(Clone, Default)]
+struct SlowReflection {
+    outstanding: Arc<AtomicUsize>,
+    max_outstanding: Arc<AtomicUsize>,
+}
+
+type ResponseStream = Pin<Box<dyn Stream<Item = Result<ServerReflectionResponse, Status>> + Send>>;
+
+fn respond(request: ServerReflectionRequest) -> Result<ServerReflectionResponse, Status> {
+    let file = match &request.message_request {
+        Some(MessageRequest::FileContainingSymbol(_)) => FileDescriptorProto {
+            name: Some("root.proto".to_string()),
+            dependency: (0..DEPENDENCIES).map(|i| format!("dep{i}.proto")).collect(),
+            ..Default::default()
+        },
+        Some(MessageRequest::FileByFilename(name)) => FileDescriptorProto {
+            name: Some(name.clone()),
+            ..Default::default()
+        },
+        _ => return Err(Status::unimplemented("not needed")),
+    };
+
+    Ok(ServerReflectionResponse {
+        valid_host: String::new(),
+        original_request: Some(request),
+        message_response: Some(MessageResponse::FileDescriptorResponse(
+            FileDescriptorResponse {
+                file_descriptor_proto: vec![file.encode_to_vec()],
+            },
+        )),
+    })
+}
+
+#[tonic::async_trait]
+impl ServerReflection for SlowReflection {
+    type ServerReflectionInfoStream = ResponseStream;
+
+    async fn server_reflection_info(
+        &self,
+        request: Request<Streaming<ServerReflectionRequest>>,
+    ) -> Result<Response<Self::ServerReflectionInfoStream>, Status> {
+        let server = self.clone();
+        let mut requests = request.into_inner();
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            while let Some(Ok(request)) = requests.next().await {
+                let outstanding = server.outstanding.fetch_add(1, Ordering::SeqCst) + 1;
+                server
+                    .max_outstanding
+                    .fetch_max(outstanding, Ordering::SeqCst);
+
+                let (server, tx) = (server.clone(), tx.clone());
+                tokio::spawn(async move {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    server.outstanding.fetch_sub(1, Ordering::SeqCst);
+                    let _ = tx.send(respond(request));
+                });
+            }
+        });
+
+        Ok(Response::new(Box::pin(UnboundedReceiverStream::new(rx))))
+    }
+}
+
+async fn resolve(max_inflight: Option<usize>) -> (Vec<String>, usize) {
+    let server = SlowReflection::default();
+    let max_outstanding = server.max_outstanding.clone();
+
+    let mut client = ReflectionClient::new(ServerReflectionServer::new(server));
+    if let Some(max_inflight) = max_inflight {
+        client = client.with_max_inflight(max_inflight);
+    }
+
+    let fd_set = tokio::time::timeout(
+        Duration::from_secs(5),
+        client.file_descriptor_set_by_symbol("root.Symbol"),
+    )
+    .await
+    .expect("Resolution must not hang")
+    .expect("Failed to resolve the file descriptor set");
+
+    let mut names: Vec<_> = fd_set
+        .file
+        .into_iter()
+        .map(|f| f.name().to_string())
+        .collect();
+    names.sort();
+
+    (names, max_outstanding.load(Ordering::SeqCst))
+}
+
+#[tokio::test]
+async fn test_inflight_requests_are_capped() {
+    let (names, max_outstanding) = resolve(Some(3)).await;
+
+    assert_eq!(names.len(), DEPENDENCIES + 1);
+    assert!(names.contains(&"root.proto".to_string()));
+    assert!(max_outstanding <= 3, "{max_outstanding} requests in flight");
+}
+
+#[tokio::test]
+async fn test_dependencies_are_requested_at_once_under_the_default_cap() {
+    let (names, max_outstanding) = resolve(None).await;
+
+    assert_eq!(names.len(), DEPENDENCIES + 1);
+    assert_eq!(max_outstanding, DEPENDENCIES);
+}
+
+#[tokio::test]
+async fn test_zero_cap_is_treated_as_one() {
+    let (names, max_outstanding) = resolve(Some(0)).await;
+
+    assert_eq!(names.len(), DEPENDENCIES + 1);
+    assert_eq!(max_outstanding, 1);
+}
@@ -0,0 +1,65 @@
+use granc_core::client::{GrancClient, Online};
+use granc_core::grpc::client::metadata_from_headers;
+use granc_core::reflection::client::ReflectionResolveError;
+use granc_test_support::echo_service::FILE_DESCRIPTOR_SET;
+use tonic::service::{Routes, interceptor::InterceptedService};
+use tonic::{Code, Request, Status};
+
+// Server-side interceptor: rejects every reflection request without a token
+fn require_token(request: Request<()>) -> Result<Request<()>, Status> {
+    match request.metadata().get("authorization") {
+        Some(token) if token == "Bearer secret" => Ok(request),
+        _ => Err(Status::unauthenticated("missing token")),
+    }
+}
+
+fn setup_client() -> GrancClient<Online<Routes>> {
+    let reflection_service = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .build_v1()
+        .unwrap();
+
+    let service = Routes::new(InterceptedService::new(reflection_service, require_token));
+
+    GrancClient::from(service)
+}
+
+#[tokio::test]
+async fn test_reflection_sends_metadata() {
+    let metadata = metadata_from_headers(vec![(
+        "authorization".to_string(),
+        "Bearer secret".to_string(),
+    )])
+    .unwrap();
+
+    let mut client =
+        setup_client().map_reflection_client(|reflection| reflection.with_metadata(metadata));
+
+    let services = client.list_services().await.unwrap();
+    assert!(services.contains(&"echo.EchoService".to_string()));
+
+    let descriptor = client
+        .get_descriptor_by_symbol("echo.EchoService")
+        .await
+        .unwrap();
+    assert_eq!(descriptor.full_name(), "echo.EchoService");
+}
+
+#[tokio::test]
+async fn test_reflection_without_metadata_is_rejected() {
+    let mut client = setup_client();
+
+    let result = client.list_services().await;
+
+    assert!(matches!(
+        result,
+        Err(ReflectionResolveError::ServerStreamInitFailed(status)) if status.code() == Code::Unauthenticated
+    ));
+}
+
+#[test]
+fn test_invalid_header_is_reported() {
+    let result = metadata_from_headers(vec![("bad key".to_string(), "value".to_string())]);
+
+    assert!(result.is_err());
+}
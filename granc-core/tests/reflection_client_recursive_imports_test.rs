@@ -0,0 +1,267 @@
+use futures_util::{Stream, StreamExt};
+use granc_core::reflection::client::ReflectionClient;
+use prost::Message;
+use prost_types::{DescriptorProto, FileDescriptorProto, ServiceDescriptorProto};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tonic::{Request, Response, Status, Streaming};
+use tonic_reflection::pb::v1::{
+    FileDescriptorResponse, ServerReflectionRequest, ServerReflectionResponse,
+    server_reflection_request::MessageRequest, server_reflection_response::MessageResponse,
+};
+use tonic_reflection::server::v1::{ServerReflection, ServerReflectionServer};
+
+/// A reflection server answering from a fixed list of files.
+///
+/// `FileContainingSymbol` requests are answered with a batch of files (dependents before their
+/// dependencies), while `FileByFilename` requests are answered with a single file.
+/// Every received request is counted, and its host recorded.
+/// With `empty_frames`, every response is preceded by an empty one.
+#[derive(Clone)]
+struct BatchingReflection {
+    files: Arc<Vec<FileDescriptorProto>>,
+    symbol_batch: Arc<Vec<&'static str>>,
+    requests: Arc<AtomicUsize>,
+    hosts: Arc<Mutex<Vec<String>>>,
+    empty_frames: bool,
+}
+
+type ResponseStream = Pin<Box<dyn Stream<Item = Result<ServerReflectionResponse, Status>> + Send>>;
+
+impl BatchingReflection {
+    fn respond(
+        &self,
+        request: ServerReflectionRequest,
+    ) -> Result<ServerReflectionResponse, Status> {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+        self.hosts.lock().unwrap().push(request.host.clone());
+
+        let names: Vec<&str> = match &request.message_request {
+            Some(MessageRequest::FileContainingSymbol(_)) => self.symbol_batch.to_vec(),
+            Some(MessageRequest::FileByFilename(name)) => vec![name.as_str()],
+            _ => return Err(Status::unimplemented("not needed")),
+        };
+
+        let file_descriptor_proto = names
+            .into_iter()
+            .map(|name| {
+                self.files
+                    .iter()
+                    .find(|f| f.name() == name)
+                    .map(|f| f.encode_to_vec())
+                    .ok_or_else(|| Status::not_found(name.to_string()))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(ServerReflectionResponse {
+            valid_host: String::new(),
+            original_request: Some(request),
+            message_response: Some(MessageResponse::FileDescriptorResponse(
+                FileDescriptorResponse {
+                    file_descriptor_proto,
+                },
+            )),
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl ServerReflection for BatchingReflection {
+    type ServerReflectionInfoStream = ResponseStream;
+
+    async fn server_reflection_info(
+        &self,
+        request: Request<Streaming<ServerReflectionRequest>>,
+    ) -> Result<Response<Self::ServerReflectionInfoStream>, Status> {
+        let server = self.clone();
+        let responses = request.into_inner().flat_map(move |request| {
+            let empty = server.empty_frames.then(|| {
+                Ok(ServerReflectionResponse {
+                    valid_host: String::new(),
+                    original_request: None,
+                    message_response: None,
+                })
+            });
+            let response = request.and_then(|request| server.respond(request));
+
+            futures_util::stream::iter(empty.into_iter().chain([response]))
+        });
+
+        Ok(Response::new(Box::pin(responses)))
+    }
+}
+
+fn file(
+    name: &str,
+    dependency: &[&str],
+    message: &str,
+    field_type: Option<&str>,
+) -> FileDescriptorProto {
+    let field = field_type.map(|type_name| prost_types::FieldDescriptorProto {
+        name: Some("field".to_string()),
+        number: Some(1),
+        r#type: Some(prost_types::field_descriptor_proto::Type::Message as i32),
+        type_name: Some(format!(".cyclic.{type_name}")),
+        ..Default::default()
+    });
+
+    FileDescriptorProto {
+        name: Some(name.to_string()),
+        package: Some("cyclic".to_string()),
+        dependency: dependency.iter().map(|d| d.to_string()).collect(),
+        message_type: vec![DescriptorProto {
+            name: Some(message.to_string()),
+            field: field.into_iter().collect(),
+            ..Default::default()
+        }],
+        syntax: Some("proto3".to_string()),
+        ..Default::default()
+    }
+}
+
+// `protoc` rejects import cycles, so the descriptors are built by hand:
+//
+// * `service.proto` imports `a.proto` and `self.proto`.
+// * `a.proto` and `b.proto` import each other.
+// * `self.proto` imports itself.
+fn cyclic_files() -> Vec<FileDescriptorProto> {
+    let mut service = file("service.proto", &["a.proto", "self.proto"], "Empty", None);
+    service.service = vec![ServiceDescriptorProto {
+        name: Some("CyclicService".to_string()),
+        method: vec![prost_types::MethodDescriptorProto {
+            name: Some("Call".to_string()),
+            input_type: Some(".cyclic.A".to_string()),
+            output_type: Some(".cyclic.B".to_string()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }];
+
+    vec![
+        service,
+        file("a.proto", &["b.proto"], "A", Some("B")),
+        file("b.proto", &["a.proto"], "B", Some("A")),
+        file("self.proto", &["self.proto"], "Selfish", Some("Selfish")),
+    ]
+}
+
+fn setup_server(symbol_batch: Vec<&'static str>) -> BatchingReflection {
+    BatchingReflection {
+        files: Arc::new(cyclic_files()),
+        symbol_batch: Arc::new(symbol_batch),
+        requests: Arc::new(AtomicUsize::new(0)),
+        hosts: Arc::new(Mutex::new(vec![])),
+        empty_frames: false,
+    }
+}
+
+fn setup_client(
+    symbol_batch: Vec<&'static str>,
+) -> (
+    ReflectionClient<ServerReflectionServer<BatchingReflection>>,
+    Arc<AtomicUsize>,
+) {
+    let server = setup_server(symbol_batch);
+    let requests = server.requests.clone();
+
+    (
+        ReflectionClient::new(ServerReflectionServer::new(server)),
+        requests,
+    )
+}
+
+async fn resolve(
+    client: &mut ReflectionClient<ServerReflectionServer<BatchingReflection>>,
+) -> Vec<String> {
+    let fd_set = tokio::time::timeout(
+        Duration::from_secs(5),
+        client.file_descriptor_set_by_symbol("cyclic.CyclicService"),
+    )
+    .await
+    .expect("Resolution must not hang on import cycles")
+    .expect("Failed to resolve the file descriptor set");
+
+    let mut names: Vec<_> = fd_set
+        .file
+        .into_iter()
+        .map(|f| f.name().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn test_mutually_recursive_imports_fetch_full_closure() {
+    let (mut client, requests) = setup_client(vec!["service.proto"]);
+
+    let names = resolve(&mut client).await;
+
+    assert_eq!(names, ["a.proto", "b.proto", "self.proto", "service.proto"]);
+    // The symbol request, then `a.proto`, `self.proto` and `b.proto` exactly once each
+    assert_eq!(requests.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn test_dependencies_in_the_same_batch_are_not_requested_again() {
+    // `a.proto` is sent alongside `service.proto`, after the file importing it
+    let (mut client, requests) = setup_client(vec!["service.proto", "a.proto"]);
+
+    let names = resolve(&mut client).await;
+
+    assert_eq!(names, ["a.proto", "b.proto", "self.proto", "service.proto"]);
+    // The symbol request, then only `self.proto` and `b.proto`
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_whole_closure_in_a_single_batch() {
+    let (mut client, requests) =
+        setup_client(vec!["service.proto", "a.proto", "b.proto", "self.proto"]);
+
+    let names = resolve(&mut client).await;
+
+    assert_eq!(names, ["a.proto", "b.proto", "self.proto", "service.proto"]);
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_full_closure_with_the_smallest_buffer() {
+    let (client, requests) = setup_client(vec!["service.proto"]);
+    let mut client = client.with_buffer_size(1);
+
+    let names = resolve(&mut client).await;
+
+    assert_eq!(names, ["a.proto", "b.proto", "self.proto", "service.proto"]);
+    assert_eq!(requests.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn test_every_request_carries_the_configured_host() {
+    let server = setup_server(vec!["service.proto"]);
+    let hosts = server.hosts.clone();
+    let mut client =
+        ReflectionClient::new(ServerReflectionServer::new(server)).with_host("api.example.com");
+
+    resolve(&mut client).await;
+
+    let hosts = hosts.lock().unwrap();
+    assert_eq!(hosts.len(), 4);
+    assert!(hosts.iter().all(|host| host == "api.example.com"));
+}
+
+#[tokio::test]
+async fn test_empty_responses_are_skipped() {
+    let server = BatchingReflection {
+        empty_frames: true,
+        ..setup_server(vec!["service.proto"])
+    };
+    let requests = server.requests.clone();
+    let mut client = ReflectionClient::new(ServerReflectionServer::new(server)).with_buffer_size(1);
+
+    let names = resolve(&mut client).await;
+
+    assert_eq!(names, ["a.proto", "b.proto", "self.proto", "service.proto"]);
+    assert_eq!(requests.load(Ordering::SeqCst), 4);
+}
@@ -1,5 +1,6 @@
 use echo_service_impl::EchoServiceImpl;
-use granc_core::reflection::client::{ReflectionClient, ReflectionResolveError};
+use granc_core::reflection::client::{ReflectionClient, ReflectionResolveError, ReflectionVersion};
+use granc_test_support::compiler;
 use granc_test_support::echo_service::{EchoServiceServer, FILE_DESCRIPTOR_SET};
 use prost_reflect::DescriptorPool;
 use tonic::Code;
@@ -111,6 +112,35 @@ async fn test_reflection_service_not_found_error() {
     ));
 }
 
+#[tokio::test]
+async fn test_reflection_client_fetches_file_by_filename() {
+    let mut client = setup_reflection_client();
+
+    let fd_set = client
+        .file_descriptor_set_by_filename("echo.proto")
+        .await
+        .expect("Failed to fetch file descriptor set by filename");
+
+    let pool =
+        DescriptorPool::from_file_descriptor_set(fd_set).expect("Failed to build descriptor pool");
+
+    assert!(pool.get_service_by_name("echo.EchoService").is_some());
+}
+
+#[tokio::test]
+async fn test_reflection_file_not_found_error() {
+    let mut client = setup_reflection_client();
+
+    let result = client
+        .file_descriptor_set_by_filename("missing.proto")
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(ReflectionResolveError::ServerStreamFailure(status)) if status.code() == Code::NotFound
+    ));
+}
+
 #[tokio::test]
 async fn test_server_does_not_support_reflection() {
     // Create a server that ONLY hosts the EchoService.
@@ -136,3 +166,140 @@ async fn test_server_does_not_support_reflection() {
         Ok(_) => panic!("Expected error, but got successful registry"),
     }
 }
+
+#[tokio::test]
+async fn test_reflection_file_descriptor_set_is_sorted_topologically() {
+    let files = compiler::compile_protos(&[
+        (
+            "service.proto",
+            r#"
+            syntax = "proto3";
+            package layered;
+            import "request.proto";
+            import "common.proto";
+            service LayeredService {
+                rpc Call(Request) returns (Common);
+            }
+            "#,
+        ),
+        (
+            "request.proto",
+            r#"
+            syntax = "proto3";
+            package layered;
+            import "common.proto";
+            message Request { Common common = 1; }
+            "#,
+        ),
+        (
+            "common.proto",
+            r#"
+            syntax = "proto3";
+            package layered;
+            message Common { string id = 1; }
+            "#,
+        ),
+    ]);
+
+    let reflection_service = tonic_reflection::server::Builder::configure()
+        .register_file_descriptor_set(files)
+        .build_v1()
+        .expect("Failed to setup Reflection Service");
+
+    let mut client = ReflectionClient::new(reflection_service);
+
+    let fd_set = client
+        .file_descriptor_set_by_symbol("layered.LayeredService")
+        .await
+        .unwrap();
+
+    let names: Vec<_> = fd_set.file.iter().map(|f| f.name()).collect();
+    assert_eq!(names, ["common.proto", "request.proto", "service.proto"]);
+
+    // The order must not depend on the order in which files were received
+    for _ in 0..10 {
+        let again = client
+            .file_descriptor_set_by_symbol("layered.LayeredService")
+            .await
+            .unwrap();
+        assert_eq!(again, fd_set);
+    }
+}
+
+#[tokio::test]
+async fn test_reflection_client_talks_to_v1alpha_servers() {
+    let reflection_service = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .build_v1alpha()
+        .expect("Failed to setup Reflection Service");
+
+    // Pinning v1 disables the fallback
+    let mut client =
+        ReflectionClient::new(reflection_service.clone()).with_version(ReflectionVersion::V1);
+    let result = client.list_services().await;
+    assert!(matches!(
+        result,
+        Err(ReflectionResolveError::ServerStreamInitFailed(status)) if status.code() == Code::Unimplemented
+    ));
+
+    let mut client =
+        ReflectionClient::new(reflection_service).with_version(ReflectionVersion::V1Alpha);
+
+    let services = client
+        .list_services()
+        .await
+        .expect("Failed to list services");
+    assert!(services.contains(&"echo.EchoService".to_string()));
+
+    let fd_set = client
+        .file_descriptor_set_by_symbol("echo.EchoService")
+        .await
+        .expect("Failed to fetch file descriptor set by symbol");
+    assert!(
+        DescriptorPool::from_file_descriptor_set(fd_set)
+            .unwrap()
+            .get_service_by_name("echo.EchoService")
+            .is_some()
+    );
+}
+
+#[tokio::test]
+async fn test_reflection_client_discovers_v1alpha() {
+    let reflection_service = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .build_v1alpha()
+        .expect("Failed to setup Reflection Service");
+
+    let mut client = ReflectionClient::new(reflection_service);
+    assert_eq!(client.version(), ReflectionVersion::V1);
+
+    let fd_set = client
+        .file_descriptor_set_by_symbol("echo.EchoService")
+        .await
+        .expect("Failed to fetch file descriptor set by symbol");
+    assert!(!fd_set.file.is_empty());
+
+    // The working version is kept for the following requests
+    assert_eq!(client.version(), ReflectionVersion::V1Alpha);
+    let services = client
+        .list_services()
+        .await
+        .expect("Failed to list services");
+    assert!(services.contains(&"echo.EchoService".to_string()));
+}
+
+#[tokio::test]
+async fn test_reflection_client_keeps_v1_when_implemented() {
+    let reflection_service = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .build_v1()
+        .expect("Failed to setup Reflection Service");
+
+    let mut client = ReflectionClient::new(reflection_service);
+    client
+        .list_services()
+        .await
+        .expect("Failed to list services");
+
+    assert_eq!(client.version(), ReflectionVersion::V1);
+}
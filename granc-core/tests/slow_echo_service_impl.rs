@@ -0,0 +1,62 @@
+use futures_util::Stream;
+use granc_test_support::echo_service::EchoService;
+use granc_test_support::echo_service::pb::{EchoRequest, EchoResponse};
+use std::pin::Pin;
+use std::time::Duration;
+use tonic::{Request, Response, Status, Streaming};
+
+/// An echo service that sends a single message and then stalls forever.
+#[derive(Debug)]
+pub struct SlowEchoServiceImpl;
+
+type EchoStream = Pin<Box<dyn Stream<Item = Result<EchoResponse, Status>> + Send>>;
+
+fn stalled_stream(message: String) -> EchoStream {
+    let first = futures_util::stream::once(async move { Ok(EchoResponse { message }) });
+    Box::pin(futures_util::StreamExt::chain(
+        first,
+        futures_util::stream::pending(),
+    ))
+}
+
+#[tonic::async_trait]
+impl EchoService for SlowEchoServiceImpl {
+    type BidirectionalEchoStream = EchoStream;
+    type ServerStreamingEchoStream = EchoStream;
+
+    async fn unary_echo(
+        &self,
+        request: Request<EchoRequest>,
+    ) -> Result<Response<EchoResponse>, Status> {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        Ok(Response::new(EchoResponse {
+            message: request.into_inner().message,
+        }))
+    }
+
+    async fn server_streaming_echo(
+        &self,
+        request: Request<EchoRequest>,
+    ) -> Result<Response<Self::ServerStreamingEchoStream>, Status> {
+        // The client must propagate its deadline to the server
+        if request.metadata().get("grpc-timeout").is_none() {
+            return Err(Status::failed_precondition("missing grpc-timeout header"));
+        }
+
+        Ok(Response::new(stalled_stream(request.into_inner().message)))
+    }
+
+    async fn client_streaming_echo(
+        &self,
+        _request: Request<Streaming<EchoRequest>>,
+    ) -> Result<Response<EchoResponse>, Status> {
+        Err(Status::unimplemented("not needed"))
+    }
+
+    async fn bidirectional_echo(
+        &self,
+        _request: Request<Streaming<EchoRequest>>,
+    ) -> Result<Response<Self::BidirectionalEchoStream>, Status> {
+        Ok(Response::new(stalled_stream("echo".to_string())))
+    }
+}
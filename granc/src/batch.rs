@@ -0,0 +1,272 @@
+//! # Batch
+//!
+//! Support for `granc batch`, which runs the calls of a script file over a single connection.
+//!
+//! A script has one JSON object per line (blank lines are ignored):
+//!
+//! ```json
+//! {"method": "echo.EchoService/UnaryEcho", "body": {"message": "hi"}, "headers": {"x-id": "1"}}
+//! ```
+//!
+//! `body` defaults to an empty message and `headers` to none. The schema of a service is only
+//! resolved once, however many lines call it.
+//!
+//! `granc call --body-file` runs the same way, calling a single method with one body per line.
+use crate::{
+    CallClient,
+    cli::{BodyOptions, parse_bodies, parse_endpoint},
+    config::merge_headers,
+    formatter::json::{BatchResult, JsonError},
+    transport::Transport,
+};
+use granc_core::{
+    client::{DynamicRequest, GrancClient, OnlineWithoutReflection},
+    tonic::Code,
+};
+use prost::Message;
+use std::{collections::HashMap, time::Duration};
+
+/// A call read from a line of the script.
+#[derive(Debug, PartialEq)]
+pub struct Step {
+    pub service: String,
+    pub method: String,
+    pub body: serde_json::Value,
+    pub headers: Vec<(String, String)>,
+}
+
+/// Parses a line of the script.
+pub fn parse_step(line: &str) -> Result<Step, JsonError> {
+    let invalid = |message: String| JsonError::new(Code::InvalidArgument, message);
+
+    let value: serde_json::Value =
+        serde_json::from_str(line).map_err(|e| invalid(format!("Invalid JSON: {e}")))?;
+
+    let serde_json::Value::Object(mut object) = value else {
+        return Err(invalid("Expected a JSON object".to_string()));
+    };
+
+    let (service, method) = match object.remove("method") {
+        Some(serde_json::Value::String(path)) => parse_endpoint(&path).map_err(invalid)?,
+        _ => return Err(invalid("Missing 'method' string".to_string())),
+    };
+
+    let body = object
+        .remove("body")
+        .unwrap_or_else(|| serde_json::Value::Object(Default::default()));
+
+    let headers = match object.remove("headers") {
+        None => vec![],
+        Some(serde_json::Value::Object(headers)) => headers
+            .into_iter()
+            .map(|(key, value)| match value {
+                serde_json::Value::String(value) => Ok((key, value)),
+                _ => Err(invalid(format!("Header '{key}' must be a string"))),
+            })
+            .collect::<Result<_, _>>()?,
+        Some(_) => return Err(invalid("'headers' must be an object".to_string())),
+    };
+
+    Ok(Step {
+        service,
+        method,
+        body,
+        headers,
+    })
+}
+
+/// How the calls of a batch are made, besides their headers and timeout.
+///
+/// Mirrors the `--strict`, `--send-interval` and `--max-messages` flags of `granc call`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CallOptions {
+    pub strict: bool,
+    pub send_interval: Option<Duration>,
+    pub max_messages: Option<usize>,
+}
+
+/// The connection of a batch, with the schemas resolved so far.
+pub struct Batch {
+    client: CallClient,
+    resolved: HashMap<String, GrancClient<OnlineWithoutReflection<Transport>>>,
+    headers: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    options: CallOptions,
+}
+
+impl Batch {
+    /// Creates a batch whose calls send `headers` (unless a line overrides them) and `timeout`.
+    pub fn new(
+        client: CallClient,
+        headers: Vec<(String, String)>,
+        timeout: Option<Duration>,
+    ) -> Self {
+        Self {
+            client,
+            resolved: HashMap::new(),
+            headers,
+            timeout,
+            options: CallOptions::default(),
+        }
+    }
+
+    /// Makes every call of the batch with `options`.
+    pub fn with_options(mut self, options: CallOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Runs the call of the line number `line` of the script, whose content is `text`.
+    pub async fn run(&mut self, line: usize, text: &str) -> BatchResult {
+        match parse_step(text) {
+            Ok(step) => self.call(line, step, false).await,
+            Err(err) => BatchResult {
+                line,
+                method: None,
+                request: None,
+                result: Err(err),
+            },
+        }
+    }
+
+    /// Calls `service/method` with the body of the line number `line` of a body file, whose
+    /// content is `text`.
+    ///
+    /// The line is read according to `options`, as a `--body` would be.
+    pub async fn run_body(
+        &mut self,
+        line: usize,
+        service: &str,
+        method: &str,
+        text: &str,
+        options: BodyOptions,
+    ) -> BatchResult {
+        match parse_bodies(vec![text.to_string()], options) {
+            Ok(body) => {
+                let step = Step {
+                    service: service.to_string(),
+                    method: method.to_string(),
+                    body,
+                    headers: vec![],
+                };
+                self.call(line, step, true).await
+            }
+            Err(err) => BatchResult {
+                line,
+                method: Some(format!("{service}/{method}")),
+                request: None,
+                result: Err(JsonError::new(Code::InvalidArgument, err)),
+            },
+        }
+    }
+
+    // Sends `step`, echoing its body in the result with `echo_body`
+    async fn call(&mut self, line: usize, step: Step, echo_body: bool) -> BatchResult {
+        let method = format!("{}/{}", step.service, step.method);
+        let request = echo_body.then(|| step.body.clone());
+
+        let call = DynamicRequest {
+            body: step.body,
+            headers: merge_headers(&self.headers, step.headers),
+            timeout: self.timeout,
+            cancellation: None,
+            strict: self.options.strict,
+            send_interval: self.options.send_interval,
+            max_messages: self.options.max_messages,
+            service: step.service,
+            method: step.method,
+        };
+
+        let result = match self.client_for(&call.service).await {
+            Ok(client) => client.dynamic(call).await.map_err(JsonError::from),
+            Err(err) => Err(err),
+        };
+
+        BatchResult {
+            line,
+            method: Some(method),
+            request,
+            result,
+        }
+    }
+
+    // Returns a client that knows the schema of `service`, resolving it via reflection the first
+    // time the service is seen
+    async fn client_for(
+        &mut self,
+        service: &str,
+    ) -> Result<&mut GrancClient<OnlineWithoutReflection<Transport>>, JsonError> {
+        let client = match &mut self.client {
+            CallClient::FileDescriptor(client) => return Ok(client),
+            CallClient::Reflection(client) => client,
+        };
+
+        if !self.resolved.contains_key(service) {
+            tracing::debug!("Resolving the schema of '{service}' via reflection");
+
+            let fd_set = client.file_descriptor_set(&[service.to_string()]).await?;
+            let resolved = (**client)
+                .clone()
+                .with_file_descriptor(fd_set.encode_to_vec())?;
+
+            self.resolved.insert(service.to_string(), resolved);
+        }
+
+        Ok(self
+            .resolved
+            .get_mut(service)
+            .expect("The schema was just resolved"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_step() {
+        let step = parse_step(
+            r#"{"method": "/echo.EchoService/UnaryEcho", "body": {"message": "hi"}, "headers": {"x-id": "1"}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            step,
+            Step {
+                service: "echo.EchoService".to_string(),
+                method: "UnaryEcho".to_string(),
+                body: json!({"message": "hi"}),
+                headers: vec![("x-id".to_string(), "1".to_string())],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_step_defaults() {
+        let step = parse_step(r#"{"method": "echo.EchoService/UnaryEcho"}"#).unwrap();
+
+        assert_eq!(step.body, json!({}));
+        assert!(step.headers.is_empty());
+    }
+
+    #[test]
+    fn test_parse_step_errors() {
+        let message =
+            |line: &str| serde_json::Value::from(parse_step(line).unwrap_err())["message"].clone();
+
+        assert_eq!(message("[]"), json!("Expected a JSON object"));
+        assert_eq!(message(r#"{"body": {}}"#), json!("Missing 'method' string"));
+        assert_eq!(
+            message(r#"{"method": "a.B/C", "headers": {"x": 1}}"#),
+            json!("Header 'x' must be a string")
+        );
+        assert!(
+            message(r#"{"method": "nope"}"#)
+                .as_str()
+                .unwrap()
+                .starts_with("Invalid endpoint format")
+        );
+        assert!(message("{").as_str().unwrap().starts_with("Invalid JSON"));
+    }
+}
@@ -2,13 +2,62 @@
 //!
 //! This module defines the command-line interface of `granc` using `clap`.
 //! It enforces strict invariants for arguments using subcommands and argument groups.
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum, error::ErrorKind};
+use granc_core::{
+    client::parse_method_path,
+    prost_reflect::{DynamicMessage, MessageDescriptor},
+};
 
 #[derive(Parser, Debug)]
 #[command(name = "granc", version, about = "Dynamic gRPC CLI")]
 pub struct Cli {
+    /// When to use colored output.
+    ///
+    /// `auto` enables colors only when stdout is a terminal and `NO_COLOR` is not set.
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Disable colored output (same as `--color never`)
+    #[arg(long, global = true, conflicts_with = "color")]
+    pub no_color: bool,
+
+    /// Print diagnostic logs to stderr (resolved method, schema source, headers...).
+    ///
+    /// Logs can also be enabled with `RUST_LOG` (e.g. `RUST_LOG=granc=debug`).
+    #[arg(long, short = 'v', global = true)]
+    pub verbose: bool,
+
+    /// Print the encoded size of every request and response message to stderr
+    #[arg(long, global = true)]
+    pub show_sizes: bool,
+
+    /// Give up connecting to the server after this long (e.g. 5s, 500ms).
+    ///
+    /// Unlike `call --timeout`, this only bounds establishing the connection.
+    #[arg(long, global = true, value_parser = parse_duration)]
+    pub connect_timeout: Option<Duration>,
+
+    /// Talk to the server using gRPC-Web (e.g. a browser-facing server behind Envoy).
+    ///
+    /// Unary and server streaming calls work as usual, client and bidirectional streams may be
+    /// rejected by the server.
+    #[arg(long, global = true)]
+    pub grpc_web: bool,
+
+    /// The `host` sent in reflection requests (defaults to the authority of the URI, e.g. `localhost:50051`).
+    ///
+    /// For servers that serve a different schema per virtual host.
+    #[arg(long, global = true)]
+    pub reflection_host: Option<String>,
+
+    /// Connect over plaintext HTTP/2 (h2c), whatever the scheme of the URI.
+    ///
+    /// Lets URIs without a scheme, like `localhost:50051`, be used as is.
+    #[arg(long, global = true)]
+    pub plaintext: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -28,23 +77,164 @@ pub enum Commands {
         uri: String,
 
         /// "JSON body (Object for Unary, Array for Streaming)"
-        #[arg(long, short = 'b', value_parser = parse_body)]
-        body: serde_json::Value,
+        ///
+        /// Repeat it to send one stream message per occurrence instead of a single array.
+        /// With `--body-format textproto`, each occurrence is one message in protobuf text format.
+        #[arg(long, short = 'b', required_unless_present_any = ["raw", "stdin", "body_file"])]
+        body: Vec<String>,
+
+        /// Make one call per line of this file (.jsonl), each line being the body of a call.
+        ///
+        /// Every call goes over the same connection. The body and the result of each line are
+        /// printed as NDJSON, or as a single array with `--output json-array`.
+        /// `--strict`, `--send-interval` and `--max-messages` apply to every call.
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with_all = ["body", "raw", "stdin", "dry_run", "watch", "print_grpcurl", "bytes"]
+        )]
+        body_file: Option<PathBuf>,
+
+        /// Replace `${VAR}` in the body with the value of the environment variable `VAR` before parsing it
+        #[arg(long, conflicts_with = "raw")]
+        expand_env: bool,
+
+        /// Accept bodies with a UTF-8 BOM or trailing commas, as pasted from some editors
+        #[arg(long, conflicts_with = "raw")]
+        lenient_json: bool,
+
+        /// Format of the `--body` values
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = BodyFormat::Json,
+            conflicts_with_all = ["raw", "body_file", "stdin", "lenient_json", "print_grpcurl"]
+        )]
+        body_format: BodyFormat,
+
+        /// Header sent with the call and the reflection requests (`key:value`)
+        #[arg(short = 'H', long = "header", value_parser = parse_header)]
+        headers: Vec<(String, String)>,
+
+        #[command(flatten)]
+        header_sources: Box<HeaderFiles>,
+
+        /// Optional path to a file descriptor set (.bin) to use instead of reflection
+        #[arg(long, short = 'f')]
+        file_descriptor_set: Option<PathBuf>,
+
+        /// Output format for responses and errors
+        #[arg(long, value_enum, default_value_t = CallFormat::Text)]
+        output: CallFormat,
+
+        /// Deadline for the call (e.g. 5s, 500ms). Sent to the server as the `grpc-timeout` header.
+        #[arg(long, value_parser = parse_duration)]
+        timeout: Option<Duration>,
+
+        /// Re-run the call on an interval (e.g. 2s, 500ms, 1m) until interrupted. Unary methods only.
+        #[arg(long, value_parser = parse_duration)]
+        watch: Option<Duration>,
+
+        /// Resolve the schema and validate the body, then print the request instead of sending it
+        #[arg(long, conflicts_with = "watch")]
+        dry_run: bool,
+
+        /// List the valid fields of a message when the body has a field that is not part of it
+        #[arg(long)]
+        strict: bool,
 
+        /// Delay between the messages of a client or bidirectional stream (e.g. 100ms, 1s)
+        #[arg(long, value_parser = parse_duration)]
+        send_interval: Option<Duration>,
+
+        /// Print the equivalent `grpcurl` command to stderr before making the call
+        #[arg(long)]
+        print_grpcurl: bool,
+
+        /// Send the pre-encoded protobuf message of this file as is, without JSON transcoding.
+        ///
+        /// The response bytes are written as is too. Unary methods only, no schema is resolved.
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["body", "dry_run", "watch", "strict"])]
+        raw: Option<PathBuf>,
+
+        /// How `bytes` fields of responses are printed
+        #[arg(long, value_enum, default_value_t = BytesFormat::Base64)]
+        bytes: BytesFormat,
+
+        #[command(flatten)]
+        response: Box<ResponseOptions>,
+
+        /// With `--raw`, write the response bytes to this file instead of stdout
+        #[arg(long, value_name = "FILE", requires = "raw")]
+        raw_output: Option<PathBuf>,
+
+        #[command(flatten)]
+        message_types: Box<MessageTypes>,
+
+        /// Send the lines typed on stdin (one JSON object each) as the messages of a bidirectional
+        /// stream, printing every response as soon as it arrives. Ends when stdin is closed.
+        #[arg(
+            long,
+            conflicts_with_all = ["body", "raw", "dry_run", "watch", "strict", "expand_env", "send_interval", "print_grpcurl"]
+        )]
+        stdin: bool,
+    },
+
+    /// Run the calls of a script file, one JSON object per line, over a single connection.
+    ///
+    /// Each line looks like `{"method": "pkg.Service/Method", "body": {...}, "headers": {...}}`.
+    /// Results are printed as NDJSON, in order. Exits with a non-zero code if any call failed.
+    Batch {
+        /// Path of the script (.jsonl)
+        script: PathBuf,
+
+        /// The server URI to connect to (e.g. http://localhost:50051)
+        #[arg(long, short = 'u')]
+        uri: String,
+
+        /// Headers sent with every call, unless a line sets the same header
         #[arg(short = 'H', long = "header", value_parser = parse_header)]
         headers: Vec<(String, String)>,
 
         /// Optional path to a file descriptor set (.bin) to use instead of reflection
         #[arg(long, short = 'f')]
         file_descriptor_set: Option<PathBuf>,
+
+        /// Deadline for every call (e.g. 5s, 500ms)
+        #[arg(long, value_parser = parse_duration)]
+        timeout: Option<Duration>,
     },
 
-    /// List available services.
+    /// List available services (or every method of every service).
     ///
     /// Requires EITHER a server URI (Reflection) OR a file descriptor set (Offline).
     List {
+        /// What to list
+        #[arg(value_enum, default_value_t = ListTarget::Services)]
+        target: ListTarget,
+
         #[command(flatten)]
         source: SourceSelection,
+
+        /// Output format for the service list and errors
+        #[arg(long, value_enum, default_value_t = ListFormat::Text)]
+        output: ListFormat,
+
+        /// Also list the methods of every service
+        #[arg(long)]
+        with_methods: bool,
+
+        /// Print the number of methods of every service, broken down by streaming type
+        #[arg(long, conflicts_with = "with_methods")]
+        summary: bool,
+
+        /// Also list the services of the gRPC tooling (reflection, health and channelz)
+        #[arg(long)]
+        include_reflection: bool,
+
+        /// Header sent with the reflection requests (`key:value`), for servers that require authentication
+        #[arg(short = 'H', long = "header", value_parser = parse_header)]
+        headers: Vec<(String, String)>,
     },
 
     /// Describe a service, message or enum.
@@ -54,8 +244,140 @@ pub enum Commands {
         #[command(flatten)]
         source: SourceSelection,
 
-        /// Fully qualified name (e.g. my.package.Service)
+        /// Fully qualified name (e.g. my.package.Service), or the path of a method (e.g. my.package.Service/Method)
         symbol: String,
+
+        /// Output format for the definition and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+
+        /// List the fields of messages by field number instead of in declaration order
+        #[arg(long)]
+        sort_fields: bool,
+
+        /// Also print the messages and enums used by the definition, transitively
+        #[arg(long)]
+        expand: bool,
+
+        /// Print the file descriptors (and their dependencies) the definition was resolved from
+        /// as JSON, as returned by the server, to debug the resolution of a schema
+        #[arg(long, conflicts_with_all = ["expand", "sort_fields"])]
+        raw: bool,
+
+        /// Header sent with the reflection requests (`key:value`), for servers that require authentication
+        #[arg(short = 'H', long = "header", value_parser = parse_header)]
+        headers: Vec<(String, String)>,
+    },
+
+    /// Describe a proto file (its services, messages and enums) by file name.
+    ///
+    /// Requires EITHER a server URI (Reflection) OR a file descriptor set (Offline).
+    File {
+        #[command(flatten)]
+        source: SourceSelection,
+
+        /// Name of the proto file (e.g. my/package/service.proto)
+        filename: String,
+
+        /// Output format for the file definition and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+
+        /// Header sent with the reflection requests (`key:value`), for servers that require authentication
+        #[arg(short = 'H', long = "header", value_parser = parse_header)]
+        headers: Vec<(String, String)>,
+    },
+
+    /// Check the health of a server using the standard `grpc.health.v1.Health/Check` method.
+    ///
+    /// Exits with a non-zero code if the server (or service) is not serving.
+    Health {
+        /// The server URI to connect to (e.g. http://localhost:50051)
+        #[arg(long, short = 'u')]
+        uri: String,
+
+        /// Name of the service to check. Checks the overall server health if omitted.
+        #[arg(long, short = 's')]
+        service: Option<String>,
+
+        /// Output format for the health status and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+
+        /// Header sent with the health check (`key:value`), for servers that require authentication
+        #[arg(short = 'H', long = "header", value_parser = parse_header)]
+        headers: Vec<(String, String)>,
+    },
+
+    /// Check that a server is reachable, and whether it supports Server Reflection.
+    ///
+    /// Exits with a non-zero code if the server can't be reached.
+    Ping {
+        /// The server URI to connect to (e.g. http://localhost:50051)
+        #[arg(long, short = 'u')]
+        uri: String,
+
+        /// Skip the Server Reflection check, sending a health check instead.
+        ///
+        /// Any answer of the server, even `UNIMPLEMENTED`, counts as reachable.
+        #[arg(long)]
+        no_reflection: bool,
+
+        /// Header sent with the reflection requests (`key:value`), for servers that require authentication
+        #[arg(short = 'H', long = "header", value_parser = parse_header)]
+        headers: Vec<(String, String)>,
+
+        /// Output format for the result and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+    },
+
+    /// Export the schema of a server to a file descriptor set (.bin) using reflection.
+    ///
+    /// The exported file can be used later with `--file-descriptor-set` for offline use.
+    Export {
+        /// Fully qualified service name (e.g. my.package.MyService)
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        symbol: Option<String>,
+
+        /// The server URI to connect to (e.g. http://localhost:50051)
+        #[arg(long, short = 'u')]
+        uri: String,
+
+        /// Export every service exposed by the server into a single set
+        #[arg(long)]
+        all: bool,
+
+        /// Path of the file descriptor set to write
+        #[arg(long, short = 'o')]
+        output: PathBuf,
+
+        /// Header sent with the reflection requests (`key:value`), for servers that require authentication
+        #[arg(short = 'H', long = "header", value_parser = parse_header)]
+        headers: Vec<(String, String)>,
+    },
+
+    /// Compare two schemas, e.g. to review the changes of an API.
+    ///
+    /// Each schema is either a file descriptor set (.bin) or a running server, whose services
+    /// are resolved via reflection. Lists the services, methods, messages, fields, enums and
+    /// enum values that were added, removed or changed. Exits with code 1 if any change is
+    /// breaking (e.g. a removed field or a changed field number), which makes it usable as a
+    /// compatibility check in CI.
+    Diff {
+        #[command(flatten)]
+        old: OldSchema,
+
+        #[command(flatten)]
+        new: NewSchema,
+
+        /// Header sent with the reflection requests (`key:value`), for servers that require authentication
+        #[arg(short = 'H', long = "header", value_parser = parse_header)]
+        headers: Vec<(String, String)>,
+
+        /// Output format for the changes and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
     },
 
     /// Generate Markdown documentation for a service.
@@ -69,9 +391,250 @@ pub enum Commands {
         /// Output directory for the generated markdown files
         #[arg(long, short = 'o')]
         output: PathBuf,
+
+        /// Write a single README.md with a table of contents instead of one file per package
+        #[arg(long)]
+        single_file: bool,
+
+        /// Add a Mermaid graph of the dependencies between the service, messages and enums to the index
+        #[arg(long)]
+        mermaid: bool,
+
+        /// List the fields of messages by field number instead of in declaration order
+        #[arg(long)]
+        sort_fields: bool,
+
+        /// Header sent with the reflection requests (`key:value`), for servers that require authentication
+        #[arg(short = 'H', long = "header", value_parser = parse_header)]
+        headers: Vec<(String, String)>,
     },
 }
 
+/// Whether colored output should be used.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Use colors only when printing to a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl Cli {
+    /// Prints the help or the version and exits, if the command line asks for either of them.
+    ///
+    /// The `--uri` default is not known yet, so it is left out of the help.
+    pub fn exit_on_help_or_version() {
+        if let Some(e) = Self::help_or_version(std::env::args_os()) {
+            e.exit()
+        }
+    }
+
+    fn help_or_version<I, T>(args: I) -> Option<clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        Self::command()
+            .try_get_matches_from(args)
+            .err()
+            .filter(|e| {
+                matches!(
+                    e.kind(),
+                    ErrorKind::DisplayHelp
+                        | ErrorKind::DisplayVersion
+                        | ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+                )
+            })
+    }
+
+    /// Parses the command line, using `default_uri` for `--uri` when it is not given.
+    ///
+    /// Unlike a `--uri` given explicitly, the default doesn't conflict with `--file-descriptor-set`.
+    pub fn parse_with_default_uri(default_uri: Option<&str>) -> Self {
+        let matches = Self::command_with_default_uri(default_uri).get_matches();
+        Self::from_arg_matches(&matches).unwrap_or_else(|e| e.exit())
+    }
+
+    fn command_with_default_uri(default_uri: Option<&str>) -> clap::Command {
+        let command = Self::command();
+
+        let Some(uri) = default_uri else {
+            return command;
+        };
+
+        let with_uri: Vec<_> = command
+            .get_subcommands()
+            .filter(|sub| sub.get_arguments().any(|arg| arg.get_id() == "uri"))
+            .map(|sub| sub.get_name().to_string())
+            .collect();
+
+        // The default satisfies `--uri`, and the "uri or file" groups it belongs to
+        with_uri.iter().fold(command, |command, name| {
+            command.mut_subcommand(name, |sub| {
+                let groups: Vec<_> = sub
+                    .get_groups()
+                    .filter(|group| group.get_args().any(|arg| arg == "uri"))
+                    .map(|group| group.get_id().clone())
+                    .collect();
+
+                let sub = sub.mut_arg("uri", |arg| {
+                    arg.default_value(uri.to_string()).required(false)
+                });
+
+                groups.into_iter().fold(sub, |sub, group| {
+                    sub.mut_group(group, |group| group.required(false))
+                })
+            })
+        })
+    }
+
+    /// Returns the color choice after resolving the `--no-color` shorthand.
+    pub fn color_choice(&self) -> ColorChoice {
+        if self.no_color {
+            ColorChoice::Never
+        } else {
+            self.color
+        }
+    }
+}
+
+/// How results and errors are printed.
+///
+/// `Text` is meant for humans (colored, pretty printed), `Json` is meant for scripts.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// How the `--body` values of a call are written.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BodyFormat {
+    #[default]
+    Json,
+    /// Protobuf text format (e.g. `message: "hello"`), as in `.textproto` files.
+    /// The body is parsed once the input type of the method is resolved.
+    Textproto,
+}
+
+/// How the response of a call is printed.
+///
+/// Same as [`OutputFormat`], plus `JsonArray`, which prints a whole stream as one JSON array.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CallFormat {
+    #[default]
+    Text,
+    /// One compact JSON document per message (NDJSON)
+    Json,
+    /// A single JSON array with every message, printed once the stream ends
+    JsonArray,
+}
+
+impl From<CallFormat> for OutputFormat {
+    fn from(format: CallFormat) -> Self {
+        match format {
+            CallFormat::Text => OutputFormat::Text,
+            CallFormat::Json | CallFormat::JsonArray => OutputFormat::Json,
+        }
+    }
+}
+
+/// How the `bytes` fields of a response are printed.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BytesFormat {
+    /// As in the proto3 JSON mapping
+    #[default]
+    Base64,
+    /// Lowercase hex, easier to read when debugging binary fields
+    Hex,
+}
+
+/// What the `list` command enumerates.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ListTarget {
+    /// The services exposed by the server
+    #[default]
+    Services,
+    /// Every method of every service, as `package.Service/Method (kind)`
+    Methods,
+}
+
+/// How the service list is printed.
+///
+/// Same as [`OutputFormat`], plus a `Table` of services and their method counts.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ListFormat {
+    #[default]
+    Text,
+    Json,
+    Table,
+}
+
+impl From<ListFormat> for OutputFormat {
+    // Errors are printed as text when a table is requested
+    fn from(format: ListFormat) -> Self {
+        match format {
+            ListFormat::Text | ListFormat::Table => OutputFormat::Text,
+            ListFormat::Json => OutputFormat::Json,
+        }
+    }
+}
+
+/// Which response messages of a call are printed, and which of their fields.
+#[derive(Args, Debug, Default)]
+pub struct ResponseOptions {
+    /// Only print these fields of every response message, as dot paths (e.g. `user.name,items.0.id`).
+    ///
+    /// Numeric segments index into arrays, other segments apply to every element.
+    /// Fields that don't exist are omitted.
+    #[arg(
+        long,
+        value_name = "PATHS",
+        value_delimiter = ',',
+        conflicts_with_all = ["raw", "body_file", "dry_run"]
+    )]
+    pub fields: Vec<String>,
+
+    /// End a server or bidirectional stream after this many messages, cancelling the call.
+    ///
+    /// Useful for streams that never end (e.g. subscriptions).
+    #[arg(long, value_name = "N", conflicts_with_all = ["raw", "dry_run", "watch"])]
+    pub max_messages: Option<usize>,
+}
+
+/// The files the headers of a call are read from, besides its `-H` flags.
+#[derive(Args, Debug, Default)]
+pub struct HeaderFiles {
+    /// Header whose value is read from a file (`key=path`), keeping secrets out of `ps` output
+    #[arg(long = "header-file", value_parser = parse_header_file)]
+    pub header_files: Vec<(String, PathBuf)>,
+
+    /// JSON file with an object of headers (`{"key": "value", ...}`). `-H` wins over it.
+    #[arg(long, value_name = "FILE")]
+    pub headers_file: Option<PathBuf>,
+}
+
+/// The message types of a method whose service is missing from the descriptor set.
+#[derive(Args, Debug, Default)]
+pub struct MessageTypes {
+    /// Fully qualified name of the request message (e.g. my.package.MyRequest), for descriptor
+    /// sets that define the messages of the method but not its service.
+    ///
+    /// The method is called as a unary method, without looking it up in the descriptor set.
+    #[arg(
+        long,
+        requires_all = ["response_type", "file_descriptor_set"],
+        conflicts_with_all = ["raw", "stdin", "body_file", "dry_run", "watch"]
+    )]
+    pub request_type: Option<String>,
+
+    /// Fully qualified name of the response message, see `--request-type`
+    #[arg(long, requires = "request_type")]
+    pub response_type: Option<String>,
+}
+
 #[derive(Args, Debug)]
 #[group(required = true, multiple = false)] // Enforces: Either URI OR FileDescriptorSet, never both.
 pub struct SourceSelection {
@@ -84,6 +647,50 @@ pub struct SourceSelection {
     file_descriptor_set: Option<PathBuf>,
 }
 
+#[derive(Args, Debug)]
+#[group(required = true, multiple = false)]
+pub struct OldSchema {
+    /// The file descriptor set (.bin) of the old schema
+    #[arg(long)]
+    old: Option<PathBuf>,
+
+    /// The server URI to resolve the old schema from via reflection
+    #[arg(long)]
+    old_uri: Option<String>,
+}
+
+impl OldSchema {
+    pub fn value(self) -> Source {
+        match (self.old, self.old_uri) {
+            (Some(path), _) => Source::File(path),
+            (None, Some(uri)) => Source::Uri(uri),
+            (None, None) => unreachable!("Clap ensures either --old or --old-uri is present"),
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+#[group(required = true, multiple = false)]
+pub struct NewSchema {
+    /// The file descriptor set (.bin) of the new schema
+    #[arg(long)]
+    new: Option<PathBuf>,
+
+    /// The server URI to resolve the new schema from via reflection
+    #[arg(long)]
+    new_uri: Option<String>,
+}
+
+impl NewSchema {
+    pub fn value(self) -> Source {
+        match (self.new, self.new_uri) {
+            (Some(path), _) => Source::File(path),
+            (None, Some(uri)) => Source::Uri(uri),
+            (None, None) => unreachable!("Clap ensures either --new or --new-uri is present"),
+        }
+    }
+}
+
 // The source where to resolve the proto schemas from.
 //
 // It can either be a URI (If the server supports server streaming)
@@ -95,10 +702,11 @@ pub enum Source {
 
 impl SourceSelection {
     pub fn value(self) -> Source {
-        if let Some(uri) = self.uri {
-            Source::Uri(uri)
-        } else if let Some(path) = self.file_descriptor_set {
+        // A file given explicitly takes precedence over a default URI
+        if let Some(path) = self.file_descriptor_set {
             Source::File(path)
+        } else if let Some(uri) = self.uri {
+            Source::Uri(uri)
         } else {
             // This is unreachable because `clap` verifies the group requirements before we ever get here.
             unreachable!(
@@ -108,16 +716,13 @@ impl SourceSelection {
     }
 }
 
-fn parse_endpoint(value: &str) -> Result<(String, String), String> {
-    let (service, method) = value.split_once('/').ok_or_else(|| {
-        format!("Invalid endpoint format: '{value}'. Expected 'package.Service/Method'",)
-    })?;
-
-    if service.trim().is_empty() || method.trim().is_empty() {
-        return Err("Service and Method names cannot be empty".to_string());
-    }
-
-    Ok((service.to_string(), method.to_string()))
+// Parses `package.Service/Method`.
+//
+// A leading slash (`/package.Service/Method`, the form found in gRPC logs) is accepted too.
+pub fn parse_endpoint(value: &str) -> Result<(String, String), String> {
+    parse_method_path(value)
+        .map(|(service, method)| (service.to_string(), method.to_string()))
+        .map_err(|e| e.to_string())
 }
 
 fn parse_header(s: &str) -> Result<(String, String), String> {
@@ -126,10 +731,181 @@ fn parse_header(s: &str) -> Result<(String, String), String> {
         .ok_or_else(|| "Format must be 'key:value'".to_string())
 }
 
+fn parse_header_file(s: &str) -> Result<(String, PathBuf), String> {
+    s.split_once('=')
+        .map(|(k, path)| (k.trim().to_string(), PathBuf::from(path)))
+        .filter(|(k, path)| !k.is_empty() && !path.as_os_str().is_empty())
+        .ok_or_else(|| "Format must be 'key=path'".to_string())
+}
+
+/// Parses a humantime-style duration: `500ms`, `2s`, `1m`, `1h` (a bare number is in seconds).
+///
+/// The units of gRPC's own `grpc-timeout` format are rejected, since some of them mean something
+/// else here (`500m` is 500 minutes, not milliseconds).
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("Invalid duration: '{value}'. Expected e.g. '500ms', '2s', '1m'"))?;
+
+    let seconds = |factor: u64| {
+        amount
+            .checked_mul(factor)
+            .map(Duration::from_secs)
+            .ok_or_else(|| format!("Duration is too long: '{value}'"))
+    };
+
+    match unit {
+        "ms" => Ok(Duration::from_millis(amount)),
+        "s" | "" => seconds(1),
+        "m" => seconds(60),
+        "h" => seconds(3600),
+        "H" | "M" | "S" | "u" | "n" => Err(format!(
+            "Invalid duration unit: '{unit}'. gRPC timeout units are not supported, use 'ms', 's', 'm' (minutes) or 'h'"
+        )),
+        _ => Err(format!(
+            "Invalid duration unit: '{unit}'. Expected one of 'ms', 's', 'm', 'h'"
+        )),
+    }
+}
+
 fn parse_body(value: &str) -> Result<serde_json::Value, String> {
     serde_json::from_str(value).map_err(|e| format!("Invalid JSON: {e}"))
 }
 
+/// How the request bodies are read before being parsed as JSON.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BodyOptions {
+    /// Replace the `${VAR}` placeholders by the value of the environment variable.
+    /// An undefined variable is an error.
+    pub expand_env: bool,
+    /// Strip a leading UTF-8 BOM and the trailing commas of objects and arrays.
+    pub lenient: bool,
+}
+
+/// Parses the request body from every `--body` occurrence, according to `options`.
+pub fn parse_bodies(
+    bodies: Vec<String>,
+    options: BodyOptions,
+) -> Result<serde_json::Value, String> {
+    bodies
+        .iter()
+        .map(|body| {
+            let body = expand_body(body, options)?;
+
+            if options.lenient {
+                parse_body(&strip_trailing_commas(body.trim_start_matches('\u{feff}')))
+            } else {
+                parse_body(&body)
+            }
+        })
+        .collect::<Result<_, _>>()
+        .map(join_bodies)
+}
+
+// Removes the commas followed by the end of an object or an array, outside of strings
+fn strip_trailing_commas(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => in_string = !in_string,
+            '\\' if in_string => {
+                // The escaped character can't end the string
+                out.push(c);
+                if let Some(escaped) = chars.next() {
+                    out.push(escaped);
+                }
+                continue;
+            }
+            ',' if !in_string => {
+                let rest = chars.clone().find(|c| !c.is_whitespace());
+                if matches!(rest, Some('}' | ']')) {
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        out.push(c);
+    }
+
+    out
+}
+
+// Replaces every `${NAME}` of `text` with `lookup(NAME)`
+fn expand_vars(text: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| "Unterminated '${' in the body".to_string())?;
+        let name = &after[..end];
+
+        if name.is_empty() {
+            return Err("Empty variable name '${}' in the body".to_string());
+        }
+
+        let value =
+            lookup(name).ok_or_else(|| format!("Environment variable '{name}' is not set"))?;
+        out.push_str(&value);
+        rest = &after[end + 1..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Parses every `--body` occurrence as a message of type `input` in protobuf text format.
+///
+/// Each occurrence is one message, whatever the kind of the method: the call sends them as they
+/// are, as the single message of the request or the messages of the stream.
+pub fn parse_text_bodies(
+    bodies: Vec<String>,
+    input: &MessageDescriptor,
+    options: BodyOptions,
+) -> Result<Vec<DynamicMessage>, String> {
+    bodies
+        .iter()
+        .map(|body| {
+            let body = expand_body(body, options)?;
+            DynamicMessage::parse_text_format(input.clone(), &body)
+                .map_err(|e| format!("Invalid text format: {e}"))
+        })
+        .collect()
+}
+
+fn expand_body(body: &str, options: BodyOptions) -> Result<String, String> {
+    if options.expand_env {
+        expand_vars(body, |name| std::env::var(name).ok())
+    } else {
+        Ok(body.to_string())
+    }
+}
+
+/// Builds the request body from the values of every `--body` occurrence.
+///
+/// A single occurrence is used as is, so it can still be an array of stream messages.
+/// Repeated occurrences are the messages of the stream, in order.
+fn join_bodies(mut bodies: Vec<serde_json::Value>) -> serde_json::Value {
+    if bodies.len() == 1 {
+        bodies.remove(0)
+    } else {
+        serde_json::Value::Array(bodies)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,7 +938,7 @@ mod tests {
                     ("helloworld.Greeter".to_string(), "SayHello".to_string())
                 );
                 assert_eq!(uri, "http://localhost:50051");
-                assert_eq!(body, serde_json::json!({"name": "Ferris"}));
+                assert_eq!(body, vec![r#"{"name": "Ferris"}"#]);
                 assert!(file_descriptor_set.is_none());
             }
             _ => panic!("Expected Call command"),
@@ -227,7 +1003,7 @@ mod tests {
             } => {
                 assert_eq!(uri, "http://localhost:50051");
                 assert_eq!(file_descriptor_set.unwrap().to_str().unwrap(), "desc.bin");
-                assert_eq!(body, serde_json::json!({}));
+                assert_eq!(body, vec!["{}"]);
                 assert_eq!(headers[0], ("auth".to_string(), "bearer".to_string()));
             }
             _ => panic!("Expected Call command"),
@@ -235,17 +1011,99 @@ mod tests {
     }
 
     #[test]
-    fn test_list_command_reflection() {
-        let args = vec!["granc", "list", "--uri", "http://localhost:50051"];
-        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
-
-        match cli.command {
-            Commands::List { source } => {
-                assert_eq!(source.uri.unwrap(), "http://localhost:50051");
-                assert!(source.file_descriptor_set.is_none());
-            }
-            _ => panic!("Expected List command"),
+    fn test_call_command_watch_and_timeout() {
+        let args = vec![
+            "granc",
+            "call",
+            "svc/mthd",
+            "-u",
+            "http://localhost:50051",
+            "-b",
+            "{}",
+            "--watch",
+            "500ms",
+            "--timeout",
+            "5s",
+        ];
+
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::Call { watch, timeout, .. } => {
+                assert_eq!(watch, Some(Duration::from_millis(500)));
+                assert_eq!(timeout, Some(Duration::from_secs(5)));
+            }
+            _ => panic!("Expected Call command"),
+        }
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("2s"), Ok(Duration::from_secs(2)));
+        assert_eq!(parse_duration("3"), Ok(Duration::from_secs(3)));
+        assert_eq!(parse_duration("1m"), Ok(Duration::from_secs(60)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert!(parse_duration("2 weeks").is_err());
+        assert!(parse_duration("s").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration_units() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("500m"), Ok(Duration::from_secs(30_000)));
+        assert_eq!(
+            parse_duration(&format!("{}m", u64::MAX)),
+            Err(format!("Duration is too long: '{}m'", u64::MAX))
+        );
+    }
+
+    #[test]
+    fn test_parse_duration_rejects_grpc_units() {
+        for value in ["2S", "500M", "1H", "10u", "10n"] {
+            let err = parse_duration(value).unwrap_err();
+            assert!(
+                err.contains("gRPC timeout units are not supported"),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_list_command_reflection() {
+        let args = vec!["granc", "list", "--uri", "http://localhost:50051"];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::List { source, .. } => {
+                assert_eq!(source.uri.unwrap(), "http://localhost:50051");
+                assert!(source.file_descriptor_set.is_none());
+            }
+            _ => panic!("Expected List command"),
+        }
+    }
+
+    #[test]
+    fn test_list_methods_command() {
+        let args = vec!["granc", "list", "methods", "-u", "http://localhost:50051"];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::List { target, source, .. } => {
+                assert_eq!(target, ListTarget::Methods);
+                assert_eq!(source.uri.unwrap(), "http://localhost:50051");
+            }
+            _ => panic!("Expected List command"),
         }
+
+        let args = vec!["granc", "list", "-u", "http://localhost:50051"];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+        assert!(matches!(
+            cli.command,
+            Commands::List {
+                target: ListTarget::Services,
+                ..
+            }
+        ));
     }
 
     #[test]
@@ -254,7 +1112,7 @@ mod tests {
         let cli = Cli::try_parse_from(&args).expect("Parsing failed");
 
         match cli.command {
-            Commands::List { source } => {
+            Commands::List { source, .. } => {
                 assert_eq!(
                     source.file_descriptor_set.unwrap().to_str().unwrap(),
                     "desc.bin"
@@ -273,16 +1131,335 @@ mod tests {
             "helloworld.Greeter",
             "--uri",
             "http://localhost:50051",
+            "-H",
+            "authorization:Bearer token",
         ];
         let cli = Cli::try_parse_from(&args).expect("Parsing failed");
 
         match cli.command {
-            Commands::Describe { symbol, source } => {
+            Commands::Describe {
+                symbol,
+                source,
+                output,
+                sort_fields,
+                expand,
+                raw,
+                headers,
+            } => {
                 assert_eq!(symbol, "helloworld.Greeter");
                 assert!(source.uri.is_some());
+                assert_eq!(output, OutputFormat::Text);
+                assert!(!sort_fields);
+                assert!(!expand);
+                assert!(!raw);
+                assert_eq!(
+                    headers,
+                    vec![("authorization".to_string(), "Bearer token".to_string())]
+                );
             }
             _ => panic!("Expected Describe command"),
         }
+
+        let args = vec![
+            "granc",
+            "describe",
+            "helloworld.Greeter",
+            "-f",
+            "x",
+            "--raw",
+        ];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+        assert!(matches!(cli.command, Commands::Describe { raw: true, .. }));
+
+        let args = vec!["granc", "describe", "x", "-f", "x", "--raw", "--expand"];
+        assert!(Cli::try_parse_from(&args).is_err());
+    }
+
+    #[test]
+    fn test_file_command() {
+        let args = vec![
+            "granc",
+            "file",
+            "echo.proto",
+            "-u",
+            "http://localhost:50051",
+        ];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::File {
+                filename, source, ..
+            } => {
+                assert_eq!(filename, "echo.proto");
+                assert_eq!(source.uri.unwrap(), "http://localhost:50051");
+            }
+            _ => panic!("Expected File command"),
+        }
+    }
+
+    #[test]
+    fn test_output_json_format() {
+        let args = vec![
+            "granc",
+            "list",
+            "--uri",
+            "http://localhost:50051",
+            "--output",
+            "json",
+        ];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::List { output, .. } => assert_eq!(output, ListFormat::Json),
+            _ => panic!("Expected List command"),
+        }
+    }
+
+    #[test]
+    fn test_list_command_table_with_methods() {
+        let args = vec![
+            "granc",
+            "list",
+            "-f",
+            "desc.bin",
+            "--output",
+            "table",
+            "--with-methods",
+        ];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::List {
+                output,
+                with_methods,
+                ..
+            } => {
+                assert_eq!(output, ListFormat::Table);
+                assert_eq!(OutputFormat::from(output), OutputFormat::Text);
+                assert!(with_methods);
+            }
+            _ => panic!("Expected List command"),
+        }
+    }
+
+    #[test]
+    fn test_list_command_include_reflection() {
+        let args = vec!["granc", "list", "-u", "http://localhost:50051"];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+        assert!(matches!(
+            cli.command,
+            Commands::List {
+                include_reflection: false,
+                ..
+            }
+        ));
+
+        let args = [&args[..], &["--include-reflection"]].concat();
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+        assert!(matches!(
+            cli.command,
+            Commands::List {
+                include_reflection: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_diff_command() {
+        let args = vec![
+            "granc", "diff", "--old", "old.bin", "--new", "new.bin", "--output", "json",
+        ];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::Diff {
+                old, new, output, ..
+            } => {
+                assert!(
+                    matches!(old.value(), Source::File(path) if path.to_str() == Some("old.bin"))
+                );
+                assert!(
+                    matches!(new.value(), Source::File(path) if path.to_str() == Some("new.bin"))
+                );
+                assert_eq!(output, OutputFormat::Json);
+            }
+            _ => panic!("Expected Diff command"),
+        }
+
+        let args = vec!["granc", "diff", "--old-uri", "http://x", "--new", "new.bin"];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::Diff { old, .. } => {
+                assert!(matches!(old.value(), Source::Uri(uri) if uri == "http://x"));
+            }
+            _ => panic!("Expected Diff command"),
+        }
+
+        assert!(Cli::try_parse_from(["granc", "diff", "--old", "old.bin"]).is_err());
+        assert!(
+            Cli::try_parse_from([
+                "granc",
+                "diff",
+                "--old",
+                "a.bin",
+                "--old-uri",
+                "http://x",
+                "--new",
+                "b.bin"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_list_command_summary() {
+        let args = vec!["granc", "list", "-f", "desc.bin", "--summary"];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::List { summary, .. } => assert!(summary),
+            _ => panic!("Expected List command"),
+        }
+
+        let args = vec!["granc", "list", "-f", "x", "--summary", "--with-methods"];
+        assert!(Cli::try_parse_from(&args).is_err());
+    }
+
+    #[test]
+    fn test_no_color_flag_is_global() {
+        let args = vec!["granc", "list", "-f", "desc.bin", "--no-color"];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+        assert!(cli.no_color);
+
+        let args = vec!["granc", "--no-color", "list", "-f", "desc.bin"];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+        assert!(cli.no_color);
+    }
+
+    #[test]
+    fn test_color_choice() {
+        let args = vec!["granc", "list", "-f", "desc.bin"];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+        assert_eq!(cli.color_choice(), ColorChoice::Auto);
+
+        let args = vec!["granc", "--color", "always", "list", "-f", "desc.bin"];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+        assert_eq!(cli.color_choice(), ColorChoice::Always);
+
+        let args = vec!["granc", "list", "-f", "desc.bin", "--no-color"];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+        assert_eq!(cli.color_choice(), ColorChoice::Never);
+    }
+
+    #[test]
+    fn test_health_command() {
+        let args = vec![
+            "granc",
+            "health",
+            "-u",
+            "http://localhost:50051",
+            "--service",
+            "helloworld.Greeter",
+            "-H",
+            "authorization:Bearer abc",
+        ];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::Health {
+                uri,
+                service,
+                headers,
+                ..
+            } => {
+                assert_eq!(uri, "http://localhost:50051");
+                assert_eq!(service.as_deref(), Some("helloworld.Greeter"));
+                assert_eq!(
+                    headers,
+                    vec![("authorization".to_string(), "Bearer abc".to_string())]
+                );
+            }
+            _ => panic!("Expected Health command"),
+        }
+    }
+
+    #[test]
+    fn test_ping_command() {
+        let args = vec!["granc", "ping", "-u", "http://localhost:50051"];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::Ping {
+                uri,
+                no_reflection,
+                headers,
+                output,
+            } => {
+                assert_eq!(uri, "http://localhost:50051");
+                assert!(!no_reflection);
+                assert!(headers.is_empty());
+                assert_eq!(output, OutputFormat::Text);
+            }
+            _ => panic!("Expected Ping command"),
+        }
+
+        let args = vec!["granc", "ping", "-u", "x", "--no-reflection"];
+        match Cli::try_parse_from(&args).expect("Parsing failed").command {
+            Commands::Ping { no_reflection, .. } => assert!(no_reflection),
+            _ => panic!("Expected Ping command"),
+        }
+    }
+
+    #[test]
+    fn test_export_command() {
+        let args = vec![
+            "granc",
+            "export",
+            "helloworld.Greeter",
+            "-u",
+            "http://localhost:50051",
+            "-o",
+            "out.bin",
+        ];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::Export {
+                symbol,
+                all,
+                output,
+                ..
+            } => {
+                assert_eq!(symbol.as_deref(), Some("helloworld.Greeter"));
+                assert!(!all);
+                assert_eq!(output.to_str().unwrap(), "out.bin");
+            }
+            _ => panic!("Expected Export command"),
+        }
+
+        let args = vec!["granc", "export", "--all", "-u", "x", "-o", "out.bin"];
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+        assert!(matches!(
+            cli.command,
+            Commands::Export {
+                symbol: None,
+                all: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_fail_export_requires_symbol_or_all() {
+        let args = vec!["granc", "export", "-u", "x", "-o", "out.bin"];
+        assert!(Cli::try_parse_from(&args).is_err());
+
+        let args = vec![
+            "granc", "export", "svc", "--all", "-u", "x", "-o", "out.bin",
+        ];
+        assert!(Cli::try_parse_from(&args).is_err());
     }
 
     #[test]
@@ -303,10 +1480,18 @@ mod tests {
                 symbol,
                 source,
                 output,
+                single_file,
+                mermaid,
+                sort_fields,
+                headers,
             } => {
                 assert_eq!(symbol, "my.package.Service");
+                assert!(headers.is_empty());
                 assert_eq!(source.uri.unwrap(), "http://localhost:50051");
                 assert_eq!(output.to_str().unwrap(), "./docs");
+                assert!(!sort_fields);
+                assert!(!single_file);
+                assert!(!mermaid);
             }
             _ => panic!("Expected Doc command"),
         }
@@ -330,6 +1515,7 @@ mod tests {
                 symbol,
                 source,
                 output,
+                ..
             } => {
                 assert_eq!(symbol, "my.package.Service");
                 assert_eq!(
@@ -342,31 +1528,791 @@ mod tests {
         }
     }
 
-    // --- Failure Cases ---
-
-    #[test]
-    fn test_fail_invalid_json_body() {
-        let args = vec!["granc", "call", "s/m", "-u", "x", "--body", "{invalid_json"];
-        let err = Cli::try_parse_from(&args).unwrap_err();
-        // Should verify that the error comes from the body parser
-        assert!(err.to_string().contains("Invalid JSON"));
-    }
-
     #[test]
-    fn test_fail_invalid_endpoint_format() {
+    fn test_doc_command_single_file() {
         let args = vec![
             "granc",
-            "call",
-            "OnlyServiceNoMethod", // Missing '/'
-            "-u",
-            "x",
-            "-b",
-            "{}",
-        ];
-        let err = Cli::try_parse_from(&args).unwrap_err();
+            "doc",
+            "my.package.Service",
+            "--file-descriptor-set",
+            "descriptors.bin",
+            "--output",
+            "./docs",
+            "--single-file",
+            "--mermaid",
+        ];
+        let cli = Cli::try_parse_from(args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::Doc {
+                single_file,
+                mermaid,
+                ..
+            } => assert!(single_file && mermaid),
+            _ => panic!("Expected Doc command"),
+        }
+    }
+
+    // --- Failure Cases ---
+
+    #[test]
+    fn test_fail_invalid_json_body() {
+        let err =
+            parse_bodies(vec!["{invalid_json".to_string()], BodyOptions::default()).unwrap_err();
+        assert!(err.contains("Invalid JSON"), "{err}");
+    }
+
+    #[test]
+    fn test_call_command_expand_env() {
+        let args = vec![
+            "granc",
+            "call",
+            "s/m",
+            "-u",
+            "x",
+            "-b",
+            "{}",
+            "--expand-env",
+        ];
+
+        match Cli::try_parse_from(&args).expect("Parsing failed").command {
+            Commands::Call { expand_env, .. } => assert!(expand_env),
+            _ => panic!("Expected Call command"),
+        }
+    }
+
+    #[test]
+    fn test_lenient_json_bodies() {
+        let lenient = BodyOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let body = "\u{feff}{\"tags\": [\"a,]\", \"b\\\",}\",], \"id\": 1,\n}";
+
+        assert_eq!(
+            parse_bodies(vec![body.to_string()], lenient),
+            Ok(serde_json::json!({"tags": ["a,]", "b\",}"], "id": 1}))
+        );
+        assert!(parse_bodies(vec![body.to_string()], BodyOptions::default()).is_err());
+
+        // Commas that are not trailing are still required
+        assert!(parse_bodies(vec![r#"{"a": 1 "b": 2}"#.to_string()], lenient).is_err());
+    }
+
+    #[test]
+    fn test_text_format_bodies() {
+        let pool = granc_core::prost_reflect::DescriptorPool::decode(
+            granc_test_support::echo_service::FILE_DESCRIPTOR_SET,
+        )
+        .unwrap();
+        let input = pool.get_message_by_name("echo.EchoRequest").unwrap();
+        let parse = |bodies: &[&str]| {
+            let bodies = bodies.iter().map(|body| body.to_string()).collect();
+            parse_text_bodies(bodies, &input, BodyOptions::default()).map(|messages| {
+                messages
+                    .iter()
+                    .map(|message| serde_json::to_value(message).unwrap())
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        assert_eq!(
+            parse(&[r#"message: "hello""#]),
+            Ok(vec![serde_json::json!({"message": "hello"})])
+        );
+
+        // One stream message per occurrence
+        assert_eq!(
+            parse(&["message: 'a'", "message: 'b'"]),
+            Ok(vec![
+                serde_json::json!({"message": "a"}),
+                serde_json::json!({"message": "b"})
+            ])
+        );
+
+        assert!(parse(&["ghost: 1"]).is_err());
+    }
+
+    #[test]
+    fn test_call_command_body_format() {
+        let args = vec![
+            "granc",
+            "call",
+            "echo.EchoService/UnaryEcho",
+            "--uri",
+            "http://localhost:50051",
+            "--body",
+            r#"message: "hello""#,
+            "--body-format",
+            "textproto",
+        ];
+        let cli = Cli::try_parse_from(&args).unwrap();
+
+        match cli.command {
+            Commands::Call { body_format, .. } => assert_eq!(body_format, BodyFormat::Textproto),
+            _ => panic!("Expected Call command"),
+        }
+
+        let args = [&args[..], &["--lenient-json"]].concat();
+        assert!(Cli::try_parse_from(&args).is_err());
+    }
+
+    #[test]
+    fn test_expand_vars() {
+        let lookup = |name: &str| (name == "API_TOKEN").then(|| "secret".to_string());
+
+        assert_eq!(
+            expand_vars(
+                r#"{"token": "${API_TOKEN}", "again": "${API_TOKEN}"}"#,
+                lookup
+            ),
+            Ok(r#"{"token": "secret", "again": "secret"}"#.to_string())
+        );
+        assert_eq!(
+            expand_vars(r#"{"price": "$5"}"#, lookup),
+            Ok(r#"{"price": "$5"}"#.to_string())
+        );
+        assert_eq!(
+            expand_vars("${MISSING}", lookup),
+            Err("Environment variable 'MISSING' is not set".to_string())
+        );
+        assert!(expand_vars("${API_TOKEN", lookup).is_err());
+        assert!(expand_vars("${}", lookup).is_err());
+    }
+
+    #[test]
+    fn test_batch_command() {
+        let args = vec![
+            "granc",
+            "batch",
+            "calls.jsonl",
+            "-u",
+            "http://localhost:50051",
+            "-H",
+            "a:b",
+        ];
+
+        match Cli::try_parse_from(&args).expect("Parsing failed").command {
+            Commands::Batch {
+                script,
+                uri,
+                headers,
+                file_descriptor_set,
+                timeout,
+            } => {
+                assert_eq!(script, PathBuf::from("calls.jsonl"));
+                assert_eq!(uri, "http://localhost:50051");
+                assert_eq!(headers, vec![("a".to_string(), "b".to_string())]);
+                assert_eq!(file_descriptor_set, None);
+                assert_eq!(timeout, None);
+            }
+            _ => panic!("Expected Batch command"),
+        }
+    }
+
+    fn parse_with_default_uri(args: &[&str]) -> Result<Cli, clap::Error> {
+        let matches = Cli::command_with_default_uri(Some("http://default:50051"))
+            .try_get_matches_from(args)?;
+        Cli::from_arg_matches(&matches)
+    }
+
+    #[test]
+    fn test_help_and_version_are_handled_without_the_default_uri() {
+        assert!(Cli::help_or_version(["granc", "--help"]).is_some());
+        assert!(Cli::help_or_version(["granc", "--version"]).is_some());
+        assert!(Cli::help_or_version(["granc", "list", "--help"]).is_some());
+
+        // Anything else is left to the parsing that knows the default URI
+        assert!(Cli::help_or_version(["granc", "list"]).is_none());
+        assert!(Cli::help_or_version(["granc", "list", "-u", "x"]).is_none());
+    }
+
+    #[test]
+    fn test_default_uri_is_used_when_missing() {
+        let cli = parse_with_default_uri(&["granc", "call", "s/m", "-b", "{}"]).unwrap();
+        match cli.command {
+            Commands::Call { uri, .. } => assert_eq!(uri, "http://default:50051"),
+            _ => panic!("Expected Call command"),
+        }
+
+        let cli = parse_with_default_uri(&["granc", "list"]).unwrap();
+        match cli.command {
+            Commands::List { source, .. } => {
+                assert!(matches!(source.value(), Source::Uri(uri) if uri == "http://default:50051"))
+            }
+            _ => panic!("Expected List command"),
+        }
+    }
+
+    #[test]
+    fn test_default_uri_is_overridden() {
+        let cli = parse_with_default_uri(&["granc", "health", "-u", "http://cli:1"]).unwrap();
+        match cli.command {
+            Commands::Health { uri, .. } => assert_eq!(uri, "http://cli:1"),
+            _ => panic!("Expected Health command"),
+        }
+
+        // A descriptor file replaces the default URI instead of conflicting with it
+        let cli = parse_with_default_uri(&["granc", "describe", "a.B", "-f", "d.bin"]).unwrap();
+        match cli.command {
+            Commands::Describe { source, .. } => {
+                assert!(
+                    matches!(source.value(), Source::File(path) if path.to_str() == Some("d.bin"))
+                )
+            }
+            _ => panic!("Expected Describe command"),
+        }
+    }
+
+    #[test]
+    fn test_global_connect_timeout() {
+        let args = vec!["granc", "list", "-u", "x", "--connect-timeout", "5s"];
+
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        assert_eq!(cli.connect_timeout, Some(Duration::from_secs(5)));
+        assert!(!cli.grpc_web);
+    }
+
+    #[test]
+    fn test_global_grpc_web() {
+        let args = vec!["granc", "describe", "echo.Echo", "-u", "x", "--grpc-web"];
+
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        assert!(cli.grpc_web);
+    }
+
+    #[test]
+    fn test_global_reflection_host() {
+        let args = vec![
+            "granc",
+            "list",
+            "-u",
+            "x",
+            "--reflection-host",
+            "api.example.com",
+        ];
+
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        assert_eq!(cli.reflection_host.as_deref(), Some("api.example.com"));
+    }
+
+    #[test]
+    fn test_global_plaintext() {
+        let args = vec!["granc", "list", "-u", "localhost:50051", "--plaintext"];
+
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        assert!(cli.plaintext);
+    }
+
+    #[test]
+    fn test_call_command_dry_run() {
+        let args = vec!["granc", "call", "s/m", "-u", "x", "-b", "{}", "--dry-run"];
+
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::Call { dry_run, .. } => assert!(dry_run),
+            _ => panic!("Expected Call command"),
+        }
+
+        let args = vec![
+            "granc",
+            "call",
+            "s/m",
+            "-u",
+            "x",
+            "-b",
+            "{}",
+            "--dry-run",
+            "--watch",
+            "1s",
+        ];
+        assert!(Cli::try_parse_from(&args).is_err());
+    }
+
+    #[test]
+    fn test_call_command_strict() {
+        let args = vec!["granc", "call", "s/m", "-u", "x", "-b", "{}", "--strict"];
+
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::Call { strict, .. } => assert!(strict),
+            _ => panic!("Expected Call command"),
+        }
+    }
+
+    #[test]
+    fn test_call_command_send_interval() {
+        let args = vec![
+            "granc",
+            "call",
+            "s/m",
+            "-u",
+            "x",
+            "-b",
+            "[{}, {}]",
+            "--send-interval",
+            "250ms",
+        ];
+
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::Call { send_interval, .. } => {
+                assert_eq!(send_interval, Some(Duration::from_millis(250)))
+            }
+            _ => panic!("Expected Call command"),
+        }
+    }
+
+    #[test]
+    fn test_call_command_json_array_output() {
+        let args = vec![
+            "granc",
+            "call",
+            "s/m",
+            "-u",
+            "x",
+            "-b",
+            "{}",
+            "--output",
+            "json-array",
+        ];
+
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::Call { output, .. } => {
+                assert_eq!(output, CallFormat::JsonArray);
+                assert_eq!(OutputFormat::from(output), OutputFormat::Json);
+            }
+            _ => panic!("Expected Call command"),
+        }
+    }
+
+    #[test]
+    fn test_call_command_header_file() {
+        let args = vec![
+            "granc",
+            "call",
+            "s/m",
+            "-u",
+            "x",
+            "-b",
+            "{}",
+            "--header-file",
+            "authorization=./token.txt",
+        ];
+
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::Call { header_sources, .. } => assert_eq!(
+                header_sources.header_files,
+                vec![("authorization".to_string(), PathBuf::from("./token.txt"))]
+            ),
+            _ => panic!("Expected Call command"),
+        }
+
+        let args = vec![
+            "granc",
+            "call",
+            "s/m",
+            "-u",
+            "x",
+            "-b",
+            "{}",
+            "--header-file",
+            "authorization",
+        ];
+        assert!(Cli::try_parse_from(&args).is_err());
+    }
+
+    #[test]
+    fn test_call_command_headers_file() {
+        let args = vec![
+            "granc",
+            "call",
+            "s/m",
+            "-u",
+            "x",
+            "-b",
+            "{}",
+            "--headers-file",
+            "headers.json",
+        ];
+
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::Call { header_sources, .. } => {
+                assert_eq!(
+                    header_sources.headers_file,
+                    Some(PathBuf::from("headers.json"))
+                )
+            }
+            _ => panic!("Expected Call command"),
+        }
+    }
+
+    #[test]
+    fn test_call_command_raw() {
+        let args = vec![
+            "granc",
+            "call",
+            "s/m",
+            "-u",
+            "x",
+            "--raw",
+            "req.bin",
+            "--raw-output",
+            "res.bin",
+        ];
+
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::Call {
+                body,
+                raw,
+                raw_output,
+                ..
+            } => {
+                assert!(body.is_empty());
+                assert_eq!(raw, Some(PathBuf::from("req.bin")));
+                assert_eq!(raw_output, Some(PathBuf::from("res.bin")));
+            }
+            _ => panic!("Expected Call command"),
+        }
+
+        // A body is required without `--raw`, and can't be combined with it
+        assert!(Cli::try_parse_from(["granc", "call", "s/m", "-u", "x"]).is_err());
+        assert!(
+            Cli::try_parse_from(["granc", "call", "s/m", "-u", "x", "--raw", "a", "-b", "{}"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_call_command_stdin() {
+        let args = vec!["granc", "call", "s/m", "-u", "x", "--stdin"];
+
+        match Cli::try_parse_from(&args).expect("Parsing failed").command {
+            Commands::Call { stdin, body, .. } => {
+                assert!(stdin);
+                assert!(body.is_empty());
+            }
+            _ => panic!("Expected Call command"),
+        }
+
+        // The messages come from stdin, not from the body
+        assert!(
+            Cli::try_parse_from(["granc", "call", "s/m", "-u", "x", "--stdin", "-b", "{}"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_call_command_body_file() {
+        let args = vec![
+            "granc",
+            "call",
+            "s/m",
+            "-u",
+            "x",
+            "--body-file",
+            "bodies.jsonl",
+        ];
+
+        match Cli::try_parse_from(&args).expect("Parsing failed").command {
+            Commands::Call {
+                body_file, body, ..
+            } => {
+                assert_eq!(body_file, Some(PathBuf::from("bodies.jsonl")));
+                assert!(body.is_empty());
+            }
+            _ => panic!("Expected Call command"),
+        }
+
+        // The bodies come from the file, not from `--body`
+        assert!(
+            Cli::try_parse_from([
+                "granc",
+                "call",
+                "s/m",
+                "-u",
+                "x",
+                "--body-file",
+                "bodies.jsonl",
+                "-b",
+                "{}"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_call_command_body_file_call_options() {
+        let args = vec![
+            "granc",
+            "call",
+            "s/m",
+            "-u",
+            "x",
+            "--body-file",
+            "bodies.jsonl",
+            "--strict",
+            "--send-interval",
+            "100ms",
+            "--max-messages",
+            "3",
+        ];
+
+        match Cli::try_parse_from(&args).expect("Parsing failed").command {
+            Commands::Call {
+                strict,
+                send_interval,
+                response,
+                ..
+            } => {
+                assert!(strict);
+                assert_eq!(send_interval, Some(Duration::from_millis(100)));
+                assert_eq!(response.max_messages, Some(3));
+            }
+            _ => panic!("Expected Call command"),
+        }
+    }
+
+    #[test]
+    fn test_call_command_body_file_conflicts_with_hex_bytes() {
+        let args = [
+            "granc",
+            "call",
+            "s/m",
+            "-u",
+            "x",
+            "--body-file",
+            "bodies.jsonl",
+            "--bytes",
+            "hex",
+        ];
+
+        let err = Cli::try_parse_from(args).unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+
+        // The default `--bytes` doesn't conflict
+        assert!(
+            Cli::try_parse_from(["granc", "call", "s/m", "-u", "x", "--body-file", "b.jsonl"])
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_call_command_fields() {
+        let args = vec![
+            "granc",
+            "call",
+            "s/m",
+            "-u",
+            "x",
+            "-b",
+            "{}",
+            "--fields",
+            "user.name,items.0",
+        ];
+
+        match Cli::try_parse_from(&args).expect("Parsing failed").command {
+            Commands::Call { response, .. } => {
+                assert_eq!(response.fields, ["user.name", "items.0"])
+            }
+            _ => panic!("Expected Call command"),
+        }
+
+        // The raw response bytes can't be projected
+        let raw = vec![
+            "granc", "call", "s/m", "-u", "x", "--raw", "m.bin", "--fields", "a",
+        ];
+        assert!(Cli::try_parse_from(&raw).is_err());
+    }
+
+    #[test]
+    fn test_call_command_max_messages() {
+        let args = vec![
+            "granc",
+            "call",
+            "s/m",
+            "-u",
+            "x",
+            "-b",
+            "{}",
+            "--max-messages",
+            "10",
+        ];
+
+        match Cli::try_parse_from(&args).expect("Parsing failed").command {
+            Commands::Call { response, .. } => assert_eq!(response.max_messages, Some(10)),
+            _ => panic!("Expected Call command"),
+        }
+
+        // Watch mode only supports unary methods
+        let watch = [&args[..], &["--watch", "1s"]].concat();
+        assert!(Cli::try_parse_from(&watch).is_err());
+    }
+
+    #[test]
+    fn test_call_command_message_types() {
+        let args = vec![
+            "granc",
+            "call",
+            "s/m",
+            "-u",
+            "x",
+            "-b",
+            "{}",
+            "-f",
+            "set.bin",
+            "--request-type",
+            "pkg.Req",
+            "--response-type",
+            "pkg.Res",
+        ];
+
+        match Cli::try_parse_from(&args).expect("Parsing failed").command {
+            Commands::Call { message_types, .. } => {
+                assert_eq!(message_types.request_type.as_deref(), Some("pkg.Req"));
+                assert_eq!(message_types.response_type.as_deref(), Some("pkg.Res"));
+            }
+            _ => panic!("Expected Call command"),
+        }
+
+        // Both types are needed, and they can only be looked up in a descriptor set
+        assert!(Cli::try_parse_from(&args[..args.len() - 2]).is_err());
+        let without_file: Vec<_> = args
+            .iter()
+            .filter(|arg| !matches!(**arg, "-f" | "set.bin"))
+            .collect();
+        assert!(Cli::try_parse_from(without_file).is_err());
+    }
+
+    #[test]
+    fn test_call_command_bytes_format() {
+        let args = vec!["granc", "call", "s/m", "-u", "x", "-b", "{}"];
+
+        match Cli::try_parse_from(&args).expect("Parsing failed").command {
+            Commands::Call { bytes, .. } => assert_eq!(bytes, BytesFormat::Base64),
+            _ => panic!("Expected Call command"),
+        }
+
+        let args = vec![
+            "granc", "call", "s/m", "-u", "x", "-b", "{}", "--bytes", "hex",
+        ];
+
+        match Cli::try_parse_from(&args).expect("Parsing failed").command {
+            Commands::Call { bytes, .. } => assert_eq!(bytes, BytesFormat::Hex),
+            _ => panic!("Expected Call command"),
+        }
+    }
+
+    #[test]
+    fn test_call_command_repeated_body() {
+        let args = vec![
+            "granc",
+            "call",
+            "s/m",
+            "-u",
+            "x",
+            "-b",
+            r#"{"id": 1}"#,
+            "--body",
+            r#"{"id": 2}"#,
+        ];
+
+        let cli = Cli::try_parse_from(&args).expect("Parsing failed");
+
+        match cli.command {
+            Commands::Call { body, .. } => {
+                assert_eq!(
+                    parse_bodies(body, BodyOptions::default()),
+                    Ok(serde_json::json!([{"id": 1}, {"id": 2}]))
+                );
+            }
+            _ => panic!("Expected Call command"),
+        }
+    }
+
+    #[test]
+    fn test_join_bodies_single_value_is_unchanged() {
+        let array = serde_json::json!([{"id": 1}, {"id": 2}]);
+        assert_eq!(join_bodies(vec![array.clone()]), array);
+
+        let object = serde_json::json!({"id": 1});
+        assert_eq!(join_bodies(vec![object.clone()]), object);
+    }
+
+    #[test]
+    fn test_fail_call_without_body() {
+        let args = vec!["granc", "call", "s/m", "-u", "x"];
+        assert!(Cli::try_parse_from(&args).is_err());
+    }
+
+    #[test]
+    fn test_fail_invalid_endpoint_format() {
+        let args = vec![
+            "granc",
+            "call",
+            "OnlyServiceNoMethod", // Missing '/'
+            "-u",
+            "x",
+            "-b",
+            "{}",
+        ];
+        let err = Cli::try_parse_from(&args).unwrap_err();
         assert!(err.to_string().contains("Invalid endpoint format"));
     }
 
+    #[test]
+    fn test_parse_endpoint() {
+        let expected = Ok(("a.b.C".to_string(), "D".to_string()));
+
+        assert_eq!(parse_endpoint("a.b.C/D"), expected);
+        assert_eq!(parse_endpoint("/a.b.C/D"), expected);
+    }
+
+    #[test]
+    fn test_fail_endpoint_with_multiple_slashes() {
+        assert!(parse_endpoint("a.b.C/D/E").is_err());
+        assert!(parse_endpoint("//a.b.C/D").is_err());
+        assert!(parse_endpoint("/a.b.C/").is_err());
+        assert!(parse_endpoint("/").is_err());
+    }
+
+    #[test]
+    fn test_fail_invalid_output_format() {
+        let args = vec!["granc", "list", "-u", "http://host", "--output", "yaml"];
+        let err = Cli::try_parse_from(&args).unwrap_err();
+        assert!(err.kind() == clap::error::ErrorKind::InvalidValue);
+    }
+
+    #[test]
+    fn test_fail_color_conflicts_with_no_color() {
+        let args = vec![
+            "granc",
+            "--color",
+            "always",
+            "--no-color",
+            "list",
+            "-f",
+            "desc.bin",
+        ];
+        let err = Cli::try_parse_from(&args).unwrap_err();
+        assert!(err.kind() == clap::error::ErrorKind::ArgumentConflict);
+    }
+
     #[test]
     fn test_fail_list_requires_source() {
         let args = vec!["granc", "list"];
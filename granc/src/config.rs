@@ -0,0 +1,292 @@
+//! # Config
+//!
+//! Defaults read from `~/.config/granc/config.toml` (or `$XDG_CONFIG_HOME/granc/config.toml`),
+//! so the same server and headers don't have to be typed on every command:
+//!
+//! ```toml
+//! # Used when `--uri` is not given and `GRANC_URL` is not set
+//! url = "http://localhost:50051"
+//!
+//! # Sent with every call and reflection request, unless the same header is given with `-H`
+//! [headers]
+//! authorization = "Bearer my-token"
+//! ```
+//!
+//! The file is optional. Only the subset of TOML needed for these settings is understood, one
+//! item per line:
+//!
+//! * Comments, on their own line or after a value.
+//! * The `[headers]` table header.
+//! * `key = value` pairs, where the key is bare, `"quoted"` or `'literal'`, and the value is a
+//!   single-line basic string (`"..."`, with the `\"`, `\\`, `\n` and `\t` escapes) or literal
+//!   string (`'...'`).
+//!
+//! Anything else, like inline tables, arrays of tables, dotted keys outside `[headers]`,
+//! multi-line strings or non-string values, is rejected with the line it was found on. So are
+//! unknown keys and tables: there is no `[tls]` table in particular, since granc only makes
+//! plaintext connections.
+use std::path::PathBuf;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    /// Default server URI.
+    pub url: Option<String>,
+    /// Default headers of calls and reflection requests.
+    pub headers: Vec<(String, String)>,
+}
+
+impl Config {
+    /// Adds the headers of the config file to the `-H` headers of a command.
+    ///
+    /// See [`merge_headers`].
+    pub fn with_default_headers(&self, headers: Vec<(String, String)>) -> Vec<(String, String)> {
+        merge_headers(&self.headers, headers)
+    }
+}
+
+/// Returns `headers` preceded by the `defaults` that they don't override.
+///
+/// Header names are compared case-insensitively.
+pub fn merge_headers(
+    defaults: &[(String, String)],
+    headers: Vec<(String, String)>,
+) -> Vec<(String, String)> {
+    let mut merged: Vec<_> = defaults
+        .iter()
+        .filter(|(key, _)| !headers.iter().any(|(k, _)| k.eq_ignore_ascii_case(key)))
+        .cloned()
+        .collect();
+
+    merged.extend(headers);
+    merged
+}
+
+/// Loads the config file, if there is one.
+///
+/// # Returns
+///
+/// * `Ok(Config)` - The parsed config, or the default one if the file doesn't exist.
+/// * `Err(String)` - If the file can't be read or is invalid.
+pub fn load() -> Result<Config, String> {
+    let Some(path) = path() else {
+        return Ok(Config::default());
+    };
+
+    match std::fs::read_to_string(&path) {
+        Ok(content) => parse(&content).map_err(|e| format!("{}: {e}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(e) => Err(format!("{}: {e}", path.display())),
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_dir.join("granc").join("config.toml"))
+}
+
+/// Parses the content of a config file.
+pub fn parse(content: &str) -> Result<Config, String> {
+    let mut config = Config::default();
+    let mut in_headers = false;
+
+    for (number, line) in content.lines().enumerate() {
+        let error = |message: String| format!("line {}: {message}", number + 1);
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(table) = line.strip_prefix('[') {
+            let table = strip_comment(table)
+                .strip_suffix(']')
+                .ok_or_else(|| error(format!("Invalid table header '{line}'")))?;
+
+            match table.trim() {
+                "headers" => in_headers = true,
+                "tls" => {
+                    return Err(error(
+                        "Unknown table 'tls': TLS connections are not supported".to_string(),
+                    ));
+                }
+                other => return Err(error(format!("Unknown table '{other}'"))),
+            }
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| error(format!("Expected 'key = \"value\"', found '{line}'")))?;
+
+        let key = unquote_key(key.trim());
+        let value = parse_string(value.trim()).map_err(error)?;
+
+        match (in_headers, key.as_str()) {
+            (true, _) => config.headers.push((key, value)),
+            (false, "url") => config.url = Some(value),
+            (false, other) => return Err(error(format!("Unknown key '{other}'"))),
+        }
+    }
+
+    Ok(config)
+}
+
+fn strip_comment(s: &str) -> &str {
+    s.split_once('#').map_or(s, |(before, _)| before).trim()
+}
+
+fn unquote_key(key: &str) -> String {
+    key.strip_prefix('"')
+        .and_then(|k| k.strip_suffix('"'))
+        .or_else(|| key.strip_prefix('\'').and_then(|k| k.strip_suffix('\'')))
+        .unwrap_or(key)
+        .to_string()
+}
+
+// A basic ("...", with escapes) or literal ('...') string, optionally followed by a comment
+fn parse_string(value: &str) -> Result<String, String> {
+    let mut chars = value.chars();
+
+    if value.starts_with("\"\"\"") || value.starts_with("'''") {
+        return Err("Multi-line strings are not supported".to_string());
+    }
+
+    let (parsed, rest) = match chars.next() {
+        Some('\'') => {
+            let literal = chars.as_str();
+            let end = literal
+                .find('\'')
+                .ok_or_else(|| format!("Unterminated string {value}"))?;
+            (literal[..end].to_string(), &literal[end + 1..])
+        }
+        Some('"') => {
+            let mut parsed = String::new();
+
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some('"') => parsed.push('"'),
+                        Some('\\') => parsed.push('\\'),
+                        Some('n') => parsed.push('\n'),
+                        Some('t') => parsed.push('\t'),
+                        other => {
+                            return Err(format!(
+                                "Unsupported escape sequence '\\{}'",
+                                other.unwrap_or(' ')
+                            ));
+                        }
+                    },
+                    Some(c) => parsed.push(c),
+                    None => return Err(format!("Unterminated string {value}")),
+                }
+            }
+
+            (parsed, chars.as_str())
+        }
+        Some('{') => return Err("Inline tables are not supported".to_string()),
+        _ => return Err(format!("Expected a quoted string, found '{value}'")),
+    };
+
+    let rest = rest.trim();
+    if !rest.is_empty() && !rest.starts_with('#') {
+        return Err(format!("Unexpected '{rest}' after the value"));
+    }
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = parse(
+            r#"
+            # Local server
+            url = "http://localhost:50051" # trailing comment
+
+            [headers]
+            authorization = "Bearer \"abc\""
+            "x-trace-id" = 'raw\value'
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config,
+            Config {
+                url: Some("http://localhost:50051".to_string()),
+                headers: vec![
+                    ("authorization".to_string(), "Bearer \"abc\"".to_string()),
+                    ("x-trace-id".to_string(), "raw\\value".to_string()),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_report_the_line() {
+        assert_eq!(
+            parse("url = \"x\"\ntls = \"yes\"").unwrap_err(),
+            "line 2: Unknown key 'tls'"
+        );
+        assert_eq!(
+            parse("[servers]").unwrap_err(),
+            "line 1: Unknown table 'servers'"
+        );
+        assert!(parse("url = http://x").unwrap_err().starts_with("line 1:"));
+        assert!(parse("url = \"x").unwrap_err().starts_with("line 1:"));
+    }
+
+    #[test]
+    fn test_unsupported_syntax_is_rejected() {
+        assert_eq!(
+            parse("[tls]\nca = \"ca.pem\"").unwrap_err(),
+            "line 1: Unknown table 'tls': TLS connections are not supported"
+        );
+        assert_eq!(
+            parse("[[headers]]").unwrap_err(),
+            "line 1: Unknown table '[headers]'"
+        );
+        assert_eq!(
+            parse("headers = { authorization = \"x\" }").unwrap_err(),
+            "line 1: Inline tables are not supported"
+        );
+        assert_eq!(
+            parse("url = \"\"\"http://x\"\"\"").unwrap_err(),
+            "line 1: Multi-line strings are not supported"
+        );
+        assert_eq!(
+            parse("url = 42").unwrap_err(),
+            "line 1: Expected a quoted string, found '42'"
+        );
+    }
+
+    #[test]
+    fn test_cli_headers_override_defaults() {
+        let config = Config {
+            url: None,
+            headers: vec![
+                ("authorization".to_string(), "default".to_string()),
+                ("x-env".to_string(), "dev".to_string()),
+            ],
+        };
+
+        let headers =
+            config.with_default_headers(vec![("Authorization".to_string(), "cli".to_string())]);
+
+        assert_eq!(
+            headers,
+            vec![
+                ("x-env".to_string(), "dev".to_string()),
+                ("Authorization".to_string(), "cli".to_string()),
+            ]
+        );
+    }
+}
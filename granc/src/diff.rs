@@ -0,0 +1,442 @@
+//! # Schema Diff
+//!
+//! Compares two schemas (e.g. the descriptor sets of two versions of an API) and lists what was
+//! added, removed or changed: services, methods, messages, fields, enums and enum values.
+//!
+//! Changes that can break existing clients or servers are flagged as breaking:
+//!
+//! * Anything removed.
+//! * A method whose input or output type, or streaming kind, changed.
+//! * A field whose number, type or cardinality (`repeated`) changed.
+//! * An enum value whose number changed.
+//!
+//! Definitions are matched by their fully qualified name, so a renamed definition is reported as
+//! removed and added.
+use crate::formatter::{json::field_type, method_kind};
+use granc_core::prost_reflect::{
+    DescriptorPool, EnumDescriptor, FieldDescriptor, MessageDescriptor, MethodDescriptor,
+    ServiceDescriptor,
+};
+use std::collections::BTreeMap;
+
+/// How a definition differs between the old and the new schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A single difference between two schemas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    /// What the definition is: `service`, `method`, `message`, `field`, `enum` or `enum value`.
+    pub element: &'static str,
+    /// Fully qualified name (e.g. `my.package.Message.field` or `my.package.Service/Method`).
+    pub name: String,
+    /// What changed, for changed definitions (e.g. `type: string -> int64`).
+    pub detail: Option<String>,
+    pub breaking: bool,
+}
+
+impl Change {
+    fn added(element: &'static str, name: String) -> Self {
+        Change {
+            kind: ChangeKind::Added,
+            element,
+            name,
+            detail: None,
+            breaking: false,
+        }
+    }
+
+    fn removed(element: &'static str, name: String) -> Self {
+        Change {
+            kind: ChangeKind::Removed,
+            element,
+            name,
+            detail: None,
+            breaking: true,
+        }
+    }
+
+    fn changed(element: &'static str, name: String, what: &str, old: String, new: String) -> Self {
+        Change {
+            kind: ChangeKind::Changed,
+            element,
+            name,
+            detail: Some(format!("{what}: {old} -> {new}")),
+            breaking: true,
+        }
+    }
+}
+
+/// Every difference between two schemas, services first, then messages and enums.
+pub struct SchemaDiff(pub Vec<Change>);
+
+impl SchemaDiff {
+    pub fn new(old: &DescriptorPool, new: &DescriptorPool) -> Self {
+        let mut changes = Vec::new();
+
+        compare(
+            by_name(old.services(), ServiceDescriptor::full_name),
+            by_name(new.services(), ServiceDescriptor::full_name),
+            "service",
+            &mut changes,
+            compare_services,
+        );
+        compare(
+            by_name(messages(old), MessageDescriptor::full_name),
+            by_name(messages(new), MessageDescriptor::full_name),
+            "message",
+            &mut changes,
+            compare_messages,
+        );
+        compare(
+            by_name(old.all_enums(), EnumDescriptor::full_name),
+            by_name(new.all_enums(), EnumDescriptor::full_name),
+            "enum",
+            &mut changes,
+            compare_enums,
+        );
+
+        SchemaDiff(changes)
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        self.0.iter().any(|change| change.breaking)
+    }
+}
+
+// Reports the definitions only found on one side, and compares the ones found on both with `changed`
+fn compare<T>(
+    old: BTreeMap<String, T>,
+    mut new: BTreeMap<String, T>,
+    element: &'static str,
+    changes: &mut Vec<Change>,
+    changed: impl Fn(&T, &T, &mut Vec<Change>),
+) {
+    for (name, old) in old {
+        match new.remove(&name) {
+            Some(new) => changed(&old, &new, changes),
+            None => changes.push(Change::removed(element, name)),
+        }
+    }
+
+    changes.extend(new.into_keys().map(|name| Change::added(element, name)));
+}
+
+fn by_name<T>(items: impl Iterator<Item = T>, name: impl Fn(&T) -> &str) -> BTreeMap<String, T> {
+    items.map(|item| (name(&item).to_string(), item)).collect()
+}
+
+// Map entries are part of the type of their field, not definitions of their own
+fn messages(pool: &DescriptorPool) -> impl Iterator<Item = MessageDescriptor> {
+    pool.all_messages()
+        .filter(|message| !message.is_map_entry())
+}
+
+fn compare_services(old: &ServiceDescriptor, new: &ServiceDescriptor, changes: &mut Vec<Change>) {
+    let method_path = |method: &MethodDescriptor| {
+        format!("{}/{}", method.parent_service().full_name(), method.name())
+    };
+
+    compare(
+        old.methods().map(|m| (method_path(&m), m)).collect(),
+        new.methods().map(|m| (method_path(&m), m)).collect(),
+        "method",
+        changes,
+        |old, new, changes| {
+            let name = method_path(new);
+
+            if old.input().full_name() != new.input().full_name() {
+                changes.push(Change::changed(
+                    "method",
+                    name.clone(),
+                    "input",
+                    old.input().full_name().to_string(),
+                    new.input().full_name().to_string(),
+                ));
+            }
+            if old.output().full_name() != new.output().full_name() {
+                changes.push(Change::changed(
+                    "method",
+                    name.clone(),
+                    "output",
+                    old.output().full_name().to_string(),
+                    new.output().full_name().to_string(),
+                ));
+            }
+            if method_kind(old) != method_kind(new) {
+                changes.push(Change::changed(
+                    "method",
+                    name,
+                    "streaming",
+                    method_kind(old).to_string(),
+                    method_kind(new).to_string(),
+                ));
+            }
+        },
+    );
+}
+
+fn compare_messages(old: &MessageDescriptor, new: &MessageDescriptor, changes: &mut Vec<Change>) {
+    compare(
+        by_name(old.fields(), FieldDescriptor::full_name),
+        by_name(new.fields(), FieldDescriptor::full_name),
+        "field",
+        changes,
+        |old, new, changes| {
+            let name = new.full_name().to_string();
+
+            if old.number() != new.number() {
+                changes.push(Change::changed(
+                    "field",
+                    name.clone(),
+                    "number",
+                    old.number().to_string(),
+                    new.number().to_string(),
+                ));
+            }
+            if type_with_cardinality(old) != type_with_cardinality(new) {
+                changes.push(Change::changed(
+                    "field",
+                    name,
+                    "type",
+                    type_with_cardinality(old),
+                    type_with_cardinality(new),
+                ));
+            }
+        },
+    );
+}
+
+fn type_with_cardinality(field: &FieldDescriptor) -> String {
+    if field.is_list() {
+        format!("repeated {}", field_type(field))
+    } else {
+        field_type(field)
+    }
+}
+
+fn compare_enums(old: &EnumDescriptor, new: &EnumDescriptor, changes: &mut Vec<Change>) {
+    // Values are scoped to the package of their enum, named after the enum reads better
+    let values = |e: &EnumDescriptor| {
+        e.values()
+            .map(|value| (format!("{}.{}", e.full_name(), value.name()), value))
+            .collect()
+    };
+
+    compare(
+        values(old),
+        values(new),
+        "enum value",
+        changes,
+        |old, new, changes| {
+            if old.number() != new.number() {
+                changes.push(Change::changed(
+                    "enum value",
+                    format!("{}.{}", new.parent_enum().full_name(), new.name()),
+                    "number",
+                    old.number().to_string(),
+                    new.number().to_string(),
+                ));
+            }
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_types::{
+        DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+        FileDescriptorProto, FileDescriptorSet, MethodDescriptorProto, ServiceDescriptorProto,
+        field_descriptor_proto::{Label, Type},
+    };
+
+    fn field(name: &str, number: i32, r#type: Type, label: Label) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            r#type: Some(r#type as i32),
+            label: Some(label as i32),
+            ..Default::default()
+        }
+    }
+
+    fn message(name: &str, fields: Vec<FieldDescriptorProto>) -> DescriptorProto {
+        DescriptorProto {
+            name: Some(name.to_string()),
+            field: fields,
+            ..Default::default()
+        }
+    }
+
+    fn method(name: &str, input: &str, server_streaming: bool) -> MethodDescriptorProto {
+        MethodDescriptorProto {
+            name: Some(name.to_string()),
+            input_type: Some(format!(".shop.{input}")),
+            output_type: Some(".shop.Item".to_string()),
+            server_streaming: Some(server_streaming),
+            ..Default::default()
+        }
+    }
+
+    fn pool(
+        messages: Vec<DescriptorProto>,
+        methods: Vec<MethodDescriptorProto>,
+        values: &[(&str, i32)],
+    ) -> DescriptorPool {
+        let file = FileDescriptorProto {
+            name: Some("shop.proto".to_string()),
+            package: Some("shop".to_string()),
+            message_type: messages,
+            enum_type: vec![EnumDescriptorProto {
+                name: Some("Status".to_string()),
+                value: values
+                    .iter()
+                    .map(|(name, number)| EnumValueDescriptorProto {
+                        name: Some(name.to_string()),
+                        number: Some(*number),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }],
+            service: vec![ServiceDescriptorProto {
+                name: Some("Shop".to_string()),
+                method: methods,
+                ..Default::default()
+            }],
+            syntax: Some("proto3".to_string()),
+            ..Default::default()
+        };
+
+        DescriptorPool::from_file_descriptor_set(FileDescriptorSet { file: vec![file] })
+            .expect("Invalid test schema")
+    }
+
+    fn old_schema() -> DescriptorPool {
+        pool(
+            vec![
+                message(
+                    "Item",
+                    vec![
+                        field("id", 1, Type::String, Label::Optional),
+                        field("price", 2, Type::Int32, Label::Optional),
+                        field("tags", 3, Type::String, Label::Repeated),
+                    ],
+                ),
+                message("Query", vec![]),
+            ],
+            vec![
+                method("GetItem", "Query", false),
+                method("ListItems", "Query", true),
+            ],
+            &[("UNKNOWN", 0), ("ACTIVE", 1)],
+        )
+    }
+
+    #[test]
+    fn test_identical_schemas_have_no_changes() {
+        let diff = SchemaDiff::new(&old_schema(), &old_schema());
+
+        assert!(diff.0.is_empty());
+        assert!(!diff.is_breaking());
+    }
+
+    #[test]
+    fn test_additions_are_not_breaking() {
+        let new = pool(
+            vec![
+                message(
+                    "Item",
+                    vec![
+                        field("id", 1, Type::String, Label::Optional),
+                        field("price", 2, Type::Int32, Label::Optional),
+                        field("tags", 3, Type::String, Label::Repeated),
+                        field("name", 4, Type::String, Label::Optional),
+                    ],
+                ),
+                message("Query", vec![]),
+            ],
+            vec![
+                method("GetItem", "Query", false),
+                method("ListItems", "Query", true),
+                method("CountItems", "Query", false),
+            ],
+            &[("UNKNOWN", 0), ("ACTIVE", 1), ("ARCHIVED", 2)],
+        );
+
+        let diff = SchemaDiff::new(&old_schema(), &new);
+
+        assert_eq!(
+            diff.0,
+            [
+                Change::added("method", "shop.Shop/CountItems".to_string()),
+                Change::added("field", "shop.Item.name".to_string()),
+                Change::added("enum value", "shop.Status.ARCHIVED".to_string()),
+            ]
+        );
+        assert!(!diff.is_breaking());
+    }
+
+    #[test]
+    fn test_removals_and_incompatible_changes_are_breaking() {
+        let new = pool(
+            vec![message(
+                "Item",
+                vec![
+                    field("id", 1, Type::String, Label::Optional),
+                    field("price", 5, Type::Int64, Label::Optional),
+                    field("tags", 3, Type::String, Label::Optional),
+                ],
+            )],
+            vec![method("GetItem", "Item", false)],
+            &[("UNKNOWN", 0), ("ACTIVE", 2)],
+        );
+
+        let diff = SchemaDiff::new(&old_schema(), &new);
+        let changes: Vec<_> = diff
+            .0
+            .iter()
+            .map(|c| (c.kind, c.name.as_str(), c.detail.as_deref()))
+            .collect();
+
+        assert_eq!(
+            changes,
+            [
+                (
+                    ChangeKind::Changed,
+                    "shop.Shop/GetItem",
+                    Some("input: shop.Query -> shop.Item")
+                ),
+                (ChangeKind::Removed, "shop.Shop/ListItems", None),
+                (
+                    ChangeKind::Changed,
+                    "shop.Item.price",
+                    Some("number: 2 -> 5")
+                ),
+                (
+                    ChangeKind::Changed,
+                    "shop.Item.price",
+                    Some("type: int32 -> int64")
+                ),
+                (
+                    ChangeKind::Changed,
+                    "shop.Item.tags",
+                    Some("type: repeated string -> string")
+                ),
+                (ChangeKind::Removed, "shop.Query", None),
+                (
+                    ChangeKind::Changed,
+                    "shop.Status.ACTIVE",
+                    Some("number: 1 -> 2")
+                ),
+            ]
+        );
+        assert!(diff.0.iter().all(|change| change.breaking));
+    }
+}
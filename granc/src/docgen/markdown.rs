@@ -1,49 +1,96 @@
 use super::package::{Package, Packages};
-use crate::formatter::FormattedString;
-use granc_core::prost_reflect::{EnumDescriptor, Kind, MessageDescriptor, ServiceDescriptor};
+use crate::formatter::{Deprecation, FormattedString, message_definition, ordered_fields};
+use granc_core::prost_reflect::{
+    EnumDescriptor, FieldDescriptor, Kind, MessageDescriptor, ServiceDescriptor,
+};
 use std::fs;
+use std::io::Write;
 use std::path::PathBuf;
 
-pub fn generate(output_dir: PathBuf, service: ServiceDescriptor) -> std::io::Result<()> {
-    // Disable colors for plain text generation
+/// How the generated documentation is split into files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// One `<package>.md` file per package, plus an `index.md`.
+    PerPackage,
+    /// A single `README.md` with a table of contents, linking with internal anchors.
+    SingleFile,
+}
+
+/// Writes the documentation of `service` to `output_dir`, reporting every generated file to `out`.
+///
+/// With `graph`, the index also gets a Mermaid diagram of the dependencies between the definitions.
+/// With `sort_fields`, the fields of messages are listed by field number.
+pub fn generate(
+    output_dir: PathBuf,
+    service: ServiceDescriptor,
+    layout: Layout,
+    graph: bool,
+    sort_fields: bool,
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    // Disable colors for plain text generation, restoring the user's choice afterwards
+    let colorize = colored::control::SHOULD_COLORIZE.should_colorize();
     colored::control::set_override(false);
 
+    let result = write_docs(output_dir, service, layout, graph, sort_fields, out);
+
+    colored::control::set_override(colorize);
+    result
+}
+
+fn write_docs(
+    output_dir: PathBuf,
+    service: ServiceDescriptor,
+    layout: Layout,
+    graph: bool,
+    sort_fields: bool,
+    report: &mut impl Write,
+) -> std::io::Result<()> {
     if !output_dir.exists() {
         fs::create_dir_all(&output_dir)?;
     }
 
     let packages = Packages::from(service.clone());
 
+    if layout == Layout::SingleFile {
+        let out = generate_single_file(&service, &packages, graph, sort_fields)?;
+        fs::write(output_dir.join("README.md"), out)?;
+        writeln!(report, "Generated: README.md")?;
+        return Ok(());
+    }
+
     for package in packages.values() {
-        let filename = format!("{}.md", package.name);
+        let filename = package_file(&package.name);
         let path = output_dir.join(&filename);
 
-        let out = generate_package_file(package)?;
+        let out = generate_package_file(package, layout, sort_fields)?;
 
         fs::write(path, out)?;
-        println!("Generated: {}", filename);
+        writeln!(report, "Generated: {}", filename)?;
     }
 
     let path = output_dir.join("index.md");
-    let out = generate_index(&service, &packages)?;
+    let out = generate_index(&service, &packages, graph)?;
     fs::write(path, out)?;
-    println!("Generated: index.md");
+    writeln!(report, "Generated: index.md")?;
 
-    // Restore colors
-    colored::control::unset_override();
     Ok(())
 }
 
 fn generate_index(
     entry_service: &ServiceDescriptor,
     packages: &Packages,
+    graph: bool,
 ) -> std::io::Result<String> {
     let mut out = String::new();
 
     out.push_str("# Documentation Index\n\n\n");
 
-    let svc_package = entry_service.package_name();
-    let svc_link = format!("{}.md#{}", svc_package, entry_service.name());
+    let svc_link = resolve_link(
+        Layout::PerPackage,
+        entry_service.package_name(),
+        entry_service.name(),
+    );
 
     out.push_str("## Service\n\n");
     out.push_str(&format!("- [**{}**]({})\n", entry_service.name(), svc_link));
@@ -58,23 +105,149 @@ fn generate_index(
         out.push_str("*None*\n");
     } else {
         for name in package_names {
-            out.push_str(&format!("- [{}]({}.md)\n", name, name));
+            let title = if name.is_empty() {
+                "(no package)"
+            } else {
+                name
+            };
+            out.push_str(&format!("- [{}]({})\n", title, package_file(name)));
+        }
+    }
+
+    if graph {
+        out.push('\n');
+        write_graph(&mut out, packages);
+    }
+
+    Ok(out)
+}
+
+// A Mermaid flowchart of the dependencies, with services, messages and enums drawn differently
+fn write_graph(out: &mut String, packages: &Packages) {
+    out.push_str("## Dependency Graph\n\n```mermaid\ngraph TD\n");
+
+    let shapes = |name: &str| match name {
+        name if packages
+            .values()
+            .any(|p| p.services.iter().any(|s| s.full_name() == name)) =>
+        {
+            ("[[", "]]")
+        }
+        name if packages
+            .values()
+            .any(|p| p.enums.iter().any(|e| e.full_name() == name)) =>
+        {
+            ("([", "])")
+        }
+        _ => ("[", "]"),
+    };
+
+    let edges = packages.edges();
+
+    // Full names may contain characters Mermaid doesn't allow in ids, so nodes are numbered
+    let mut nodes: Vec<_> = edges
+        .iter()
+        .flat_map(|(from, to)| [from.as_str(), to.as_str()])
+        .collect();
+    nodes.sort();
+    nodes.dedup();
+
+    let id = |name: &str| nodes.binary_search(&name).expect("A node of the graph");
+
+    for (i, name) in nodes.iter().enumerate() {
+        let (open, close) = shapes(name);
+        out.push_str(&format!("    n{i}{open}\"{name}\"{close}\n"));
+    }
+
+    for (from, to) in &edges {
+        out.push_str(&format!("    n{} --> n{}\n", id(from), id(to)));
+    }
+
+    out.push_str("```\n");
+}
+
+// Every package one after the other, preceded by a table of contents
+fn generate_single_file(
+    entry_service: &ServiceDescriptor,
+    packages: &Packages,
+    graph: bool,
+    sort_fields: bool,
+) -> std::io::Result<String> {
+    let layout = Layout::SingleFile;
+    let mut out = String::new();
+
+    out.push_str(&format!("# {}\n\n", entry_service.full_name()));
+    out.push_str("## Table of Contents\n\n");
+
+    let all_packages = packages;
+    let mut packages: Vec<_> = packages.values().collect();
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for package in &packages {
+        out.push_str(&format!(
+            "- [{}](#{})\n",
+            package.name,
+            package_anchor(&package.name)
+        ));
+
+        for name in sorted_names(package) {
+            out.push_str(&format!(
+                "  - [{}]({})\n",
+                name,
+                resolve_link(layout, &package.name, name)
+            ));
         }
     }
+    out.push('\n');
+
+    if graph {
+        write_graph(&mut out, all_packages);
+        out.push('\n');
+    }
+
+    for package in packages {
+        write_anchor(&mut out, &package_anchor(&package.name));
+        out.push_str(&format!("# Package `{}`\n\n", package.name));
+        out.push_str(&generate_package_file(package, layout, sort_fields)?);
+    }
 
     Ok(out)
 }
 
-fn generate_package_file(package: &Package) -> std::io::Result<String> {
+// The names of the services, messages and enums of a package, in the order they are written
+fn sorted_names(package: &Package) -> Vec<&str> {
+    let mut services: Vec<_> = package.services.iter().map(|s| s.name()).collect();
+    let mut messages: Vec<_> = package.messages.iter().map(|m| m.name()).collect();
+    let mut enums: Vec<_> = package.enums.iter().map(|e| e.name()).collect();
+
+    services.sort();
+    messages.sort();
+    enums.sort();
+
+    services.into_iter().chain(messages).chain(enums).collect()
+}
+
+fn package_anchor(package: &str) -> String {
+    format!("package-{package}")
+}
+
+fn generate_package_file(
+    package: &Package,
+    layout: Layout,
+    sort_fields: bool,
+) -> std::io::Result<String> {
     let mut out = String::new();
 
     let mut services = package.services.clone();
     services.sort_by(|a, b| a.name().cmp(b.name()));
 
     for service in services {
-        write_anchor(&mut out, service.name());
-        out.push_str(&format!("## {}\n\n", service.name()));
-        write_service_content(&mut out, &service);
+        write_anchor(&mut out, &anchor_id(layout, &package.name, service.name()));
+        out.push_str(&format!(
+            "## {}\n\n",
+            strike_if_deprecated(&service, service.name())
+        ));
+        write_service_content(&mut out, &service, layout);
         out.push_str("---\n\n");
     }
 
@@ -82,9 +255,9 @@ fn generate_package_file(package: &Package) -> std::io::Result<String> {
     messages.sort_by(|a, b| a.name().cmp(b.name()));
 
     for message in messages {
-        write_anchor(&mut out, message.name());
+        write_anchor(&mut out, &anchor_id(layout, &package.name, message.name()));
         out.push_str(&format!("## {}\n\n", message.name()));
-        write_message_content(&mut out, &message);
+        write_message_content(&mut out, &message, layout, sort_fields);
         out.push_str("---\n\n");
     }
 
@@ -92,7 +265,10 @@ fn generate_package_file(package: &Package) -> std::io::Result<String> {
     enums.sort_by(|a, b| a.name().cmp(b.name()));
 
     for enum_desc in enums {
-        write_anchor(&mut out, enum_desc.name());
+        write_anchor(
+            &mut out,
+            &anchor_id(layout, &package.name, enum_desc.name()),
+        );
         out.push_str(&format!("## {}\n\n", enum_desc.name()));
         write_enum_content(&mut out, &enum_desc);
         out.push_str("---\n\n");
@@ -101,25 +277,42 @@ fn generate_package_file(package: &Package) -> std::io::Result<String> {
     Ok(out)
 }
 
-fn write_anchor(out: &mut String, name: &str) {
-    out.push_str(&format!("<a id=\"{name}\"></a>\n"));
+fn write_anchor(out: &mut String, id: &str) {
+    out.push_str(&format!("<a id=\"{id}\"></a>\n"));
+}
+
+// In a single file, names are qualified with their package as they may clash across packages
+fn anchor_id(layout: Layout, package: &str, name: &str) -> String {
+    match layout {
+        Layout::PerPackage => name.to_string(),
+        Layout::SingleFile if package.is_empty() => name.to_string(),
+        Layout::SingleFile => format!("{package}.{name}"),
+    }
 }
 
-fn write_service_content(out: &mut String, service: &ServiceDescriptor) {
+fn write_service_content(out: &mut String, service: &ServiceDescriptor, layout: Layout) {
     out.push_str("### Definition\n\n```protobuf\n");
     out.push_str(&format!("package {};\n\n", service.package_name()));
     out.push_str(&FormattedString::from(service.clone()).0);
     out.push_str("\n```\n\n");
 
     out.push_str("### Methods\n\n");
+    if service.methods().len() == 0 {
+        out.push_str("*None*\n\n");
+    }
+
     for method in service.methods() {
-        out.push_str(&format!("#### `{}`\n\n", method.name()));
+        let name = format!("`{}`", method.name());
+        out.push_str(&format!(
+            "#### {}\n\n",
+            strike_if_deprecated(&method, &name)
+        ));
 
         let input = method.input();
         let output = method.output();
 
-        let input_link = resolve_link(input.package_name(), input.name());
-        let output_link = resolve_link(output.package_name(), output.name());
+        let input_link = resolve_link(layout, input.package_name(), input.name());
+        let output_link = resolve_link(layout, output.package_name(), output.name());
 
         out.push_str(&format!("- Request: [{}]({})\n", input.name(), input_link));
         out.push_str(&format!(
@@ -131,33 +324,47 @@ fn write_service_content(out: &mut String, service: &ServiceDescriptor) {
     }
 }
 
-fn write_message_content(out: &mut String, message: &MessageDescriptor) {
+fn write_message_content(
+    out: &mut String,
+    message: &MessageDescriptor,
+    layout: Layout,
+    sort_fields: bool,
+) {
     out.push_str("### Definition\n\n```protobuf\n");
     out.push_str(&format!("package {};\n\n", message.package_name()));
-    out.push_str(&FormattedString::from(message.clone()).0);
+    out.push_str(&message_definition(message.clone(), sort_fields).0);
     out.push_str("\n```\n\n");
 
     out.push_str("### Dependencies\n\n");
     let mut has_deps = false;
 
-    for field in message.fields() {
+    for field in ordered_fields(message.fields(), sort_fields) {
+        // Fields of a oneof are mutually exclusive, which is worth noting next to them
+        let oneof = field
+            .containing_oneof()
+            .filter(|o| !o.is_synthetic())
+            .map(|o| format!(" (oneof `{}`)", o.name()))
+            .unwrap_or_default();
+
         match field.kind() {
             Kind::Message(m) => {
                 has_deps = true;
-                let link = resolve_link(m.package_name(), m.name());
+                let link = resolve_link(layout, m.package_name(), m.name());
                 out.push_str(&format!(
-                    "- Field `{}`: [{}]({})\n",
-                    field.name(),
+                    "- {}{}: [{}]({})\n",
+                    field_label(&field),
+                    oneof,
                     m.name(),
                     link
                 ));
             }
             Kind::Enum(e) => {
                 has_deps = true;
-                let link = resolve_link(e.package_name(), e.name());
+                let link = resolve_link(layout, e.package_name(), e.name());
                 out.push_str(&format!(
-                    "- Field `{}`: [{}]({})\n",
-                    field.name(),
+                    "- {}{}: [{}]({})\n",
+                    field_label(&field),
+                    oneof,
                     e.name(),
                     link
                 ));
@@ -172,6 +379,19 @@ fn write_message_content(out: &mut String, message: &MessageDescriptor) {
     out.push('\n');
 }
 
+fn field_label(field: &FieldDescriptor) -> String {
+    strike_if_deprecated(field, &format!("Field `{}`", field.name()))
+}
+
+// Deprecated definitions are struck through and flagged, so they stand out when skimming
+fn strike_if_deprecated(definition: &impl Deprecation, text: &str) -> String {
+    if definition.is_deprecated() {
+        format!("~~{text}~~ *[deprecated]*")
+    } else {
+        text.to_string()
+    }
+}
+
 fn write_enum_content(out: &mut String, enum_desc: &EnumDescriptor) {
     out.push_str("### Definition\n\n```protobuf\n");
     out.push_str(&format!("package {};\n\n", enum_desc.package_name()));
@@ -179,7 +399,222 @@ fn write_enum_content(out: &mut String, enum_desc: &EnumDescriptor) {
     out.push_str("\n```\n\n");
 }
 
-fn resolve_link(package: &str, name: &str) -> String {
-    // Always link to local file + anchor
-    format!("{}.md#{}", package, name)
+// Definitions without a package still need a file name that isn't hidden
+fn package_file(package: &str) -> String {
+    match package {
+        "" => "_default.md".to_string(),
+        package => format!("{package}.md"),
+    }
+}
+
+fn resolve_link(layout: Layout, package: &str, name: &str) -> String {
+    match layout {
+        // Link to the file of the package + anchor
+        Layout::PerPackage => format!("{}#{}", package_file(package), name),
+        Layout::SingleFile => format!("#{}", anchor_id(layout, package, name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use granc_core::prost_reflect::DescriptorPool;
+    use granc_test_support::compiler;
+    use std::collections::HashMap;
+
+    fn single_file_docs(files: &[(&str, &str)], service: &str) -> String {
+        let pool = DescriptorPool::from_file_descriptor_set(compiler::compile_protos(files))
+            .expect("Failed to decode descriptor pool");
+        let service = pool
+            .get_service_by_name(service)
+            .expect("Service not found");
+
+        generate_single_file(&service, &Packages::from(service.clone()), false, false).unwrap()
+    }
+
+    // The files of the per-package layout, by name
+    fn per_package_docs(files: &[(&str, &str)], service: &str) -> HashMap<String, String> {
+        let pool = DescriptorPool::from_file_descriptor_set(compiler::compile_protos(files))
+            .expect("Failed to decode descriptor pool");
+        let service = pool
+            .get_service_by_name(service)
+            .expect("Service not found");
+        let packages = Packages::from(service.clone());
+
+        let mut docs: HashMap<_, _> = packages
+            .values()
+            .map(|package| {
+                let out = generate_package_file(package, Layout::PerPackage, false).unwrap();
+                (package_file(&package.name), out)
+            })
+            .collect();
+
+        docs.insert(
+            "index.md".to_string(),
+            generate_index(&service, &packages, false).unwrap(),
+        );
+        docs
+    }
+
+    #[test]
+    fn test_per_package_links_across_packages() {
+        let root = r#"
+            syntax = "proto3";
+            message Root { string id = 1; }
+        "#;
+
+        let types = r#"
+            syntax = "proto3";
+            package types;
+            import "google/protobuf/timestamp.proto";
+            import "root.proto";
+            enum Status { UNKNOWN = 0; }
+            message Request {
+                google.protobuf.Timestamp at = 1;
+                Status status = 2;
+                Root root = 3;
+            }
+        "#;
+
+        let api = r#"
+            syntax = "proto3";
+            package api;
+            import "google/protobuf/empty.proto";
+            import "types.proto";
+            service Api { rpc Get(types.Request) returns (google.protobuf.Empty); }
+        "#;
+
+        let docs = per_package_docs(
+            &[
+                ("root.proto", root),
+                ("types.proto", types),
+                ("api.proto", api),
+            ],
+            "api.Api",
+        );
+
+        assert!(docs["api.md"].contains("- Request: [Request](types.md#Request)"));
+        assert!(docs["api.md"].contains("- Response: [Empty](google.protobuf.md#Empty)"));
+        assert!(
+            docs["types.md"].contains("- Field `at`: [Timestamp](google.protobuf.md#Timestamp)")
+        );
+        assert!(docs["types.md"].contains("- Field `root`: [Root](_default.md#Root)"));
+        assert!(docs["index.md"].contains("- [(no package)](_default.md)\n"));
+
+        // Every link points to a generated file, and to an anchor of that file
+        for out in docs.values() {
+            for link in out.split("](").skip(1) {
+                let link = &link[..link.find(')').unwrap()];
+                let (file, anchor) = link.split_once('#').unwrap_or((link, ""));
+                let target = docs
+                    .get(file)
+                    .unwrap_or_else(|| panic!("No file for link '{link}'"));
+
+                if !anchor.is_empty() {
+                    assert!(
+                        target.contains(&format!("<a id=\"{anchor}\"></a>")),
+                        "No anchor for link '{link}'"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_single_file_links_to_internal_anchors() {
+        let common = r#"
+            syntax = "proto3";
+            package common;
+            message Request { string id = 1; }
+        "#;
+
+        let api = r#"
+            syntax = "proto3";
+            package api;
+            import "common.proto";
+            message Request { common.Request inner = 1; }
+            service Api { rpc Get(Request) returns (common.Request); }
+        "#;
+
+        let out = single_file_docs(&[("common.proto", common), ("api.proto", api)], "api.Api");
+
+        // Table of contents, one section per package
+        assert!(out.contains("- [api](#package-api)\n"));
+        assert!(out.contains("  - [Api](#api.Api)\n"));
+        assert!(out.contains("  - [Request](#common.Request)\n"));
+        assert!(out.contains("<a id=\"package-common\"></a>\n# Package `common`"));
+
+        // Anchors are qualified, as both packages define a `Request`
+        assert!(out.contains("<a id=\"api.Request\"></a>"));
+        assert!(out.contains("<a id=\"common.Request\"></a>"));
+        assert!(out.contains("- Request: [Request](#api.Request)"));
+        assert!(out.contains("- Response: [Request](#common.Request)"));
+        assert!(out.contains("- Field `inner`: [Request](#common.Request)"));
+        assert!(!out.contains(".md#"));
+    }
+
+    #[test]
+    fn test_mermaid_graph() {
+        let proto = r#"
+            syntax = "proto3";
+            package shop;
+            enum Size { SMALL = 0; }
+            message Item { Size size = 1; }
+            message Order { repeated Item items = 1; }
+            service Shop { rpc Buy(Order) returns (Item); }
+        "#;
+
+        let pool = DescriptorPool::from_file_descriptor_set(compiler::compile_protos(&[(
+            "shop.proto",
+            proto,
+        )]))
+        .expect("Failed to decode descriptor pool");
+        let service = pool
+            .get_service_by_name("shop.Shop")
+            .expect("Service not found");
+
+        let out = generate_index(&service, &Packages::from(service.clone()), true).unwrap();
+
+        let graph = r#"## Dependency Graph
+
+```mermaid
+graph TD
+    n0["shop.Item"]
+    n1["shop.Order"]
+    n2[["shop.Shop"]]
+    n3(["shop.Size"])
+    n0 --> n3
+    n1 --> n0
+    n2 --> n0
+    n2 --> n1
+```
+"#;
+        assert!(out.ends_with(graph), "{out}");
+    }
+
+    #[test]
+    fn test_deprecated_definitions_are_struck_through() {
+        let proto = r#"
+            syntax = "proto3";
+            package old;
+            message Item { string id = 1; }
+            message Request {
+                Item item = 1 [deprecated = true];
+                Item current = 2;
+            }
+            service Legacy {
+                option deprecated = true;
+                rpc Get(Request) returns (Item) { option deprecated = true; }
+                rpc List(Request) returns (Item);
+            }
+        "#;
+
+        let out = single_file_docs(&[("old.proto", proto)], "old.Legacy");
+
+        assert!(out.contains("## ~~Legacy~~ *[deprecated]*\n"));
+        assert!(out.contains("#### ~~`Get`~~ *[deprecated]*\n"));
+        assert!(out.contains("#### `List`\n"));
+        assert!(out.contains("- ~~Field `item`~~ *[deprecated]*: [Item](#old.Item)"));
+        assert!(out.contains("- Field `current`: [Item](#old.Item)"));
+    }
 }
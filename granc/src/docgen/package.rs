@@ -8,7 +8,7 @@ use granc_core::{
     client::Descriptor,
     prost_reflect::{EnumDescriptor, Kind, MessageDescriptor, ServiceDescriptor},
 };
-use std::collections::{HashMap, hash_map::Keys};
+use std::collections::{BTreeSet, HashMap, hash_map::Keys};
 
 /// Represents a single protobuffer package.
 ///
@@ -62,6 +62,31 @@ impl Packages {
     pub fn names(&self) -> Keys<'_, String, Package> {
         self.0.keys()
     }
+
+    /// The dependencies between the definitions of every package, as `(from, to)` pairs of full names.
+    ///
+    /// A service depends on the requests and responses of its methods, a message on the types of its fields.
+    pub fn edges(&self) -> BTreeSet<(String, String)> {
+        let edge = |from: &str, to: &str| (from.to_string(), to.to_string());
+
+        let services = self.values().flat_map(|p| &p.services).flat_map(|service| {
+            service.methods().flat_map(move |m| {
+                [m.input(), m.output()].map(|d| edge(service.full_name(), d.full_name()))
+            })
+        });
+
+        let messages = self.values().flat_map(|p| &p.messages).flat_map(|message| {
+            message
+                .fields()
+                .filter_map(move |field| match field.kind() {
+                    Kind::Message(m) => Some(edge(message.full_name(), m.full_name())),
+                    Kind::Enum(e) => Some(edge(message.full_name(), e.full_name())),
+                    _ => None,
+                })
+        });
+
+        services.chain(messages).collect()
+    }
 }
 
 impl From<ServiceDescriptor> for Packages {
@@ -202,6 +227,68 @@ mod tests {
         assert_eq!(test_package.enums[0].name(), "Status");
     }
 
+    #[test]
+    fn test_enums_are_keyed_by_type_name() {
+        let proto = r#"
+            syntax = "proto3";
+            package keys;
+
+            message Outer {
+                enum Level {
+                    LOW = 0;
+                    HIGH = 1;
+                }
+            }
+
+            message Request {
+                Outer.Level priority = 1;
+                Outer.Level fallback = 2;
+            }
+
+            service Keyed {
+                rpc Call(Request) returns (Request);
+            }
+        "#;
+
+        let pool = compile_protos(&[("keys.proto", proto)]);
+        let service = pool
+            .get_service_by_name("keys.Keyed")
+            .expect("Service not found");
+
+        let descriptors = collect_service_dependencies(&service);
+
+        assert!(matches!(
+            descriptors.get("keys.Outer.Level"),
+            Some(Descriptor::EnumDescriptor(e)) if e.full_name() == "keys.Outer.Level"
+        ));
+        assert!(!descriptors.contains_key("keys.Request.priority"));
+        assert!(!descriptors.contains_key("keys.Request.fallback"));
+        assert_eq!(descriptors.len(), 2);
+    }
+
+    #[test]
+    fn test_service_without_methods() {
+        let proto = r#"
+            syntax = "proto3";
+            package empty;
+
+            service Nothing {}
+        "#;
+
+        let pool = compile_protos(&[("empty.proto", proto)]);
+        let service = pool
+            .get_service_by_name("empty.Nothing")
+            .expect("Service not found");
+
+        let packages = Packages::from(service);
+
+        let pkg = packages.0.get("empty").expect("Package 'empty' missing");
+
+        assert_eq!(pkg.services.len(), 1);
+        assert_eq!(pkg.messages.len(), 0);
+        assert_eq!(pkg.enums.len(), 0);
+    }
+
     #[test]
     fn test_circular_dependency_handling() {
         let proto = r#"
@@ -240,6 +327,45 @@ mod tests {
         assert!(names.contains(&"NodeB"));
     }
 
+    #[test]
+    fn test_edges() {
+        let proto = r#"
+            syntax = "proto3";
+            package graph;
+
+            enum Kind {
+                UNKNOWN = 0;
+            }
+
+            message Node {
+                Kind kind = 1;
+                Node next = 2;
+                string id = 3;
+            }
+
+            service Walker {
+                rpc Walk(Node) returns (Node);
+            }
+        "#;
+
+        let pool = compile_protos(&[("graph.proto", proto)]);
+        let service = pool
+            .get_service_by_name("graph.Walker")
+            .expect("Service not found");
+
+        let edges: Vec<_> = Packages::from(service).edges().into_iter().collect();
+
+        let edge = |from: &str, to: &str| (from.to_string(), to.to_string());
+        assert_eq!(
+            edges,
+            [
+                edge("graph.Node", "graph.Kind"),
+                edge("graph.Node", "graph.Node"),
+                edge("graph.Walker", "graph.Node"),
+            ]
+        );
+    }
+
     #[test]
     fn test_multi_file_imports() {
         let common_proto = r#"
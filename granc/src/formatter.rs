@@ -1,12 +1,40 @@
+pub mod json;
+pub mod table;
+
+use crate::{
+    diff::{ChangeKind, SchemaDiff},
+    health::HealthStatus,
+};
 use colored::*;
 use granc_core::{
-    client::{Descriptor, DynamicResponse, online, online_without_reflection},
+    client::{
+        Descriptor, DescriptorSetError, DynamicResponse, ResolveMethodError, online,
+        online_without_reflection,
+    },
+    grpc::client::GrpcRequestError,
     prost_reflect::{
-        self, EnumDescriptor, Kind, MessageDescriptor, MethodDescriptor, ServiceDescriptor,
+        Cardinality, EnumDescriptor, EnumValueDescriptor, FieldDescriptor, FileDescriptor, Kind,
+        MessageDescriptor, MethodDescriptor, ReflectMessage, ServiceDescriptor, Syntax,
     },
     tonic::Status,
 };
-use std::fmt::Display;
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    fmt::Display,
+    ops::RangeInclusive,
+};
+
+/// The fields in the order they are rendered: as declared, or by number with `--sort-fields`.
+pub fn ordered_fields(
+    fields: impl Iterator<Item = FieldDescriptor>,
+    sort_fields: bool,
+) -> Vec<FieldDescriptor> {
+    let mut fields: Vec<_> = fields.collect();
+    if sort_fields {
+        fields.sort_by_key(|field| field.number());
+    }
+    fields
+}
 
 /// A wrapper struct for a formatted, colored string.
 ///
@@ -15,8 +43,258 @@ pub struct FormattedString(pub String);
 
 pub struct ServiceList(pub Vec<String>);
 
+/// The result of `granc ping`: whether the reachable server supports reflection (`None` if not checked).
+pub struct Reachability(pub Option<bool>);
+
+/// The services of a server, together with the names of their methods.
+pub struct ServiceMethodsList(pub Vec<ServiceDescriptor>);
+
+/// The services of a server, together with their number of methods of each streaming type.
+pub struct ServiceSummaryList(pub Vec<ServiceDescriptor>);
+
+/// A flat list of methods, printed as `package.Service/Method (kind)`.
+pub struct MethodList(pub Vec<MethodDescriptor>);
+
+/// A validated request that `granc call --dry-run` would send.
+pub struct DryRun {
+    pub method: MethodDescriptor,
+    pub headers: Vec<(String, String)>,
+    /// The messages of the request, as the server would decode them.
+    pub messages: Vec<serde_json::Value>,
+}
+
 pub struct GenericError<T: Display>(pub &'static str, pub T);
 
+/// A symbol, or a method given by its path (`package.Service/Method`), as printed by `granc describe`.
+pub enum Definition {
+    Symbol(Descriptor),
+    Method(MethodDescriptor),
+}
+
+/// A definition followed by the messages and enums it uses, with `describe --expand`.
+pub struct Described {
+    pub definition: Definition,
+    /// Every message and enum used by the definition, transitively, in the order they are found.
+    pub used_types: Vec<Descriptor>,
+    /// Whether the fields of messages are listed by field number (`--sort-fields`).
+    pub sort_fields: bool,
+}
+
+impl Described {
+    pub fn new(definition: Definition, expand: bool, sort_fields: bool) -> Self {
+        let used_types = if expand {
+            used_types(&definition, sort_fields)
+        } else {
+            vec![]
+        };
+
+        Described {
+            definition,
+            used_types,
+            sort_fields,
+        }
+    }
+}
+
+/// The file declaring a definition and the files it depends on, transitively, as the
+/// `FileDescriptorSet` they were resolved from (dependencies first), for `describe --raw`.
+pub fn raw_files(definition: &Definition) -> serde_json::Value {
+    let file = match definition {
+        Definition::Symbol(Descriptor::MessageDescriptor(message)) => message.parent_file(),
+        Definition::Symbol(Descriptor::ServiceDescriptor(service)) => service.parent_file(),
+        Definition::Symbol(Descriptor::EnumDescriptor(enum_desc)) => enum_desc.parent_file(),
+        Definition::Method(method) => method.parent_file(),
+    };
+
+    let mut seen = HashSet::new();
+    let mut files = vec![];
+    collect_files(file, &mut seen, &mut files);
+
+    let set = prost_types::FileDescriptorSet { file: files };
+    serde_json::to_value(set.transcode_to_dynamic()).unwrap_or_default()
+}
+
+fn collect_files(
+    file: FileDescriptor,
+    seen: &mut HashSet<String>,
+    files: &mut Vec<prost_types::FileDescriptorProto>,
+) {
+    if !seen.insert(file.name().to_string()) {
+        return;
+    }
+    for dependency in file.dependencies() {
+        collect_files(dependency, seen, files);
+    }
+    files.push(file.file_descriptor_proto().clone());
+}
+
+/// The messages and enums used by the fields of a definition (and by the methods of a service),
+/// transitively. Map entries are skipped in favour of their value types.
+fn used_types(definition: &Definition, sort_fields: bool) -> Vec<Descriptor> {
+    let mut seen = HashSet::new();
+    let mut used = vec![];
+    let mut messages = VecDeque::new();
+
+    let roots = match definition {
+        Definition::Symbol(Descriptor::MessageDescriptor(message)) => {
+            // The message itself is printed already
+            seen.insert(message.full_name().to_string());
+            messages.push_back(message.clone());
+            vec![]
+        }
+        Definition::Symbol(Descriptor::ServiceDescriptor(service)) => service
+            .methods()
+            .flat_map(|m| [m.input(), m.output()])
+            .collect(),
+        Definition::Symbol(Descriptor::EnumDescriptor(_)) => vec![],
+        Definition::Method(method) => vec![method.input(), method.output()],
+    };
+
+    for message in roots {
+        visit_message(message, &mut seen, &mut used, &mut messages);
+    }
+
+    while let Some(message) = messages.pop_front() {
+        for field in ordered_fields(message.fields(), sort_fields) {
+            match field.kind() {
+                Kind::Message(m) => visit_message(m, &mut seen, &mut used, &mut messages),
+                Kind::Enum(e) if seen.insert(e.full_name().to_string()) => {
+                    used.push(Descriptor::EnumDescriptor(e))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    used
+}
+
+fn visit_message(
+    message: MessageDescriptor,
+    seen: &mut HashSet<String>,
+    used: &mut Vec<Descriptor>,
+    messages: &mut VecDeque<MessageDescriptor>,
+) {
+    if seen.insert(message.full_name().to_string()) {
+        if !message.is_map_entry() {
+            used.push(Descriptor::MessageDescriptor(message.clone()));
+        }
+        messages.push_back(message);
+    }
+}
+
+/// The fields of response messages to print with `--fields`, as dot paths (e.g. `user.name`).
+///
+/// Numeric segments index into arrays (e.g. `items.0.id`), while other segments apply to every
+/// element of an array. Paths that match nothing are omitted.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FieldProjection {
+    /// Whether the whole value is kept, when a path ends here.
+    whole: bool,
+    children: BTreeMap<String, FieldProjection>,
+}
+
+impl FieldProjection {
+    pub fn new(paths: &[String]) -> Self {
+        let mut projection = Self::default();
+
+        for path in paths.iter().filter(|path| !path.trim().is_empty()) {
+            let mut node = &mut projection;
+            for segment in path.split('.').map(str::trim) {
+                if node.whole {
+                    break;
+                }
+                node = node.children.entry(segment.to_string()).or_default();
+            }
+            // A path to a value replaces the longer paths into it
+            node.whole = true;
+            node.children.clear();
+        }
+
+        projection
+    }
+
+    /// Whether no path was given, in which case values are kept as they are.
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty() && !self.whole
+    }
+
+    /// The value with only the projected fields, or an empty object if none of them exists.
+    pub fn apply(&self, value: serde_json::Value) -> serde_json::Value {
+        if self.is_empty() {
+            return value;
+        }
+
+        self.project(&value)
+            .unwrap_or_else(|| serde_json::Value::Object(Default::default()))
+    }
+
+    fn project(&self, value: &serde_json::Value) -> Option<serde_json::Value> {
+        if self.whole {
+            return Some(value.clone());
+        }
+
+        match value {
+            serde_json::Value::Object(fields) => {
+                let projected: serde_json::Map<_, _> = fields
+                    .iter()
+                    .filter_map(|(name, field)| {
+                        let child = self.children.get(name)?;
+                        Some((name.clone(), child.project(field)?))
+                    })
+                    .collect();
+
+                (!projected.is_empty()).then_some(serde_json::Value::Object(projected))
+            }
+            serde_json::Value::Array(items) => {
+                // Segments that are not indexes apply to every element
+                let every = FieldProjection {
+                    whole: false,
+                    children: self
+                        .children
+                        .iter()
+                        .filter(|(segment, _)| segment.parse::<usize>().is_err())
+                        .map(|(segment, child)| (segment.clone(), child.clone()))
+                        .collect(),
+                };
+
+                let projected: Vec<_> = items
+                    .iter()
+                    .enumerate()
+                    .filter_map(
+                        |(index, item)| match self.children.get(&index.to_string()) {
+                            Some(child) => child.clone().merge(every.clone()).project(item),
+                            None => every.project(item),
+                        },
+                    )
+                    .collect();
+
+                (!projected.is_empty()).then_some(serde_json::Value::Array(projected))
+            }
+            _ => None,
+        }
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        if self.whole || other.whole {
+            return FieldProjection {
+                whole: true,
+                children: BTreeMap::new(),
+            };
+        }
+
+        for (segment, child) in other.children {
+            let merged = match self.children.remove(&segment) {
+                Some(existing) => existing.merge(child),
+                None => child,
+            };
+            self.children.insert(segment, merged);
+        }
+
+        self
+    }
+}
+
 impl std::fmt::Display for FormattedString {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f)?;
@@ -27,27 +305,90 @@ impl std::fmt::Display for FormattedString {
 
 impl From<serde_json::Value> for FormattedString {
     fn from(value: serde_json::Value) -> Self {
-        FormattedString(serde_json::to_string_pretty(&value).unwrap_or_else(|_| value.to_string()))
+        let mut out = String::new();
+        write_highlighted_json(&mut out, &value, 0);
+        FormattedString(out)
     }
 }
 
+/// Pretty prints a JSON value with the same layout as `serde_json::to_string_pretty`,
+/// coloring keys, strings, numbers, booleans and nulls differently.
+///
+/// When colors are disabled the output is identical to the plain pretty printed JSON.
+fn write_highlighted_json(out: &mut String, value: &serde_json::Value, indent: usize) {
+    use serde_json::Value;
+
+    let pad = "  ".repeat(indent + 1);
+    let closing_pad = "  ".repeat(indent);
+
+    match value {
+        Value::Null => out.push_str(&"null".purple().to_string()),
+        Value::Bool(b) => out.push_str(&b.to_string().yellow().to_string()),
+        Value::Number(n) => out.push_str(&n.to_string().cyan().to_string()),
+        Value::String(s) => out.push_str(&json_string(s).green().to_string()),
+        Value::Array(items) if items.is_empty() => out.push_str("[]"),
+        Value::Array(items) => {
+            out.push_str("[\n");
+            for (i, item) in items.iter().enumerate() {
+                out.push_str(&pad);
+                write_highlighted_json(out, item, indent + 1);
+                if i + 1 < items.len() {
+                    out.push(',');
+                }
+                out.push('\n');
+            }
+            out.push_str(&closing_pad);
+            out.push(']');
+        }
+        Value::Object(map) if map.is_empty() => out.push_str("{}"),
+        Value::Object(map) => {
+            out.push_str("{\n");
+            for (i, (key, item)) in map.iter().enumerate() {
+                out.push_str(&pad);
+                out.push_str(&json_string(key).blue().bold().to_string());
+                out.push_str(": ");
+                write_highlighted_json(out, item, indent + 1);
+                if i + 1 < map.len() {
+                    out.push(',');
+                }
+                out.push('\n');
+            }
+            out.push_str(&closing_pad);
+            out.push('}');
+        }
+    }
+}
+
+// Quotes and escapes a string as a JSON string literal
+fn json_string(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or_else(|_| format!("{s:?}"))
+}
+
 impl From<Status> for FormattedString {
     fn from(status: Status) -> Self {
-        FormattedString(format!(
+        let mut out = format!(
             "{} code={:?} message={:?}",
             "gRPC Failed:".red().bold(),
             status.code(),
             status.message()
-        ))
+        );
+        for detail in json::status_details(&status) {
+            out.push_str(&format!("\n  {} {}", "detail:".dimmed(), detail));
+        }
+        FormattedString(out)
     }
 }
 
 impl From<DynamicResponse> for FormattedString {
     fn from(value: DynamicResponse) -> Self {
         match value {
-            DynamicResponse::Unary(Ok(value)) => FormattedString::from(value),
-            DynamicResponse::Unary(Err(status)) => FormattedString::from(status),
-            DynamicResponse::Streaming(Ok(values)) => {
+            DynamicResponse::Unary(_, Ok(value)) => FormattedString::from(value),
+            DynamicResponse::Unary(_, Err(status)) => FormattedString::from(status),
+            // Otherwise nothing would be printed, leaving it unclear whether the call succeeded
+            DynamicResponse::Streaming(_, Ok(values)) if values.is_empty() => {
+                FormattedString("(empty stream)".dimmed().to_string())
+            }
+            DynamicResponse::Streaming(_, Ok(values)) => {
                 let mut s = String::new();
                 for elem in values {
                     match elem {
@@ -57,7 +398,7 @@ impl From<DynamicResponse> for FormattedString {
                 }
                 FormattedString(s)
             }
-            DynamicResponse::Streaming(Err(status)) => FormattedString::from(status),
+            DynamicResponse::Streaming(_, Err(status)) => FormattedString::from(status),
         }
     }
 }
@@ -76,8 +417,14 @@ impl From<online_without_reflection::DynamicCallError> for FormattedString {
     }
 }
 
-impl From<prost_reflect::DescriptorError> for FormattedString {
-    fn from(err: prost_reflect::DescriptorError) -> Self {
+impl From<GrpcRequestError> for FormattedString {
+    fn from(err: GrpcRequestError) -> Self {
+        FormattedString(format!("{}\n\n'{}'", "Call Failed:".red().bold(), err))
+    }
+}
+
+impl From<DescriptorSetError> for FormattedString {
+    fn from(err: DescriptorSetError) -> Self {
         FormattedString(format!(
             "{}\n\n'{}'",
             "Failed to parse file descriptor:".red().bold(),
@@ -108,6 +455,15 @@ impl From<online::ClientConnectError> for FormattedString {
     }
 }
 
+impl From<ResolveMethodError> for FormattedString {
+    fn from(err: ResolveMethodError) -> Self {
+        FormattedString(format!(
+            "{}\n\n'{}'",
+            "Method Lookup Failed:".red().bold(),
+            err
+        ))
+    }
+}
 impl From<online::GetDescriptorError> for FormattedString {
     fn from(err: online::GetDescriptorError) -> Self {
         FormattedString(format!(
@@ -118,6 +474,67 @@ impl From<online::GetDescriptorError> for FormattedString {
     }
 }
 
+impl From<SchemaDiff> for FormattedString {
+    fn from(SchemaDiff(changes): SchemaDiff) -> Self {
+        if changes.is_empty() {
+            return FormattedString("No schema changes.".green().to_string());
+        }
+
+        let mut out = String::from("Schema changes:\n");
+        for change in &changes {
+            let marker = match change.kind {
+                ChangeKind::Added => "+".green(),
+                ChangeKind::Removed => "-".red(),
+                ChangeKind::Changed => "~".yellow(),
+            };
+            let detail = change
+                .detail
+                .as_ref()
+                .map(|detail| format!(" ({detail})"))
+                .unwrap_or_default();
+            let breaking = if change.breaking {
+                format!(" {}", "BREAKING".red().bold())
+            } else {
+                "".to_string()
+            };
+
+            out.push_str(&format!(
+                "  {marker} {} {}{detail}{breaking}\n",
+                change.element,
+                change.name.bold()
+            ));
+        }
+
+        let breaking = changes.iter().filter(|change| change.breaking).count();
+        out.push_str(&format!("\n{} changes, {breaking} breaking", changes.len()));
+
+        FormattedString(out)
+    }
+}
+
+impl From<HealthStatus> for FormattedString {
+    fn from(HealthStatus(status): HealthStatus) -> Self {
+        let status = match status.as_str() {
+            "SERVING" => status.green().bold(),
+            "NOT_SERVING" => status.red().bold(),
+            _ => status.yellow().bold(),
+        };
+        FormattedString(status.to_string())
+    }
+}
+
+impl From<Reachability> for FormattedString {
+    fn from(Reachability(reflection): Reachability) -> Self {
+        let reachable = "reachable".green().bold();
+
+        FormattedString(match reflection {
+            Some(true) => format!("{reachable}, reflection: {}", "yes".green()),
+            Some(false) => format!("{reachable}, reflection: {}", "no".yellow()),
+            None => reachable.to_string(),
+        })
+    }
+}
+
 impl From<ServiceList> for FormattedString {
     fn from(ServiceList(services): ServiceList) -> Self {
         if services.is_empty() {
@@ -133,13 +550,224 @@ impl From<ServiceList> for FormattedString {
     }
 }
 
+impl From<ServiceMethodsList> for FormattedString {
+    fn from(ServiceMethodsList(services): ServiceMethodsList) -> Self {
+        if services.is_empty() {
+            return FormattedString("No services found.".yellow().to_string());
+        }
+
+        let mut out = String::new();
+        out.push_str("Available Services:\n");
+        for svc in services {
+            out.push_str(&format!("  - {}\n", svc.full_name().green()));
+            for method in svc.methods() {
+                out.push_str(&format!("      {}\n", method.name()));
+            }
+        }
+        FormattedString(out.trim_end().to_string())
+    }
+}
+
+impl From<ServiceSummaryList> for FormattedString {
+    fn from(ServiceSummaryList(services): ServiceSummaryList) -> Self {
+        if services.is_empty() {
+            return FormattedString("No services found.".yellow().to_string());
+        }
+
+        let mut out = String::new();
+        out.push_str("Available Services:\n");
+        for svc in services {
+            let count = svc.methods().len();
+            let kinds: Vec<_> = method_kind_counts(&svc)
+                .into_iter()
+                .filter(|(_, n)| *n > 0)
+                .map(|(kind, n)| format!("{n} {kind}"))
+                .collect();
+
+            out.push_str(&format!(
+                "  - {}: {} {}",
+                svc.full_name().green(),
+                count.to_string().cyan(),
+                if count == 1 { "method" } else { "methods" }
+            ));
+            if !kinds.is_empty() {
+                out.push_str(&format!(" ({})", kinds.join(", ")));
+            }
+            out.push('\n');
+        }
+        FormattedString(out.trim_end().to_string())
+    }
+}
+
+impl From<MethodList> for FormattedString {
+    fn from(MethodList(methods): MethodList) -> Self {
+        if methods.is_empty() {
+            return FormattedString("No methods found.".yellow().to_string());
+        }
+
+        let lines: Vec<_> = methods
+            .iter()
+            .map(|m| format!("{} ({})", method_path(m).green(), method_kind(m).cyan()))
+            .collect();
+        FormattedString(lines.join("\n"))
+    }
+}
+
+impl From<DryRun> for FormattedString {
+    fn from(dry_run: DryRun) -> Self {
+        let mut out = format!(
+            "{} {} ({})\n",
+            "Dry run:".yellow().bold(),
+            method_path(&dry_run.method).green(),
+            method_kind(&dry_run.method).cyan()
+        );
+
+        for (key, value) in &dry_run.headers {
+            out.push_str(&format!("{}: {value}\n", key.cyan()));
+        }
+
+        for message in dry_run.messages {
+            out.push('\n');
+            out.push_str(&FormattedString::from(message).0);
+        }
+
+        FormattedString(out)
+    }
+}
+
+/// Returns the path of a method as used in gRPC calls (e.g. `package.Service/Method`).
+fn method_path(method: &MethodDescriptor) -> String {
+    format!("{}/{}", method.parent_service().full_name(), method.name())
+}
+
+// The number of methods of a service of every kind, in the order of `method_kind`
+fn method_kind_counts(service: &ServiceDescriptor) -> [(&'static str, usize); 4] {
+    let mut counts = [
+        ("unary", 0),
+        ("server-stream", 0),
+        ("client-stream", 0),
+        ("bidi", 0),
+    ];
+
+    for method in service.methods() {
+        let kind = method_kind(&method);
+        if let Some((_, n)) = counts.iter_mut().find(|(k, _)| *k == kind) {
+            *n += 1;
+        }
+    }
+
+    counts
+}
+
+/// Returns the streaming type of a method: `unary`, `server-stream`, `client-stream` or `bidi`.
+pub(crate) fn method_kind(method: &MethodDescriptor) -> &'static str {
+    match (method.is_client_streaming(), method.is_server_streaming()) {
+        (false, false) => "unary",
+        (false, true) => "server-stream",
+        (true, false) => "client-stream",
+        (true, true) => "bidi",
+    }
+}
+
+impl From<FileDescriptor> for FormattedString {
+    fn from(file: FileDescriptor) -> Self {
+        let mut out = format!("{} {}\n", "file".cyan(), file.name().green());
+
+        if !file.package_name().is_empty() {
+            out.push_str(&format!("{} {};\n", "package".cyan(), file.package_name()));
+        }
+
+        for dependency in file.dependencies() {
+            out.push_str(&format!("{} \"{}\";\n", "import".cyan(), dependency.name()));
+        }
+
+        let definitions = file
+            .services()
+            .map(FormattedString::from)
+            .chain(file.messages().map(FormattedString::from))
+            .chain(file.enums().map(FormattedString::from));
+
+        for definition in definitions {
+            out.push('\n');
+            out.push_str(&definition.0);
+            out.push('\n');
+        }
+
+        FormattedString(out.trim_end().to_string())
+    }
+}
+
+impl From<Described> for FormattedString {
+    fn from(described: Described) -> Self {
+        let sort_fields = described.sort_fields;
+        let definition = match described.definition {
+            Definition::Symbol(d) => descriptor_definition(d, sort_fields),
+            Definition::Method(d) => FormattedString::from(d),
+        };
+
+        let mut out = definition.0;
+        for descriptor in described.used_types {
+            out.push_str("\n\n");
+            out.push_str(&descriptor_definition(descriptor, sort_fields).0);
+        }
+        FormattedString(out)
+    }
+}
+
 impl From<Descriptor> for FormattedString {
     fn from(value: Descriptor) -> Self {
-        match value {
-            Descriptor::MessageDescriptor(d) => FormattedString::from(d),
-            Descriptor::ServiceDescriptor(d) => FormattedString::from(d),
-            Descriptor::EnumDescriptor(d) => FormattedString::from(d),
-        }
+        descriptor_definition(value, false)
+    }
+}
+
+// A service, message or enum as declared in a proto file, see `message_definition`
+fn descriptor_definition(descriptor: Descriptor, sort_fields: bool) -> FormattedString {
+    match descriptor {
+        Descriptor::MessageDescriptor(d) => message_definition(d, sort_fields),
+        Descriptor::ServiceDescriptor(d) => FormattedString::from(d),
+        Descriptor::EnumDescriptor(d) => FormattedString::from(d),
+    }
+}
+
+/// Whether a definition is marked with the `deprecated = true` option.
+pub trait Deprecation {
+    fn is_deprecated(&self) -> bool;
+}
+
+impl Deprecation for ServiceDescriptor {
+    fn is_deprecated(&self) -> bool {
+        let options = self.service_descriptor_proto().options.as_ref();
+        options.is_some_and(|o| o.deprecated())
+    }
+}
+
+impl Deprecation for MethodDescriptor {
+    fn is_deprecated(&self) -> bool {
+        let options = self.method_descriptor_proto().options.as_ref();
+        options.is_some_and(|o| o.deprecated())
+    }
+}
+
+impl Deprecation for FieldDescriptor {
+    fn is_deprecated(&self) -> bool {
+        let options = self.field_descriptor_proto().options.as_ref();
+        options.is_some_and(|o| o.deprecated())
+    }
+}
+
+impl Deprecation for EnumValueDescriptor {
+    fn is_deprecated(&self) -> bool {
+        let options = self.enum_value_descriptor_proto().options.as_ref();
+        options.is_some_and(|o| o.deprecated())
+    }
+}
+
+// A dim ` (deprecated)` marker for deprecated definitions, nothing otherwise
+fn deprecated_marker(definition: &impl Deprecation) -> String {
+    if definition.is_deprecated() {
+        format!(" {}", "(deprecated)".dimmed())
+    } else {
+        "".to_string()
     }
 }
 
@@ -147,9 +775,10 @@ impl From<ServiceDescriptor> for FormattedString {
     fn from(service: ServiceDescriptor) -> Self {
         let mut out = String::new();
         out.push_str(&format!(
-            "{} {} {{\n",
+            "{} {}{} {{\n",
             "service".cyan(),
-            service.name().green()
+            service.name().green(),
+            deprecated_marker(&service)
         ));
 
         for method in service.methods() {
@@ -178,74 +807,191 @@ impl From<MethodDescriptor> for FormattedString {
         };
 
         FormattedString(format!(
-            "{} {}({}{}) {} ({}{});",
+            "{} {}({}{}) {} ({}{});{}",
             "rpc".cyan(),
             method.name().green(),
             input_stream,
             method.input().full_name().yellow(),
             "returns".cyan(),
             output_stream,
-            method.output().full_name().yellow()
+            method.output().full_name().yellow(),
+            deprecated_marker(&method)
         ))
     }
 }
 
 impl From<MessageDescriptor> for FormattedString {
     fn from(message: MessageDescriptor) -> Self {
-        let mut out = String::new();
-        out.push_str(&format!(
-            "{} {} {{\n",
-            "message".cyan(),
-            message.name().green()
-        ));
+        message_definition(message, false)
+    }
+}
 
-        for field in message.fields() {
-            let label = if field.is_list() {
-                format!("{} ", "repeated".cyan())
-            } else {
-                "".to_string()
-            };
+/// A message as declared in a proto file, with its fields in declaration order or, with
+/// `sort_fields`, by field number.
+pub fn message_definition(message: MessageDescriptor, sort_fields: bool) -> FormattedString {
+    let mut out = String::new();
+    out.push_str(&format!(
+        "{} {} {{\n",
+        "message".cyan(),
+        message.name().green()
+    ));
 
-            let type_name = match field.kind() {
-                Kind::Double => "double".yellow(),
-                Kind::Float => "float".yellow(),
-                Kind::Int32 => "int32".yellow(),
-                Kind::Int64 => "int64".yellow(),
-                Kind::Uint32 => "uint32".yellow(),
-                Kind::Uint64 => "uint64".yellow(),
-                Kind::Sint32 => "sint32".yellow(),
-                Kind::Sint64 => "sint64".yellow(),
-                Kind::Fixed32 => "fixed32".yellow(),
-                Kind::Fixed64 => "fixed64".yellow(),
-                Kind::Sfixed32 => "sfixed32".yellow(),
-                Kind::Sfixed64 => "sfixed64".yellow(),
-                Kind::Bool => "bool".yellow(),
-                Kind::String => "string".yellow(),
-                Kind::Bytes => "bytes".yellow(),
-                Kind::Message(m) => m.full_name().yellow(),
-                Kind::Enum(e) => e.full_name().yellow(),
-            };
+    // Fields of a oneof are grouped under it, where its first field is declared
+    let mut rendered_oneofs = HashSet::new();
 
-            if field.is_map() {
-                out.push_str(&format!(
-                    "  // map entry: {} {} = {};\n",
-                    type_name,
-                    field.name(),
-                    field.number()
-                ));
-            } else {
-                out.push_str(&format!(
-                    "  {}{}{} {} = {};\n",
-                    label,
-                    type_name,
-                    " ".normal(), // Reset color
-                    field.name(),
-                    field.number()
-                ));
+    for field in ordered_fields(message.fields(), sort_fields) {
+        match field.containing_oneof() {
+            Some(oneof) if !oneof.is_synthetic() => {
+                if rendered_oneofs.insert(oneof.name().to_string()) {
+                    out.push_str(&format!("  {} {} {{\n", "oneof".cyan(), oneof.name()));
+                    for field in ordered_fields(oneof.fields(), sort_fields) {
+                        let definition = field_definition(&field, sort_fields);
+                        out.push_str(&indented(&definition, "    "));
+                    }
+                    out.push_str("  }\n");
+                }
             }
+            _ => out.push_str(&indented(&field_definition(&field, sort_fields), "  ")),
         }
-        out.push('}');
-        FormattedString(out)
+    }
+    out.push('}');
+    FormattedString(out)
+}
+
+// Prefixes every line of a definition, which spans several lines for proto2 groups
+fn indented(definition: &str, indent: &str) -> String {
+    definition
+        .lines()
+        .map(|line| format!("{indent}{line}\n"))
+        .collect()
+}
+
+// Renders a field as declared in a proto file (e.g. `repeated string tags = 3;`), followed by
+// its JSON name when it differs from the proto one
+fn field_definition(field: &FieldDescriptor, sort_fields: bool) -> String {
+    let in_oneof = field.containing_oneof();
+    let is_proto2 = field.parent_file().syntax() == Syntax::Proto2;
+
+    let label = if field.is_list() {
+        format!("{} ", "repeated".cyan())
+    } else if in_oneof.as_ref().is_some_and(|o| o.is_synthetic()) {
+        // proto3 `optional` fields are wrapped in a synthetic oneof
+        format!("{} ", "optional".cyan())
+    } else if field.cardinality() == Cardinality::Required {
+        format!("{} ", "required".cyan())
+    } else if is_proto2 && in_oneof.is_none() && !field.is_map() {
+        // Every proto2 field outside of a oneof has explicit presence
+        format!("{} ", "optional".cyan())
+    } else {
+        "".to_string()
+    };
+
+    let type_name = match field.kind() {
+        Kind::Message(group) if field.is_group() => {
+            return group_definition(field, &group, label, sort_fields);
+        }
+        // Maps are encoded as a repeated entry message with `key` and `value` fields
+        Kind::Message(entry) if field.is_map() => format!(
+            "{}<{}, {}>",
+            "map".cyan(),
+            type_name(&entry.map_entry_key_field().kind()),
+            type_name(&entry.map_entry_value_field().kind())
+        ),
+        kind => type_name(&kind).to_string(),
+    };
+
+    let json_name = if field.json_name() != field.name() {
+        format!(
+            " {}",
+            format!("// json: \"{}\"", field.json_name()).dimmed()
+        )
+    } else {
+        "".to_string()
+    };
+
+    format!(
+        "{}{}{} {} = {}{};{}{}",
+        label,
+        type_name,
+        " ".normal(), // Reset color
+        field.name(),
+        field.number(),
+        field_options(field),
+        deprecated_marker(field),
+        json_name
+    )
+}
+
+// A proto2 group declares its message inline: `optional group Result = 1 { ... }`
+fn group_definition(
+    field: &FieldDescriptor,
+    group: &MessageDescriptor,
+    label: String,
+    sort_fields: bool,
+) -> String {
+    let fields: String = ordered_fields(group.fields(), sort_fields)
+        .iter()
+        .map(|field| indented(&field_definition(field, sort_fields), "  "))
+        .collect();
+
+    format!(
+        "{}{} {}{}= {}{} {{{}\n{}}}",
+        label,
+        "group".cyan(),
+        group.name().yellow(),
+        " ".normal(), // Reset color
+        field.number(),
+        field_options(field),
+        deprecated_marker(field),
+        fields
+    )
+}
+
+// The `[default = ..., packed = ...]` options of a field, when they are set explicitly
+fn field_options(field: &FieldDescriptor) -> String {
+    let proto = field.field_descriptor_proto();
+    let mut options = Vec::new();
+
+    if let Some(default) = &proto.default_value {
+        let default = match field.kind() {
+            Kind::String => json_string(default),
+            // Defaults of bytes fields are already escaped
+            Kind::Bytes => format!("\"{default}\""),
+            _ => default.clone(),
+        };
+        options.push(format!("default = {}", default.purple()));
+    }
+
+    if let Some(packed) = proto.options.as_ref().and_then(|o| o.packed) {
+        options.push(format!("packed = {}", packed.to_string().purple()));
+    }
+
+    if options.is_empty() {
+        "".to_string()
+    } else {
+        format!(" [{}]", options.join(", "))
+    }
+}
+
+fn type_name(kind: &Kind) -> ColoredString {
+    match kind {
+        Kind::Double => "double".yellow(),
+        Kind::Float => "float".yellow(),
+        Kind::Int32 => "int32".yellow(),
+        Kind::Int64 => "int64".yellow(),
+        Kind::Uint32 => "uint32".yellow(),
+        Kind::Uint64 => "uint64".yellow(),
+        Kind::Sint32 => "sint32".yellow(),
+        Kind::Sint64 => "sint64".yellow(),
+        Kind::Fixed32 => "fixed32".yellow(),
+        Kind::Fixed64 => "fixed64".yellow(),
+        Kind::Sfixed32 => "sfixed32".yellow(),
+        Kind::Sfixed64 => "sfixed64".yellow(),
+        Kind::Bool => "bool".yellow(),
+        Kind::String => "string".yellow(),
+        Kind::Bytes => "bytes".yellow(),
+        Kind::Message(m) => m.full_name().yellow(),
+        Kind::Enum(e) => e.full_name().yellow(),
     }
 }
 
@@ -258,15 +1004,697 @@ impl From<EnumDescriptor> for FormattedString {
             enum_desc.name().green()
         ));
 
+        let allow_alias = enum_desc
+            .enum_descriptor_proto()
+            .options
+            .as_ref()
+            .and_then(|o| o.allow_alias);
+        if let Some(allow_alias) = allow_alias {
+            out.push_str(&format!(
+                "  {} allow_alias = {};\n",
+                "option".cyan(),
+                allow_alias.to_string().purple()
+            ));
+        }
+
         for val in enum_desc.values() {
             out.push_str(&format!(
-                "  {} = {};\n",
+                "  {} = {};{}\n",
                 val.name(),
-                val.number().to_string().purple()
+                val.number().to_string().purple(),
+                deprecated_marker(&val)
             ));
         }
+
+        let ranges: Vec<_> = enum_desc.reserved_ranges().map(reserved_range).collect();
+        if !ranges.is_empty() {
+            out.push_str(&format!("  {} {};\n", "reserved".cyan(), ranges.join(", ")));
+        }
+
+        let names: Vec<_> = enum_desc.reserved_names().map(json_string).collect();
+        if !names.is_empty() {
+            out.push_str(&format!("  {} {};\n", "reserved".cyan(), names.join(", ")));
+        }
         out.push('}');
 
         FormattedString(out)
     }
 }
+
+// Renders a reserved range of enum values as declared in a proto file (e.g. `9 to 11`)
+fn reserved_range(range: RangeInclusive<i32>) -> String {
+    match (*range.start(), *range.end()) {
+        (start, end) if start == end => start.to_string().purple().to_string(),
+        (start, i32::MAX) => format!("{} to {}", start.to_string().purple(), "max".purple()),
+        (start, end) => format!(
+            "{} to {}",
+            start.to_string().purple(),
+            end.to_string().purple()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use granc_core::prost_reflect::DescriptorPool;
+    use granc_test_support::compiler;
+
+    #[test]
+    fn test_reachability() {
+        use json::JsonString;
+
+        colored::control::set_override(false);
+        assert_eq!(
+            FormattedString::from(Reachability(Some(false))).0,
+            "reachable, reflection: no"
+        );
+        assert_eq!(FormattedString::from(Reachability(None)).0, "reachable");
+
+        let json = JsonString::from(Reachability(Some(true)));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json.0).unwrap(),
+            serde_json::json!({"reachable": true, "reflection": true})
+        );
+    }
+
+    #[test]
+    fn test_json_array_collects_the_stream() {
+        use granc_core::{client::MethodKind, tonic::Status};
+        use json::{JsonArray, JsonString};
+
+        let response = DynamicResponse::Streaming(
+            MethodKind::ServerStreaming,
+            Ok(vec![
+                Ok(serde_json::json!({"message": "a"})),
+                Ok(serde_json::json!({"message": "b"})),
+                Err(Status::internal("boom")),
+            ]),
+        );
+
+        let out: serde_json::Value =
+            serde_json::from_str(&JsonString::from(JsonArray(response)).0).unwrap();
+
+        assert_eq!(out[0], serde_json::json!({"message": "a"}));
+        assert_eq!(out[1], serde_json::json!({"message": "b"}));
+        assert_eq!(out[2]["code"], "INTERNAL");
+        assert_eq!(out.as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_empty_stream_is_noted() {
+        use granc_core::{client::MethodKind, tonic::Status};
+
+        colored::control::set_override(false);
+
+        let empty = DynamicResponse::Streaming(MethodKind::ServerStreaming, Ok(vec![]));
+        assert_eq!(FormattedString::from(empty).0, "(empty stream)");
+
+        let failed = DynamicResponse::Streaming(
+            MethodKind::ServerStreaming,
+            Err(Status::not_found("nothing here")),
+        );
+        assert!(!FormattedString::from(failed).0.contains("(empty stream)"));
+    }
+
+    #[test]
+    fn test_batch_result_json() {
+        use granc_core::{
+            client::MethodKind,
+            tonic::{Code, Status},
+        };
+        use json::{BatchResult, JsonError, JsonString};
+
+        let to_json = |result: BatchResult| -> serde_json::Value {
+            serde_json::from_str(&JsonString::from(result).0).unwrap()
+        };
+
+        let ok = BatchResult {
+            line: 1,
+            method: Some("echo.EchoService/UnaryEcho".to_string()),
+            request: None,
+            result: Ok(DynamicResponse::Unary(
+                MethodKind::Unary,
+                Ok(serde_json::json!({"message": "a"})),
+            )),
+        };
+        assert!(!ok.is_error());
+        assert_eq!(
+            to_json(ok),
+            serde_json::json!({
+                "line": 1,
+                "method": "echo.EchoService/UnaryEcho",
+                "response": {"message": "a"}
+            })
+        );
+
+        let stream = BatchResult {
+            line: 2,
+            method: Some("echo.EchoService/ServerStreamingEcho".to_string()),
+            request: None,
+            result: Ok(DynamicResponse::Streaming(
+                MethodKind::ServerStreaming,
+                Ok(vec![
+                    Ok(serde_json::json!({"message": "a"})),
+                    Err(Status::internal("boom")),
+                ]),
+            )),
+        };
+        assert!(stream.is_error());
+        let out = to_json(stream);
+        assert_eq!(out["response"][0], serde_json::json!({"message": "a"}));
+        assert_eq!(out["response"][1]["code"], "INTERNAL");
+
+        let invalid = BatchResult {
+            line: 3,
+            method: None,
+            request: None,
+            result: Err(JsonError::new(
+                Code::InvalidArgument,
+                "Expected a JSON object",
+            )),
+        };
+        assert!(invalid.is_error());
+        let out = to_json(invalid);
+        assert_eq!(out["method"], serde_json::Value::Null);
+        assert_eq!(out["error"]["code"], "INVALID_ARGUMENT");
+        assert_eq!(out.get("response"), None);
+        assert_eq!(out.get("request"), None);
+
+        let with_request = BatchResult {
+            line: 4,
+            method: Some("echo.EchoService/UnaryEcho".to_string()),
+            request: Some(serde_json::json!({"message": "a"})),
+            result: Ok(DynamicResponse::Unary(
+                MethodKind::Unary,
+                Ok(serde_json::json!({"message": "a"})),
+            )),
+        };
+        assert_eq!(
+            to_json(with_request),
+            serde_json::json!({
+                "line": 4,
+                "method": "echo.EchoService/UnaryEcho",
+                "request": {"message": "a"},
+                "response": {"message": "a"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_uncolored_json_matches_pretty_printer() {
+        colored::control::set_override(false);
+
+        let value = serde_json::json!({
+            "name": "Ferris \"the crab\"",
+            "age": 7,
+            "ratio": 0.5,
+            "alive": true,
+            "owner": null,
+            "tags": ["rust", "crab"],
+            "empty_list": [],
+            "empty_object": {},
+            "nested": { "list": [{ "a": 1 }, [false]] }
+        });
+
+        let formatted = FormattedString::from(value.clone());
+
+        assert_eq!(formatted.0, serde_json::to_string_pretty(&value).unwrap());
+    }
+
+    #[test]
+    fn test_map_fields_are_rendered_as_maps() {
+        colored::control::set_override(false);
+
+        let files = compiler::compile_protos(&[(
+            "maps.proto",
+            r#"
+            syntax = "proto3";
+            package maps;
+            message Value { string text = 1; }
+            message WithMaps {
+                map<string, int32> counts = 1;
+                map<int64, Value> values = 2;
+                repeated string tags = 3;
+            }
+            "#,
+        )]);
+        let pool = DescriptorPool::from_file_descriptor_set(files).unwrap();
+        let message = pool.get_message_by_name("maps.WithMaps").unwrap();
+
+        let formatted = FormattedString::from(message).0;
+
+        assert!(formatted.contains("  map<string, int32>  counts = 1;"));
+        assert!(formatted.contains("  map<int64, maps.Value>  values = 2;"));
+        assert!(formatted.contains("  repeated string  tags = 3;"));
+        assert!(!formatted.contains("repeated map"));
+        assert!(!formatted.contains("CountsEntry"));
+    }
+
+    #[test]
+    fn test_dry_run_shows_method_headers_and_messages() {
+        colored::control::set_override(false);
+
+        let files = compiler::compile_protos(&[(
+            "dry.proto",
+            r#"
+            syntax = "proto3";
+            package dry;
+            message Ping { string id = 1; }
+            service Pinger { rpc Ping(stream Ping) returns (Ping); }
+            "#,
+        )]);
+        let pool = DescriptorPool::from_file_descriptor_set(files).unwrap();
+        let method = pool
+            .get_service_by_name("dry.Pinger")
+            .unwrap()
+            .methods()
+            .next()
+            .unwrap();
+
+        let formatted = FormattedString::from(DryRun {
+            method,
+            headers: vec![("x-trace".to_string(), "1".to_string())],
+            messages: vec![
+                serde_json::json!({"id": "a"}),
+                serde_json::json!({"id": "b"}),
+            ],
+        })
+        .0;
+
+        assert!(formatted.starts_with("Dry run: dry.Pinger/Ping (client-stream)\n"));
+        assert!(formatted.contains("x-trace: 1\n"));
+        assert!(formatted.contains("\"id\": \"a\""));
+        assert!(formatted.contains("\"id\": \"b\""));
+    }
+
+    #[test]
+    fn test_service_summary_counts_methods_by_kind() {
+        colored::control::set_override(false);
+
+        let files = compiler::compile_protos(&[(
+            "greeter.proto",
+            r#"
+            syntax = "proto3";
+            package hello;
+            message Msg { string name = 1; }
+            service Greeter {
+                rpc SayHello(Msg) returns (Msg);
+                rpc SayGoodbye(Msg) returns (Msg);
+                rpc Watch(Msg) returns (stream Msg);
+            }
+            service Ping { rpc Ping(Msg) returns (Msg); }
+            "#,
+        )]);
+        let pool = DescriptorPool::from_file_descriptor_set(files).unwrap();
+        let services: Vec<_> = pool.services().collect();
+
+        let text = FormattedString::from(ServiceSummaryList(services.clone())).0;
+        assert!(text.contains("  - hello.Greeter: 3 methods (2 unary, 1 server-stream)\n"));
+        assert!(text.ends_with("  - hello.Ping: 1 method (1 unary)"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&json::JsonString::from(ServiceSummaryList(services)).0).unwrap();
+        assert_eq!(json[0]["name"], "hello.Greeter");
+        assert_eq!(json[0]["method_count"], 3);
+        assert_eq!(
+            json[0]["kinds"],
+            serde_json::json!({"unary": 2, "server-stream": 1, "client-stream": 0, "bidi": 0})
+        );
+    }
+
+    #[test]
+    fn test_deprecated_definitions_are_marked() {
+        colored::control::set_override(false);
+
+        let files = compiler::compile_protos(&[(
+            "old.proto",
+            r#"
+            syntax = "proto3";
+            package old;
+            enum State {
+                UNKNOWN = 0;
+                LEGACY = 1 [deprecated = true];
+            }
+            message Item {
+                string id = 1 [deprecated = true];
+                State state = 2;
+            }
+            service Legacy {
+                option deprecated = true;
+                rpc Get(Item) returns (Item) { option deprecated = true; }
+                rpc List(Item) returns (Item);
+            }
+            "#,
+        )]);
+        let pool = DescriptorPool::from_file_descriptor_set(files).unwrap();
+
+        let service = FormattedString::from(pool.get_service_by_name("old.Legacy").unwrap()).0;
+        assert!(service.starts_with("service Legacy (deprecated) {\n"));
+        assert!(service.contains("rpc Get(old.Item) returns (old.Item); (deprecated)\n"));
+        assert!(service.contains("rpc List(old.Item) returns (old.Item);\n"));
+
+        let message = FormattedString::from(pool.get_message_by_name("old.Item").unwrap()).0;
+        assert!(message.contains("  string  id = 1; (deprecated)\n"));
+        assert!(message.contains("  old.State  state = 2;\n"));
+
+        let enum_desc = FormattedString::from(pool.get_enum_by_name("old.State").unwrap()).0;
+        assert!(enum_desc.contains("  LEGACY = 1; (deprecated)\n"));
+        assert!(enum_desc.contains("  UNKNOWN = 0;\n"));
+    }
+
+    #[test]
+    fn test_fields_show_json_names_and_presence() {
+        colored::control::set_override(false);
+
+        let files = compiler::compile_protos(&[
+            (
+                "users.proto",
+                r#"
+                syntax = "proto3";
+                package users;
+                message User {
+                    string user_id = 1;
+                    optional string nickname = 2;
+                    repeated int32 scores = 3 [packed = false];
+                    string email = 4 [json_name = "mail"];
+                }
+                "#,
+            ),
+            (
+                "legacy.proto",
+                r#"
+                syntax = "proto2";
+                package legacy;
+                message Account {
+                    required string id = 1;
+                    optional int32 retries = 2 [default = 3];
+                    optional string region = 3 [default = "eu"];
+                    oneof owner { string team = 4; }
+                    repeated group Login = 5 {
+                        required int64 timestamp = 6;
+                        optional string ip = 7;
+                    }
+                }
+                "#,
+            ),
+        ]);
+        let pool = DescriptorPool::from_file_descriptor_set(files).unwrap();
+
+        let user = FormattedString::from(pool.get_message_by_name("users.User").unwrap()).0;
+        assert!(user.contains("  string  user_id = 1; // json: \"userId\"\n"));
+        assert!(user.contains("  optional string  nickname = 2;\n"));
+        assert!(user.contains("  repeated int32  scores = 3 [packed = false];\n"));
+        assert!(user.contains("  string  email = 4; // json: \"mail\"\n"));
+
+        let account = FormattedString::from(pool.get_message_by_name("legacy.Account").unwrap()).0;
+        assert!(account.contains("  required string  id = 1;\n"));
+        assert!(account.contains("  optional int32  retries = 2 [default = 3];\n"));
+        assert!(account.contains("  optional string  region = 3 [default = \"eu\"];\n"));
+        assert!(account.contains("    string  team = 4;\n"));
+        assert!(account.contains(
+            "  repeated group Login = 5 {\n    required int64  timestamp = 6;\n    optional string  ip = 7;\n  }\n"
+        ));
+    }
+
+    #[test]
+    fn test_enum_options_and_reserved_values() {
+        colored::control::set_override(false);
+
+        let files = compiler::compile_protos(&[(
+            "status.proto",
+            r#"
+            syntax = "proto3";
+            package status;
+            enum Status {
+                option allow_alias = true;
+                UNKNOWN = 0;
+                STARTED = 1;
+                RUNNING = 1;
+                reserved 2, 9 to 11, 40 to max;
+                reserved "STOPPED", "PAUSED";
+            }
+            "#,
+        )]);
+        let pool = DescriptorPool::from_file_descriptor_set(files).unwrap();
+
+        let status = FormattedString::from(pool.get_enum_by_name("status.Status").unwrap()).0;
+        assert!(status.starts_with("enum Status {\n  option allow_alias = true;\n"));
+        assert!(status.contains("  STARTED = 1;\n"));
+        assert!(status.contains("  RUNNING = 1;\n"));
+        assert!(status.ends_with(
+            "  reserved 2, 9 to 11, 40 to max;\n  reserved \"STOPPED\", \"PAUSED\";\n}"
+        ));
+    }
+
+    #[test]
+    fn test_oneofs_are_grouped() {
+        colored::control::set_override(false);
+
+        let files = compiler::compile_protos(&[(
+            "oneofs.proto",
+            r#"
+            syntax = "proto3";
+            package oneofs;
+            message Shape {
+                string name = 1;
+                oneof kind {
+                    double radius = 2;
+                    double side = 3;
+                }
+                optional int32 sides = 4;
+            }
+            "#,
+        )]);
+        let pool = DescriptorPool::from_file_descriptor_set(files).unwrap();
+        let message = pool.get_message_by_name("oneofs.Shape").unwrap();
+
+        let formatted = FormattedString::from(message).0;
+
+        let expected = [
+            "message Shape {",
+            "  string  name = 1;",
+            "  oneof kind {",
+            "    double  radius = 2;",
+            "    double  side = 3;",
+            "  }",
+            "  optional int32  sides = 4;",
+            "}",
+        ];
+        assert_eq!(formatted, expected.join("\n"));
+    }
+
+    #[test]
+    fn test_sort_fields_by_number() {
+        colored::control::set_override(false);
+
+        let files = compiler::compile_protos(&[(
+            "sorted.proto",
+            r#"
+            syntax = "proto3";
+            package sorted;
+            message Item {
+                string name = 3;
+                string id = 1;
+                string tag = 2;
+            }
+            "#,
+        )]);
+        let pool = DescriptorPool::from_file_descriptor_set(files).unwrap();
+        let message = pool.get_message_by_name("sorted.Item").unwrap();
+        let field_names = |text: String| -> Vec<String> {
+            text.lines()
+                .filter_map(|line| line.strip_prefix("  string  "))
+                .map(|line| line.split(' ').next().unwrap().to_string())
+                .collect()
+        };
+
+        let declared = FormattedString::from(message.clone()).0;
+        assert_eq!(field_names(declared), ["name", "id", "tag"]);
+
+        let sorted = message_definition(message, true).0;
+        assert_eq!(field_names(sorted), ["id", "tag", "name"]);
+    }
+
+    #[test]
+    fn test_field_projection() {
+        let value = serde_json::json!({
+            "user": { "name": "Ada", "email": "ada@example.com" },
+            "items": [
+                { "id": 1, "tags": ["a", "b"] },
+                { "id": 2, "tags": ["c"] }
+            ],
+            "total": 2
+        });
+        let project = |paths: &[&str]| {
+            let paths: Vec<String> = paths.iter().map(|path| path.to_string()).collect();
+            FieldProjection::new(&paths).apply(value.clone())
+        };
+
+        assert_eq!(project(&[]), value);
+        assert_eq!(
+            project(&["user.name", "total"]),
+            serde_json::json!({ "user": { "name": "Ada" }, "total": 2 })
+        );
+        assert_eq!(
+            project(&["items.1.id"]),
+            serde_json::json!({ "items": [{ "id": 2 }] })
+        );
+        assert_eq!(
+            project(&["items.id", "items.0.tags.1"]),
+            serde_json::json!({ "items": [{ "id": 1, "tags": ["b"] }, { "id": 2 }] })
+        );
+        assert_eq!(
+            project(&["user", "user.name"]),
+            serde_json::json!({ "user": { "name": "Ada", "email": "ada@example.com" } })
+        );
+        assert_eq!(
+            project(&["user.phone", "items.5", "total.value"]),
+            serde_json::json!({})
+        );
+    }
+
+    #[test]
+    fn test_described_types_are_expanded() {
+        colored::control::set_override(false);
+
+        let files = compiler::compile_protos(&[(
+            "shop.proto",
+            r#"
+            syntax = "proto3";
+            package shop;
+            enum Status { STATUS_UNSPECIFIED = 0; STATUS_OPEN = 1; }
+            message Tag { string name = 1; }
+            message Item {
+                string id = 1;
+                Status status = 2;
+                map<string, Tag> tags = 3;
+                repeated Item children = 4;
+            }
+            message Order { Item item = 1; Status status = 2; }
+            service Shop { rpc Get(Order) returns (Item); }
+            "#,
+        )]);
+        let pool = DescriptorPool::from_file_descriptor_set(files).unwrap();
+        let used_names = |definition: Definition| -> Vec<String> {
+            let json: serde_json::Value = serde_json::from_str(
+                &json::JsonString::from(Described::new(definition, true, false)).0,
+            )
+            .unwrap();
+            json["used_types"]
+                .as_array()
+                .map(|types| {
+                    types
+                        .iter()
+                        .map(|t| t["full_name"].as_str().unwrap().to_string())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let order = pool.get_message_by_name("shop.Order").unwrap();
+        assert_eq!(
+            used_names(Definition::Symbol(Descriptor::MessageDescriptor(order))),
+            ["shop.Item", "shop.Status", "shop.Tag"]
+        );
+
+        let method = pool
+            .get_service_by_name("shop.Shop")
+            .unwrap()
+            .methods()
+            .next()
+            .unwrap();
+        assert_eq!(
+            used_names(Definition::Method(method.clone())),
+            ["shop.Order", "shop.Item", "shop.Status", "shop.Tag"]
+        );
+
+        let status = pool.get_enum_by_name("shop.Status").unwrap();
+        assert!(used_names(Definition::Symbol(Descriptor::EnumDescriptor(status))).is_empty());
+
+        let text = FormattedString::from(Described::new(Definition::Method(method), true, false)).0;
+        assert!(text.starts_with("rpc Get(shop.Order) returns (shop.Item);\n\nmessage Order {"));
+        assert!(text.contains("\n\nenum Status {"));
+        assert!(!text.contains("TagsEntry"));
+
+        let unexpanded = Described::new(
+            Definition::Symbol(Descriptor::EnumDescriptor(
+                pool.get_enum_by_name("shop.Status").unwrap(),
+            )),
+            false,
+            false,
+        );
+        assert!(unexpanded.used_types.is_empty());
+    }
+
+    #[test]
+    fn test_raw_files_include_dependencies() {
+        use granc_test_support::echo_service::FILE_DESCRIPTOR_SET;
+
+        let pool = DescriptorPool::decode(FILE_DESCRIPTOR_SET).unwrap();
+        let service = pool.get_service_by_name("echo.EchoService").unwrap();
+        let file = service.parent_file();
+
+        let raw = raw_files(&Definition::Symbol(Descriptor::ServiceDescriptor(
+            service.clone(),
+        )));
+        let files = raw["file"].as_array().unwrap();
+
+        assert_eq!(files.len(), file.dependencies().len() + 1);
+        // Dependencies come before the files that import them
+        let last = files.last().unwrap();
+        assert_eq!(last["name"], file.name());
+        assert_eq!(last["service"][0]["name"], "EchoService");
+
+        let method = service.methods().next().unwrap();
+        assert_eq!(raw_files(&Definition::Method(method)), raw);
+    }
+
+    #[test]
+    fn test_status_details_are_decoded_once_registered() {
+        use granc_core::prost_reflect::DynamicMessage;
+        use granc_core::tonic::Code;
+        use granc_test_support::echo_service::FILE_DESCRIPTOR_SET;
+        use prost::Message;
+
+        colored::control::set_override(false);
+
+        let pool = DescriptorPool::decode(FILE_DESCRIPTOR_SET).unwrap();
+        let detail_type = pool.get_message_by_name("echo.EchoResponse").unwrap();
+        let detail = DynamicMessage::deserialize(
+            detail_type.clone(),
+            serde_json::json!({ "message": "too long" }),
+        )
+        .unwrap();
+
+        // A `google.rpc.Status` whose only detail is the message above
+        let any = prost_types::Any {
+            type_url: "type.googleapis.com/echo.EchoResponse".to_string(),
+            value: detail.encode_to_vec(),
+        };
+        let mut details = vec![];
+        prost::encoding::message::encode(3, &any, &mut details);
+        let status = Status::with_details(Code::InvalidArgument, "invalid", details.into());
+
+        assert_eq!(json::unknown_detail_types(&status), ["echo.EchoResponse"]);
+        assert_eq!(
+            json::status_details(&status)[0]["@type"],
+            "type.googleapis.com/echo.EchoResponse"
+        );
+        assert!(json::status_details(&status)[0]["value"].is_string());
+
+        json::register_detail_type(detail_type);
+
+        assert!(json::unknown_detail_types(&status).is_empty());
+        assert_eq!(
+            json::status_details(&status),
+            [serde_json::json!({
+                "@type": "type.googleapis.com/echo.EchoResponse",
+                "message": "too long"
+            })]
+        );
+        assert!(FormattedString::from(status).0.ends_with(
+            "\n  detail: {\"@type\":\"type.googleapis.com/echo.EchoResponse\",\"message\":\"too long\"}"
+        ));
+    }
+}
@@ -0,0 +1,752 @@
+//! # JSON Output
+//!
+//! Machine-readable counterparts of the [`super::FormattedString`] conversions, used when
+//! `--output json` is selected.
+//!
+//! Values are rendered as compact JSON, so streaming responses produce one document per line (NDJSON).
+//! Errors are rendered as an object shaped like a gRPC status:
+//!
+//! ```json
+//! {"code": "NOT_FOUND", "code_number": 5, "message": "...", "details": []}
+//! ```
+use super::{
+    Definition, Described, DryRun, GenericError, MethodList, Reachability, ServiceList,
+    ServiceMethodsList, ServiceSummaryList, method_kind, method_kind_counts, method_path,
+    ordered_fields,
+};
+use crate::{
+    diff::{ChangeKind, SchemaDiff},
+    health::HealthStatus,
+};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use granc_core::{
+    client::{
+        Descriptor, DescriptorSetError, DynamicResponse, ResolveMethodError, online,
+        online_without_reflection,
+    },
+    grpc::client::GrpcRequestError,
+    prost_reflect::{
+        self, DynamicMessage, EnumDescriptor, FieldDescriptor, Kind, MessageDescriptor,
+        MethodDescriptor, ServiceDescriptor,
+    },
+    reflection::client::ReflectionResolveError,
+    tonic::{Code, Status},
+};
+use prost::Message;
+use serde_json::{Value, json};
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    sync::{Mutex, MutexGuard},
+};
+
+/// A wrapper struct for a JSON encoded string.
+///
+/// Implements `Display` so it can be printed directly.
+pub struct JsonString(pub String);
+
+impl std::fmt::Display for JsonString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<Value> for JsonString {
+    fn from(value: Value) -> Self {
+        JsonString(value.to_string())
+    }
+}
+
+impl From<Status> for JsonString {
+    fn from(status: Status) -> Self {
+        JsonString::from(JsonError::from(status))
+    }
+}
+
+impl From<JsonError> for JsonString {
+    fn from(err: JsonError) -> Self {
+        JsonString::from(Value::from(err))
+    }
+}
+
+impl From<DynamicResponse> for JsonString {
+    fn from(value: DynamicResponse) -> Self {
+        match value {
+            DynamicResponse::Unary(_, Ok(value)) => JsonString::from(value),
+            DynamicResponse::Unary(_, Err(status)) => JsonString::from(status),
+            DynamicResponse::Streaming(_, Ok(values)) => {
+                let lines: Vec<_> = values
+                    .into_iter()
+                    .map(|elem| match elem {
+                        Ok(val) => JsonString::from(val).0,
+                        Err(status) => JsonString::from(status).0,
+                    })
+                    .collect();
+                JsonString(lines.join("\n"))
+            }
+            DynamicResponse::Streaming(_, Err(status)) => JsonString::from(status),
+        }
+    }
+}
+
+/// A call response printed as a single JSON document (`--output json-array`).
+///
+/// The messages of a stream are gathered in an array, including the status of a stream that
+/// ends with an error. Unary responses are printed as they are with `--output json`.
+pub struct JsonArray(pub DynamicResponse);
+
+impl From<JsonArray> for JsonString {
+    fn from(JsonArray(value): JsonArray) -> Self {
+        match value {
+            DynamicResponse::Streaming(_, Ok(values)) => JsonString::from(stream_to_array(values)),
+            other => JsonString::from(other),
+        }
+    }
+}
+
+// The messages of a stream in an array, including the status of a stream that ended with an error
+fn stream_to_array(values: Vec<Result<Value, Status>>) -> Value {
+    values
+        .into_iter()
+        .map(|elem| match elem {
+            Ok(val) => val,
+            Err(status) => Value::from(JsonError::from(status)),
+        })
+        .collect()
+}
+
+/// The result of a line of a `batch` script, or of a `call --body-file`.
+///
+/// Printed as `{"line": 1, "method": "pkg.Service/Method", "response": ...}`, where the messages
+/// of a stream are gathered in an array as with [`JsonArray`], or with an `error` instead of a
+/// `response` when the line or the call failed. The body sent is printed as `request`, if any.
+pub struct BatchResult {
+    pub line: usize,
+    pub method: Option<String>,
+    pub request: Option<Value>,
+    pub result: Result<DynamicResponse, JsonError>,
+}
+
+impl BatchResult {
+    /// Whether the line failed, including streams that ended with an error.
+    pub fn is_error(&self) -> bool {
+        match &self.result {
+            Ok(DynamicResponse::Unary(_, result)) => result.is_err(),
+            Ok(DynamicResponse::Streaming(_, Ok(values))) => values.iter().any(Result::is_err),
+            Ok(DynamicResponse::Streaming(_, Err(_))) => true,
+            Err(_) => true,
+        }
+    }
+}
+
+impl From<BatchResult> for JsonString {
+    fn from(result: BatchResult) -> Self {
+        JsonString::from(Value::from(result))
+    }
+}
+
+impl From<BatchResult> for Value {
+    fn from(
+        BatchResult {
+            line,
+            method,
+            request,
+            result,
+        }: BatchResult,
+    ) -> Self {
+        let (key, value) = match result {
+            Ok(DynamicResponse::Unary(_, Ok(value))) => ("response", value),
+            Ok(DynamicResponse::Streaming(_, Ok(values))) => ("response", stream_to_array(values)),
+            Ok(
+                DynamicResponse::Unary(_, Err(status)) | DynamicResponse::Streaming(_, Err(status)),
+            ) => ("error", Value::from(JsonError::from(status))),
+            Err(err) => ("error", Value::from(err)),
+        };
+
+        let mut out = json!({ "line": line, "method": method, key: value });
+        if let Some(request) = request {
+            out["request"] = request;
+        }
+        out
+    }
+}
+
+impl From<SchemaDiff> for JsonString {
+    fn from(SchemaDiff(changes): SchemaDiff) -> Self {
+        let breaking = changes.iter().filter(|change| change.breaking).count();
+        let changes: Vec<_> = changes
+            .into_iter()
+            .map(|change| {
+                json!({
+                    "change": match change.kind {
+                        ChangeKind::Added => "added",
+                        ChangeKind::Removed => "removed",
+                        ChangeKind::Changed => "changed",
+                    },
+                    "element": change.element,
+                    "name": change.name,
+                    "detail": change.detail,
+                    "breaking": change.breaking,
+                })
+            })
+            .collect();
+
+        JsonString::from(json!({ "changes": changes, "breaking": breaking }))
+    }
+}
+
+impl From<HealthStatus> for JsonString {
+    fn from(HealthStatus(status): HealthStatus) -> Self {
+        JsonString::from(json!({ "status": status }))
+    }
+}
+
+impl From<Reachability> for JsonString {
+    fn from(Reachability(reflection): Reachability) -> Self {
+        JsonString::from(json!({ "reachable": true, "reflection": reflection }))
+    }
+}
+
+impl From<ServiceList> for JsonString {
+    fn from(ServiceList(services): ServiceList) -> Self {
+        JsonString::from(json!(services))
+    }
+}
+
+impl From<ServiceMethodsList> for JsonString {
+    fn from(ServiceMethodsList(services): ServiceMethodsList) -> Self {
+        let services: Vec<_> = services
+            .iter()
+            .map(|s| {
+                json!({
+                    "name": s.full_name(),
+                    "methods": s.methods().map(|m| m.name().to_string()).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        JsonString::from(json!(services))
+    }
+}
+
+impl From<ServiceSummaryList> for JsonString {
+    fn from(ServiceSummaryList(services): ServiceSummaryList) -> Self {
+        let services: Vec<_> = services
+            .iter()
+            .map(|s| {
+                let kinds: serde_json::Map<_, _> = method_kind_counts(s)
+                    .into_iter()
+                    .map(|(kind, n)| (kind.to_string(), json!(n)))
+                    .collect();
+
+                json!({
+                    "name": s.full_name(),
+                    "method_count": s.methods().len(),
+                    "kinds": kinds,
+                })
+            })
+            .collect();
+        JsonString::from(json!(services))
+    }
+}
+
+impl From<MethodList> for JsonString {
+    fn from(MethodList(methods): MethodList) -> Self {
+        let methods: Vec<_> = methods
+            .iter()
+            .map(|m| {
+                json!({
+                    "path": method_path(m),
+                    "service": m.parent_service().full_name(),
+                    "method": m.name(),
+                    "kind": method_kind(m),
+                })
+            })
+            .collect();
+        JsonString::from(json!(methods))
+    }
+}
+
+impl From<DryRun> for JsonString {
+    fn from(dry_run: DryRun) -> Self {
+        let headers: serde_json::Map<_, _> = dry_run
+            .headers
+            .into_iter()
+            .map(|(key, value)| (key, Value::String(value)))
+            .collect();
+
+        JsonString::from(json!({
+            "path": method_path(&dry_run.method),
+            "kind": method_kind(&dry_run.method),
+            "headers": headers,
+            "messages": dry_run.messages,
+        }))
+    }
+}
+
+impl From<prost_reflect::FileDescriptor> for JsonString {
+    fn from(file: prost_reflect::FileDescriptor) -> Self {
+        JsonString::from(json!({
+            "kind": "file",
+            "name": file.name(),
+            "package": file.package_name(),
+            "dependencies": file.dependencies().map(|d| d.name().to_string()).collect::<Vec<_>>(),
+            "services": file.services().map(|s| service_to_json(&s)).collect::<Vec<_>>(),
+            "messages": file.messages().map(|m| message_to_json(&m, false)).collect::<Vec<_>>(),
+            "enums": file.enums().map(|e| enum_to_json(&e)).collect::<Vec<_>>(),
+        }))
+    }
+}
+
+impl From<Described> for JsonString {
+    fn from(described: Described) -> Self {
+        let mut value = match described.definition {
+            Definition::Symbol(d) => descriptor_to_json(&d, described.sort_fields),
+            Definition::Method(d) => {
+                let mut value = method_to_json(&d);
+                value["kind"] = json!("method");
+                value["full_name"] = json!(d.full_name());
+                value
+            }
+        };
+
+        if !described.used_types.is_empty() {
+            value["used_types"] = described
+                .used_types
+                .iter()
+                .map(|d| descriptor_to_json(d, described.sort_fields))
+                .collect();
+        }
+        JsonString::from(value)
+    }
+}
+
+impl From<Descriptor> for JsonString {
+    fn from(value: Descriptor) -> Self {
+        JsonString::from(descriptor_to_json(&value, false))
+    }
+}
+
+fn descriptor_to_json(descriptor: &Descriptor, sort_fields: bool) -> Value {
+    match descriptor {
+        Descriptor::MessageDescriptor(d) => message_to_json(d, sort_fields),
+        Descriptor::ServiceDescriptor(d) => service_to_json(d),
+        Descriptor::EnumDescriptor(d) => enum_to_json(d),
+    }
+}
+
+fn service_to_json(service: &ServiceDescriptor) -> Value {
+    json!({
+        "kind": "service",
+        "name": service.name(),
+        "full_name": service.full_name(),
+        "package": service.package_name(),
+        "methods": service.methods().map(|m| method_to_json(&m)).collect::<Vec<_>>(),
+    })
+}
+
+fn method_to_json(method: &MethodDescriptor) -> Value {
+    json!({
+        "name": method.name(),
+        "input_type": method.input().full_name(),
+        "output_type": method.output().full_name(),
+        "client_streaming": method.is_client_streaming(),
+        "server_streaming": method.is_server_streaming(),
+    })
+}
+
+fn message_to_json(message: &MessageDescriptor, sort_fields: bool) -> Value {
+    json!({
+        "kind": "message",
+        "name": message.name(),
+        "full_name": message.full_name(),
+        "package": message.package_name(),
+        "fields": ordered_fields(message.fields(), sort_fields).iter().map(field_to_json).collect::<Vec<_>>(),
+    })
+}
+
+fn field_to_json(field: &FieldDescriptor) -> Value {
+    json!({
+        "name": field.name(),
+        "number": field.number(),
+        "type": field_type(field),
+        "repeated": field.is_list(),
+        "map": field.is_map(),
+    })
+}
+
+fn enum_to_json(enum_desc: &EnumDescriptor) -> Value {
+    json!({
+        "kind": "enum",
+        "name": enum_desc.name(),
+        "full_name": enum_desc.full_name(),
+        "package": enum_desc.package_name(),
+        "values": enum_desc
+            .values()
+            .map(|v| json!({ "name": v.name(), "number": v.number() }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// The type of a field as written in a `.proto` file (e.g. `int32`, `my.package.Message` or
+/// `map<string, int32>`), without its label.
+pub(crate) fn field_type(field: &FieldDescriptor) -> String {
+    match field.kind() {
+        Kind::Message(entry) if field.is_map() => format!(
+            "map<{}, {}>",
+            kind_name(&entry.map_entry_key_field().kind()),
+            kind_name(&entry.map_entry_value_field().kind())
+        ),
+        kind => kind_name(&kind),
+    }
+}
+
+fn kind_name(kind: &Kind) -> String {
+    match kind {
+        Kind::Message(m) => m.full_name().to_string(),
+        Kind::Enum(e) => e.full_name().to_string(),
+        scalar => format!("{scalar:?}").to_lowercase(),
+    }
+}
+
+/// A machine-readable error, shaped like a gRPC status.
+///
+/// Errors that do not come from the server are mapped to the closest gRPC status code,
+/// so scripts can handle every failure in the same way.
+#[derive(Debug)]
+pub struct JsonError {
+    code: Code,
+    message: String,
+    details: Vec<Value>,
+}
+
+impl JsonError {
+    pub fn new(code: Code, message: impl Display) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+            details: vec![],
+        }
+    }
+}
+
+impl From<JsonError> for Value {
+    fn from(err: JsonError) -> Self {
+        json!({
+            "code": code_name(err.code),
+            "code_number": err.code as i32,
+            "message": err.message,
+            "details": err.details,
+        })
+    }
+}
+
+impl From<Status> for JsonError {
+    fn from(status: Status) -> Self {
+        Self {
+            code: status.code(),
+            message: status.message().to_string(),
+            details: status_details(&status),
+        }
+    }
+}
+
+/// The gRPC status code closest to an error.
+///
+/// It is the `code` of the [`JsonError`] printed for the error, and decides the exit code of the
+/// program when the error ends it.
+pub trait StatusCode {
+    fn status_code(&self) -> Code;
+}
+
+impl StatusCode for Status {
+    fn status_code(&self) -> Code {
+        self.code()
+    }
+}
+
+impl StatusCode for online::DynamicCallError {
+    fn status_code(&self) -> Code {
+        match self {
+            online::DynamicCallError::ReflectionResolve(e) => e.status_code(),
+            online::DynamicCallError::DescriptorError(_) => Code::InvalidArgument,
+            online::DynamicCallError::DynamicCallError(e) => e.status_code(),
+        }
+    }
+}
+
+impl From<online::DynamicCallError> for JsonError {
+    fn from(err: online::DynamicCallError) -> Self {
+        match err {
+            online::DynamicCallError::ReflectionResolve(e) => JsonError::from(e),
+            online::DynamicCallError::DynamicCallError(e) => JsonError::from(e),
+            _ => JsonError::new(err.status_code(), err),
+        }
+    }
+}
+
+impl StatusCode for online_without_reflection::DynamicCallError {
+    fn status_code(&self) -> Code {
+        use online_without_reflection::DynamicCallError;
+
+        match self {
+            DynamicCallError::InvalidInput(_) => Code::InvalidArgument,
+            DynamicCallError::ServiceNotFound(_)
+            | DynamicCallError::MethodNotFound(_)
+            | DynamicCallError::MessageNotFound(_) => Code::NotFound,
+            DynamicCallError::GrpcRequestError(e) => e.status_code(),
+        }
+    }
+}
+
+impl From<online_without_reflection::DynamicCallError> for JsonError {
+    fn from(err: online_without_reflection::DynamicCallError) -> Self {
+        JsonError::new(err.status_code(), err)
+    }
+}
+
+impl StatusCode for ReflectionResolveError {
+    fn status_code(&self) -> Code {
+        match self {
+            ReflectionResolveError::ServerStreamInitFailed(status)
+            | ReflectionResolveError::ServerStreamFailure(status) => status.code(),
+            ReflectionResolveError::StreamClosed | ReflectionResolveError::SendFailed => {
+                Code::Unavailable
+            }
+            ReflectionResolveError::ServerError { code, .. } => Code::from(*code),
+            ReflectionResolveError::UnexpectedResponseType(_)
+            | ReflectionResolveError::DecodeError(_) => Code::Internal,
+        }
+    }
+}
+
+impl From<ReflectionResolveError> for JsonError {
+    fn from(err: ReflectionResolveError) -> Self {
+        JsonError::new(err.status_code(), err)
+    }
+}
+
+impl StatusCode for GrpcRequestError {
+    fn status_code(&self) -> Code {
+        match self {
+            GrpcRequestError::ClientNotReady(_) => Code::Unavailable,
+            _ => Code::InvalidArgument,
+        }
+    }
+}
+
+impl From<GrpcRequestError> for JsonError {
+    fn from(err: GrpcRequestError) -> Self {
+        JsonError::new(err.status_code(), err)
+    }
+}
+
+impl StatusCode for DescriptorSetError {
+    fn status_code(&self) -> Code {
+        Code::InvalidArgument
+    }
+}
+
+impl From<DescriptorSetError> for JsonError {
+    fn from(err: DescriptorSetError) -> Self {
+        JsonError::new(err.status_code(), err)
+    }
+}
+
+impl StatusCode for std::io::Error {
+    fn status_code(&self) -> Code {
+        match self.kind() {
+            std::io::ErrorKind::NotFound => Code::NotFound,
+            std::io::ErrorKind::PermissionDenied => Code::PermissionDenied,
+            _ => Code::Unknown,
+        }
+    }
+}
+
+impl From<std::io::Error> for JsonError {
+    fn from(err: std::io::Error) -> Self {
+        JsonError::new(err.status_code(), err)
+    }
+}
+
+impl<T: Display> StatusCode for GenericError<T> {
+    fn status_code(&self) -> Code {
+        Code::Unknown
+    }
+}
+
+impl<T: Display> From<GenericError<T>> for JsonError {
+    fn from(GenericError(msg, err): GenericError<T>) -> Self {
+        JsonError::new(
+            Code::Unknown,
+            format!("{}: {}", msg.trim_end_matches(':'), err),
+        )
+    }
+}
+
+impl StatusCode for online::ClientConnectError {
+    fn status_code(&self) -> Code {
+        match self {
+            online::ClientConnectError::InvalidUri(..) => Code::InvalidArgument,
+            online::ClientConnectError::DnsResolution(..)
+            | online::ClientConnectError::ConnectionRefused(..)
+            | online::ClientConnectError::Timeout(..)
+            | online::ClientConnectError::ConnectionFailed(..) => Code::Unavailable,
+        }
+    }
+}
+
+impl From<online::ClientConnectError> for JsonError {
+    fn from(err: online::ClientConnectError) -> Self {
+        JsonError::new(err.status_code(), err)
+    }
+}
+
+impl StatusCode for online::GetDescriptorError {
+    fn status_code(&self) -> Code {
+        match self {
+            online::GetDescriptorError::ReflectionResolve(e) => e.status_code(),
+            online::GetDescriptorError::DescriptorError(_) => Code::InvalidArgument,
+            online::GetDescriptorError::NotFound(_) => Code::NotFound,
+            online::GetDescriptorError::ResolveMethod(e) => e.status_code(),
+        }
+    }
+}
+
+impl From<online::GetDescriptorError> for JsonError {
+    fn from(err: online::GetDescriptorError) -> Self {
+        match err {
+            online::GetDescriptorError::ReflectionResolve(e) => JsonError::from(e),
+            online::GetDescriptorError::ResolveMethod(e) => JsonError::from(e),
+            _ => JsonError::new(err.status_code(), err),
+        }
+    }
+}
+
+impl StatusCode for ResolveMethodError {
+    fn status_code(&self) -> Code {
+        match self {
+            ResolveMethodError::InvalidPath(_)
+            | ResolveMethodError::TooManySeparators(_)
+            | ResolveMethodError::EmptyName => Code::InvalidArgument,
+            ResolveMethodError::ServiceNotFound(_) | ResolveMethodError::MethodNotFound(_) => {
+                Code::NotFound
+            }
+        }
+    }
+}
+
+impl From<ResolveMethodError> for JsonError {
+    fn from(err: ResolveMethodError) -> Self {
+        JsonError::new(err.status_code(), err)
+    }
+}
+
+/// Returns the canonical name of a gRPC status code (e.g. `NOT_FOUND`).
+fn code_name(code: Code) -> &'static str {
+    match code {
+        Code::Ok => "OK",
+        Code::Cancelled => "CANCELLED",
+        Code::Unknown => "UNKNOWN",
+        Code::InvalidArgument => "INVALID_ARGUMENT",
+        Code::DeadlineExceeded => "DEADLINE_EXCEEDED",
+        Code::NotFound => "NOT_FOUND",
+        Code::AlreadyExists => "ALREADY_EXISTS",
+        Code::PermissionDenied => "PERMISSION_DENIED",
+        Code::ResourceExhausted => "RESOURCE_EXHAUSTED",
+        Code::FailedPrecondition => "FAILED_PRECONDITION",
+        Code::Aborted => "ABORTED",
+        Code::OutOfRange => "OUT_OF_RANGE",
+        Code::Unimplemented => "UNIMPLEMENTED",
+        Code::Internal => "INTERNAL",
+        Code::Unavailable => "UNAVAILABLE",
+        Code::DataLoss => "DATA_LOSS",
+        Code::Unauthenticated => "UNAUTHENTICATED",
+    }
+}
+
+/// The `google.rpc.Status` message carried in the `grpc-status-details-bin` trailer.
+#[derive(Clone, PartialEq, Message)]
+struct RpcStatus {
+    #[prost(int32, tag = "1")]
+    code: i32,
+    #[prost(string, tag = "2")]
+    message: String,
+    #[prost(message, repeated, tag = "3")]
+    details: Vec<RpcAny>,
+}
+
+/// A `google.protobuf.Any` detail attached to a status.
+#[derive(Clone, PartialEq, Message)]
+struct RpcAny {
+    #[prost(string, tag = "1")]
+    type_url: String,
+    #[prost(bytes = "vec", tag = "2")]
+    value: Vec<u8>,
+}
+
+/// The message types used to decode the details of statuses, by fully qualified name.
+static DETAIL_TYPES: Mutex<BTreeMap<String, MessageDescriptor>> = Mutex::new(BTreeMap::new());
+
+/// Makes the details of this type readable in errors, instead of base64 encoded.
+pub fn register_detail_type(message: MessageDescriptor) {
+    detail_types().insert(message.full_name().to_string(), message);
+}
+
+/// The fully qualified names of the detail types of a status that are not registered yet.
+pub fn unknown_detail_types(status: &Status) -> Vec<String> {
+    let Ok(rpc_status) = RpcStatus::decode(status.details()) else {
+        return vec![];
+    };
+    let known = detail_types();
+
+    let mut names: Vec<String> = rpc_status
+        .details
+        .iter()
+        .map(|any| detail_type_name(&any.type_url).to_string())
+        .filter(|name| !known.contains_key(name))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn detail_types() -> MutexGuard<'static, BTreeMap<String, MessageDescriptor>> {
+    DETAIL_TYPES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// `type.googleapis.com/google.rpc.BadRequest` -> `google.rpc.BadRequest`
+fn detail_type_name(type_url: &str) -> &str {
+    type_url.rsplit('/').next().unwrap_or(type_url)
+}
+
+/// Decodes the rich error details of a status, if any.
+///
+/// Details whose type is registered (see [`register_detail_type`]) are rendered like an `Any` in
+/// JSON, the others keep their payload base64 encoded.
+pub fn status_details(status: &Status) -> Vec<Value> {
+    if status.details().is_empty() {
+        return vec![];
+    }
+
+    match RpcStatus::decode(status.details()) {
+        Ok(rpc_status) => rpc_status.details.into_iter().map(detail_to_json).collect(),
+        Err(_) => vec![json!({ "value": BASE64.encode(status.details()) })],
+    }
+}
+
+fn detail_to_json(any: RpcAny) -> Value {
+    let decoded = detail_types()
+        .get(detail_type_name(&any.type_url))
+        .and_then(|message| DynamicMessage::decode(message.clone(), any.value.as_slice()).ok())
+        .and_then(|message| serde_json::to_value(&message).ok());
+
+    match decoded {
+        Some(Value::Object(mut fields)) => {
+            fields.insert("@type".to_string(), json!(any.type_url));
+            Value::Object(fields)
+        }
+        // Well-known types are not objects in JSON, so they are wrapped as in an `Any`
+        Some(value) => json!({ "@type": any.type_url, "value": value }),
+        None => json!({ "@type": any.type_url, "value": BASE64.encode(any.value) }),
+    }
+}
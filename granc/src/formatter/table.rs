@@ -0,0 +1,157 @@
+//! # Table Output
+//!
+//! A small renderer for aligned, box-drawn tables, used by `granc list --output table`.
+//!
+//! Column widths are computed from the uncolored text of each cell, so cells can be
+//! colored without breaking the alignment.
+use super::{FormattedString, MethodList, method_kind, method_path};
+use colored::*;
+use granc_core::prost_reflect::ServiceDescriptor;
+
+/// The services of a server, rendered as a table of service names and method counts.
+///
+/// When `with_methods` is set, the name of every method is listed (indented) under its service.
+pub struct ServiceTable {
+    pub services: Vec<ServiceDescriptor>,
+    pub with_methods: bool,
+}
+
+impl From<ServiceTable> for FormattedString {
+    fn from(table: ServiceTable) -> Self {
+        if table.services.is_empty() {
+            return FormattedString("No services found.".yellow().to_string());
+        }
+
+        let mut rows = Vec::new();
+
+        for service in &table.services {
+            rows.push([
+                Cell::new(service.full_name(), |s| s.green().to_string()),
+                Cell::new(service.methods().len().to_string(), |s| {
+                    s.cyan().to_string()
+                }),
+            ]);
+
+            if table.with_methods {
+                for method in service.methods() {
+                    rows.push([Cell::plain(format!("  {}", method.name())), Cell::plain("")]);
+                }
+            }
+        }
+
+        FormattedString(render(
+            [Cell::plain("Service"), Cell::plain("Methods")],
+            &rows,
+        ))
+    }
+}
+
+/// A list of methods, rendered as a table of method paths and streaming types.
+pub struct MethodTable(pub MethodList);
+
+impl From<MethodTable> for FormattedString {
+    fn from(MethodTable(MethodList(methods)): MethodTable) -> Self {
+        if methods.is_empty() {
+            return FormattedString("No methods found.".yellow().to_string());
+        }
+
+        let rows: Vec<_> = methods
+            .iter()
+            .map(|m| {
+                [
+                    Cell::new(method_path(m), |s| s.green().to_string()),
+                    Cell::new(method_kind(m), |s| s.cyan().to_string()),
+                ]
+            })
+            .collect();
+
+        FormattedString(render([Cell::plain("Method"), Cell::plain("Type")], &rows))
+    }
+}
+
+/// A table cell: the plain text used to measure it, and the (possibly colored) text to print.
+struct Cell {
+    text: String,
+    display: String,
+}
+
+impl Cell {
+    fn new(text: impl Into<String>, style: impl Fn(&str) -> String) -> Self {
+        let text = text.into();
+        let display = style(&text);
+        Self { text, display }
+    }
+
+    fn plain(text: impl Into<String>) -> Self {
+        Self::new(text, str::to_string)
+    }
+
+    fn width(&self) -> usize {
+        self.text.chars().count()
+    }
+}
+
+fn render<const N: usize>(header: [Cell; N], rows: &[[Cell; N]]) -> String {
+    let widths: [usize; N] = std::array::from_fn(|i| {
+        rows.iter()
+            .map(|row| row[i].width())
+            .chain([header[i].width()])
+            .max()
+            .unwrap_or_default()
+    });
+
+    let border = |left: &str, middle: &str, right: &str| {
+        let lines: Vec<_> = widths.iter().map(|w| "─".repeat(w + 2)).collect();
+        format!("{left}{}{right}", lines.join(middle))
+    };
+
+    let line = |row: &[Cell; N]| {
+        let cells: Vec<_> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| {
+                let padding = " ".repeat(width - cell.width());
+                format!(" {}{padding} ", cell.display)
+            })
+            .collect();
+        format!("│{}│", cells.join("│"))
+    };
+
+    let header = header.map(|cell| Cell {
+        display: cell.text.bold().to_string(),
+        text: cell.text,
+    });
+
+    let mut out = vec![border("┌", "┬", "┐"), line(&header), border("├", "┼", "┤")];
+    out.extend(rows.iter().map(line));
+    out.push(border("└", "┴", "┘"));
+
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_aligns_columns() {
+        colored::control::set_override(false);
+
+        let rows = [
+            [Cell::plain("echo.EchoService"), Cell::plain("4")],
+            [Cell::plain("  UnaryEcho"), Cell::plain("")],
+        ];
+
+        let table = render([Cell::plain("Service"), Cell::plain("Methods")], &rows);
+
+        let expected = [
+            "┌──────────────────┬─────────┐",
+            "│ Service          │ Methods │",
+            "├──────────────────┼─────────┤",
+            "│ echo.EchoService │ 4       │",
+            "│   UnaryEcho      │         │",
+            "└──────────────────┴─────────┘",
+        ];
+        assert_eq!(table, expected.join("\n"));
+    }
+}
@@ -0,0 +1,164 @@
+//! # Health
+//!
+//! Support for the standard [gRPC Health Checking Protocol](https://github.com/grpc/grpc/blob/master/doc/health-checking.md).
+//!
+//! The `grpc.health.v1` schema is hardcoded here, so health checks work against servers
+//! that do not expose Server Reflection.
+use granc_core::client::DynamicRequest;
+use prost::Message;
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+    FileDescriptorProto, FileDescriptorSet, MethodDescriptorProto, ServiceDescriptorProto,
+    field_descriptor_proto::{Label, Type},
+};
+
+const SERVICE: &str = "grpc.health.v1.Health";
+const METHOD: &str = "Check";
+
+/// The serving status reported by a health check (e.g. `SERVING`).
+pub struct HealthStatus(pub String);
+
+impl HealthStatus {
+    pub fn is_serving(&self) -> bool {
+        self.0 == "SERVING"
+    }
+}
+
+impl From<serde_json::Value> for HealthStatus {
+    fn from(response: serde_json::Value) -> Self {
+        // `UNKNOWN` is the default enum value, so it is omitted from the JSON response
+        let status = response
+            .get("status")
+            .and_then(|s| s.as_str())
+            .unwrap_or("UNKNOWN");
+
+        HealthStatus(status.to_string())
+    }
+}
+
+/// Builds the request for `grpc.health.v1.Health/Check`.
+///
+/// An empty service name asks for the overall health of the server.
+pub fn check_request(service: Option<String>, headers: Vec<(String, String)>) -> DynamicRequest {
+    DynamicRequest {
+        body: serde_json::json!({ "service": service.unwrap_or_default() }),
+        headers,
+        timeout: None,
+        cancellation: None,
+        strict: false,
+        send_interval: None,
+        max_messages: None,
+        service: SERVICE.to_string(),
+        method: METHOD.to_string(),
+    }
+}
+
+/// Returns the encoded `FileDescriptorSet` of `grpc/health/v1/health.proto`.
+pub fn file_descriptor_set() -> Vec<u8> {
+    let string_field = |name: &str, number: i32| FieldDescriptorProto {
+        name: Some(name.to_string()),
+        number: Some(number),
+        label: Some(Label::Optional as i32),
+        r#type: Some(Type::String as i32),
+        json_name: Some(name.to_string()),
+        ..Default::default()
+    };
+
+    let serving_status = EnumDescriptorProto {
+        name: Some("ServingStatus".to_string()),
+        value: ["UNKNOWN", "SERVING", "NOT_SERVING", "SERVICE_UNKNOWN"]
+            .into_iter()
+            .enumerate()
+            .map(|(number, name)| EnumValueDescriptorProto {
+                name: Some(name.to_string()),
+                number: Some(number as i32),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    };
+
+    let request = DescriptorProto {
+        name: Some("HealthCheckRequest".to_string()),
+        field: vec![string_field("service", 1)],
+        ..Default::default()
+    };
+
+    let response = DescriptorProto {
+        name: Some("HealthCheckResponse".to_string()),
+        field: vec![FieldDescriptorProto {
+            name: Some("status".to_string()),
+            number: Some(1),
+            label: Some(Label::Optional as i32),
+            r#type: Some(Type::Enum as i32),
+            type_name: Some(".grpc.health.v1.HealthCheckResponse.ServingStatus".to_string()),
+            json_name: Some("status".to_string()),
+            ..Default::default()
+        }],
+        enum_type: vec![serving_status],
+        ..Default::default()
+    };
+
+    let method = |name: &str, server_streaming: bool| MethodDescriptorProto {
+        name: Some(name.to_string()),
+        input_type: Some(".grpc.health.v1.HealthCheckRequest".to_string()),
+        output_type: Some(".grpc.health.v1.HealthCheckResponse".to_string()),
+        server_streaming: Some(server_streaming),
+        ..Default::default()
+    };
+
+    let service = ServiceDescriptorProto {
+        name: Some("Health".to_string()),
+        method: vec![method("Check", false), method("Watch", true)],
+        ..Default::default()
+    };
+
+    let file = FileDescriptorProto {
+        name: Some("grpc/health/v1/health.proto".to_string()),
+        package: Some("grpc.health.v1".to_string()),
+        message_type: vec![request, response],
+        service: vec![service],
+        syntax: Some("proto3".to_string()),
+        ..Default::default()
+    };
+
+    FileDescriptorSet { file: vec![file] }.encode_to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use granc_core::prost_reflect::DescriptorPool;
+
+    #[test]
+    fn test_health_descriptor_is_valid() {
+        let pool = DescriptorPool::decode(file_descriptor_set().as_slice())
+            .expect("Failed to decode health descriptor");
+
+        let service = pool
+            .get_service_by_name(SERVICE)
+            .expect("Service not found");
+        let check = service
+            .methods()
+            .find(|m| m.name() == METHOD)
+            .expect("Method not found");
+
+        assert!(!check.is_server_streaming());
+        assert_eq!(
+            check.output().full_name(),
+            "grpc.health.v1.HealthCheckResponse"
+        );
+    }
+
+    #[test]
+    fn test_health_status_from_response() {
+        let status = HealthStatus::from(serde_json::json!({ "status": "SERVING" }));
+        assert!(status.is_serving());
+
+        let status = HealthStatus::from(serde_json::json!({ "status": "NOT_SERVING" }));
+        assert!(!status.is_serving());
+
+        let status = HealthStatus::from(serde_json::json!({}));
+        assert_eq!(status.0, "UNKNOWN");
+    }
+}
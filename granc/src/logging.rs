@@ -0,0 +1,224 @@
+//! # Logging
+//!
+//! Diagnostic logs, enabled with `--verbose` or the `RUST_LOG` environment variable
+//! (e.g. `RUST_LOG=granc=debug,granc_core=trace`).
+//!
+//! Logs are written to stderr, so they never pollute the output of a command.
+//!
+//! This is a minimal [`tracing`] subscriber: it understands `RUST_LOG` style directives
+//! (`level` or `target=level`, comma separated) and prints every enabled event with its fields.
+//! Spans are not recorded.
+use granc_core::grpc::codec::SIZES_TARGET;
+use std::fmt::{Debug, Write};
+use tracing::{
+    Event, Metadata, Subscriber,
+    field::{Field, Visit},
+    level_filters::LevelFilter,
+    span,
+};
+
+/// Installs the stderr logger.
+///
+/// `--verbose` enables debug logs for `granc` and `granc_core`, `--show-sizes` only the sizes of
+/// the messages; `RUST_LOG` directives are applied on top of them. Nothing is installed if none is given.
+pub fn init(verbose: bool, show_sizes: bool) {
+    let mut directives = Vec::new();
+
+    if verbose {
+        directives.extend(parse_directives("granc=debug,granc_core=debug"));
+    }
+
+    if show_sizes {
+        directives.extend(parse_directives(&format!("{SIZES_TARGET}=debug")));
+    }
+
+    if let Ok(spec) = std::env::var("RUST_LOG") {
+        directives.extend(parse_directives(&spec));
+    }
+
+    if directives.is_empty() {
+        return;
+    }
+
+    let _ = tracing::subscriber::set_global_default(StderrLogger { directives });
+}
+
+/// Replaces the value of headers that usually carry credentials, so they can be logged.
+pub fn redact_header<'a>(key: &str, value: &'a str) -> &'a str {
+    const SENSITIVE: [&str; 6] = [
+        "authorization",
+        "cookie",
+        "token",
+        "secret",
+        "password",
+        "key",
+    ];
+
+    let key = key.to_ascii_lowercase();
+
+    if SENSITIVE.iter().any(|s| key.contains(s)) {
+        "<redacted>"
+    } else {
+        value
+    }
+}
+
+/// A single `RUST_LOG` directive: events of `target` (and its submodules) up to `level` are enabled.
+struct Directive {
+    target: Option<String>,
+    level: LevelFilter,
+}
+
+impl Directive {
+    fn matches(&self, target: &str) -> bool {
+        match &self.target {
+            None => true,
+            Some(t) => target == t || target.starts_with(&format!("{t}::")),
+        }
+    }
+}
+
+// Invalid directives are ignored
+fn parse_directives(spec: &str) -> Vec<Directive> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .filter_map(|d| match d.split_once('=') {
+            Some((target, level)) => Some(Directive {
+                target: Some(target.trim().to_string()),
+                level: level.trim().parse().ok()?,
+            }),
+            None => Some(Directive {
+                target: None,
+                level: d.parse().ok()?,
+            }),
+        })
+        .collect()
+}
+
+struct StderrLogger {
+    directives: Vec<Directive>,
+}
+
+impl StderrLogger {
+    // The most specific matching directive wins; among equals, the last one
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.directives
+            .iter()
+            .filter(|d| d.matches(target))
+            .max_by_key(|d| d.target.as_ref().map_or(0, String::len))
+            .map_or(LevelFilter::OFF, |d| d.level)
+    }
+}
+
+impl Subscriber for StderrLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        *metadata.level() <= self.level_for(metadata.target())
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        self.directives.iter().map(|d| d.level).max()
+    }
+
+    fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = FieldWriter::default();
+        event.record(&mut fields);
+
+        let metadata = event.metadata();
+        eprintln!(
+            "{:>5} {}: {}{}",
+            metadata.level(),
+            metadata.target(),
+            fields.message,
+            fields.fields
+        );
+    }
+
+    fn enter(&self, _: &span::Id) {}
+
+    fn exit(&self, _: &span::Id) {}
+}
+
+#[derive(Default)]
+struct FieldWriter {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldWriter {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={value}", field.name());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_most_specific_directive_wins() {
+        let logger = StderrLogger {
+            directives: parse_directives("warn,granc=debug,granc_core::reflection=trace,bad=nope"),
+        };
+
+        assert_eq!(logger.level_for("h2::codec"), LevelFilter::WARN);
+        assert_eq!(logger.level_for("granc"), LevelFilter::DEBUG);
+        assert_eq!(logger.level_for("granc::formatter"), LevelFilter::DEBUG);
+        // `granc` is not a module prefix of `granc_core`
+        assert_eq!(logger.level_for("granc_core::client"), LevelFilter::WARN);
+        assert_eq!(
+            logger.level_for("granc_core::reflection::client"),
+            LevelFilter::TRACE
+        );
+        // Invalid directives are ignored
+        assert_eq!(logger.level_for("bad"), LevelFilter::WARN);
+    }
+
+    #[test]
+    fn test_later_directive_overrides() {
+        let logger = StderrLogger {
+            directives: parse_directives("granc=debug,granc=off"),
+        };
+
+        assert_eq!(logger.level_for("granc"), LevelFilter::OFF);
+    }
+
+    #[test]
+    fn test_sizes_directive_only_enables_sizes() {
+        let logger = StderrLogger {
+            directives: parse_directives(&format!("{SIZES_TARGET}=debug")),
+        };
+
+        assert_eq!(logger.level_for(SIZES_TARGET), LevelFilter::DEBUG);
+        assert_eq!(logger.level_for("granc_core::client"), LevelFilter::OFF);
+        assert_eq!(logger.level_for("granc"), LevelFilter::OFF);
+    }
+
+    #[test]
+    fn test_redact_header() {
+        assert_eq!(redact_header("Authorization", "Bearer abc"), "<redacted>");
+        assert_eq!(redact_header("x-api-key", "abc"), "<redacted>");
+        assert_eq!(redact_header("x-auth-token", "abc"), "<redacted>");
+        assert_eq!(redact_header("x-trace-id", "1234"), "1234");
+    }
+}
@@ -7,43 +7,575 @@
 //!    (connecting to server vs loading local file).
 //! 3. **Execution**: Delegates request processing to `GrancClient`.
 //! 4. **Presentation**: Formats and prints data.
+mod batch;
 mod cli;
+mod config;
+mod diff;
 mod docgen;
 mod formatter;
+mod health;
+mod logging;
+mod transport;
 
-use clap::Parser;
-use cli::{Cli, Commands, Source};
-use formatter::{FormattedString, GenericError};
-use granc_core::client::{Descriptor, DynamicRequest, DynamicResponse, GrancClient};
-use std::process;
+use batch::{Batch, CallOptions};
+use cli::{
+    BodyFormat, BodyOptions, BytesFormat, CallFormat, Cli, ColorChoice, Commands, HeaderFiles,
+    ListFormat, ListTarget, MessageTypes, OutputFormat, Source, parse_bodies, parse_text_bodies,
+};
+use formatter::{
+    Definition, Described, DryRun, FieldProjection, FormattedString, GenericError, Reachability,
+    json::{self, JsonArray, JsonError, JsonString, StatusCode},
+};
+use futures_util::{Stream, StreamExt, stream::BoxStream};
+use granc_core::{
+    client::{
+        Descriptor, DynamicRequest, DynamicResponse, GrancClient, Online, OnlineWithoutReflection,
+        RequestBody, online, online_without_reflection::DynamicCallError,
+    },
+    grpc::{
+        client::metadata_from_headers,
+        codec::{RequestMessage, bytes_as_hex},
+    },
+    prost_reflect::{
+        DescriptorPool, DeserializeOptions, DynamicMessage, FileDescriptor, MessageDescriptor,
+        MethodDescriptor, ServiceDescriptor,
+    },
+    reflection::client::ReflectionResolveError,
+    tokio_util::sync::CancellationToken,
+    tonic::{Code, Status},
+};
+use health::HealthStatus;
+use prost::{Message, bytes::Bytes};
+use std::{
+    error::Error as _,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::OnceLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::Notify;
+use tokio::task::{JoinError, JoinSet};
+use transport::Transport;
+
+/// The output format selected for the running command.
+///
+/// It is set once before dispatching, so that errors raised anywhere in the program
+/// are reported in the same format as the successful output.
+static OUTPUT_FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// How commands connect to the server (`--connect-timeout`, `--grpc-web`, `--reflection-host`
+/// and `--plaintext`).
+static CONNECT_OPTIONS: OnceLock<ConnectOptions> = OnceLock::new();
+
+#[derive(Debug, Clone, Default)]
+struct ConnectOptions {
+    timeout: Option<Duration>,
+    grpc_web: bool,
+    reflection_host: Option<String>,
+    plaintext: bool,
+}
+
+/// Notified by a second Ctrl-C during a call, to end the program right away.
+static ABORTED: Notify = Notify::const_new();
+
+/// Maximum number of reflection requests sent at once when resolving many services.
+const MAX_CONCURRENT_REQUESTS: usize = 8;
 
 #[tokio::main]
 async fn main() {
-    let args = Cli::parse();
+    // --help and --version don't need the config, so an invalid config file doesn't break them
+    Cli::exit_on_help_or_version();
+
+    let config = match config::load().map_err(|e| GenericError("Invalid config file", e)) {
+        Ok(config) => config,
+        Err(err) => process::exit(Failure::from(err).report(&mut std::io::stderr())),
+    };
+    let default_uri = std::env::var("GRANC_URL").ok().or(config.url.clone());
+    let args = Cli::parse_with_default_uri(default_uri.as_deref());
 
-    match args.command {
+    configure_colors(args.color_choice());
+    logging::init(args.verbose, args.show_sizes);
+    let _ = CONNECT_OPTIONS.set(ConnectOptions {
+        timeout: args.connect_timeout,
+        grpc_web: args.grpc_web,
+        reflection_host: args.reflection_host,
+        plaintext: args.plaintext,
+    });
+
+    let (mut stdout, mut stderr) = (std::io::stdout(), std::io::stderr());
+    let code = tokio::select! {
+        code = run(args.command, &config, &mut stdout, &mut stderr) => code,
+        () = ABORTED.notified() => exit_code(Code::Cancelled),
+    };
+
+    // Exiting explicitly doesn't wait for the blocking thread reading stdin with `--stdin`, which
+    // only ends on the next line
+    process::exit(code);
+}
+
+/// Runs a command, writing its results to `out` and its errors to `err`.
+///
+/// # Returns
+///
+/// The exit code of the process: `0` on success, see [`exit_code`] otherwise.
+async fn run(
+    command: Commands,
+    config: &config::Config,
+    out: &mut impl Write,
+    err: &mut impl Write,
+) -> i32 {
+    let result = execute(command, config, out).await;
+    // Errors come after the output they may refer to (e.g. an interrupted stream)
+    let _ = out.flush();
+
+    match result {
+        Ok(()) => 0,
+        Err(failure) => failure.report(err),
+    }
+}
+
+async fn execute(command: Commands, config: &config::Config, out: &mut impl Write) -> Outcome {
+    match command {
         Commands::Call {
             endpoint,
             uri,
             body,
+            expand_env,
+            lenient_json,
+            body_format,
+            mut headers,
+            header_sources,
+            file_descriptor_set,
+            output,
+            watch,
+            timeout,
+            dry_run,
+            strict,
+            send_interval,
+            print_grpcurl,
+            raw,
+            raw_output,
+            bytes,
+            response: response_options,
+            stdin,
+            body_file,
+            message_types,
+        } => {
+            // The results of a body file are NDJSON, like those of a batch
+            set_output_format(match body_file {
+                Some(_) => OutputFormat::Json,
+                None => output.into(),
+            });
+
+            let (service, method) = endpoint;
+            let HeaderFiles {
+                header_files,
+                headers_file,
+            } = *header_sources;
+            headers.extend(read_header_files(header_files).or_fail()?);
+            if let Some(path) = headers_file {
+                headers = config::merge_headers(&read_headers_file(&path).or_fail()?, headers);
+            }
+            let headers = config.with_default_headers(headers);
+
+            tracing::debug!("Calling /{service}/{method} on {uri}");
+            for (key, value) in &headers {
+                tracing::debug!("Header {key}: {}", logging::redact_header(key, value));
+            }
+
+            let body_options = BodyOptions {
+                expand_env,
+                lenient: lenient_json,
+            };
+
+            if let Some(path) = raw {
+                let body = std::fs::read(path).or_fail()?;
+                let mut client = CallClient::connect(&uri, file_descriptor_set, &headers).await?;
+                let response = client
+                    .raw(&service, &method, body.into(), headers, timeout)
+                    .await?;
+
+                return match (response, raw_output) {
+                    (Ok(bytes), Some(path)) => std::fs::write(path, bytes).or_fail(),
+                    (Ok(bytes), None) => out
+                        .write_all(&bytes)
+                        .map_err(|e| GenericError("Failed to write the output", e))
+                        .or_fail(),
+                    (Err(status), _) => write_output(out, status),
+                };
+            }
+
+            if let Some(path) = body_file {
+                let content = read_script(&path, "Failed to read the body file")?;
+                let client = CallClient::connect(&uri, file_descriptor_set, &headers).await?;
+                let mut batch = Batch::new(client, headers, timeout).with_options(CallOptions {
+                    strict,
+                    send_interval,
+                    max_messages: response_options.max_messages,
+                });
+                let mut results = vec![];
+                let mut failed = false;
+
+                for (number, line) in content.lines().enumerate() {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+
+                    let result = batch
+                        .run_body(number + 1, &service, &method, line, body_options)
+                        .await;
+                    failed |= result.is_error();
+
+                    if output == CallFormat::JsonArray {
+                        results.push(serde_json::Value::from(result));
+                    } else {
+                        write_line(out, JsonString::from(result))?;
+                    }
+                }
+
+                if output == CallFormat::JsonArray {
+                    write_line(out, JsonString::from(serde_json::Value::Array(results)))?;
+                }
+
+                return if failed {
+                    Err(Failure::exit(1))
+                } else {
+                    Ok(())
+                };
+            }
+
+            let (body, text_bodies) = match body_format {
+                BodyFormat::Json => (
+                    parse_bodies(body, body_options)
+                        .map_err(|e| GenericError("Invalid body", e))
+                        .or_fail()?,
+                    None,
+                ),
+                // Parsed once the input type of the method is known
+                BodyFormat::Textproto => (serde_json::Value::Null, Some(body)),
+            };
+
+            let request = DynamicRequest {
+                service,
+                method,
+                body,
+                headers,
+                timeout,
+                cancellation: None,
+                strict,
+                send_interval,
+                max_messages: response_options.max_messages,
+            };
+
+            if print_grpcurl {
+                eprintln!(
+                    "{}",
+                    grpcurl_command(&uri, file_descriptor_set.as_deref(), &request)
+                );
+            }
+
+            if dry_run {
+                let method = resolve_method(&uri, file_descriptor_set, &request).await?;
+                let dry_run = match text_bodies {
+                    Some(bodies) => {
+                        let messages = text_messages(bodies, &method.input(), body_options)?;
+                        validate_request(method, request.with_body(messages))
+                    }
+                    None => validate_request(method, request),
+                };
+                return write_output(out, dry_run.or_fail()?);
+            }
+
+            let mut client =
+                CallClient::connect(&uri, file_descriptor_set, &request.headers).await?;
+            let projection = FieldProjection::new(&response_options.fields);
+
+            if let MessageTypes {
+                request_type: Some(request_type),
+                response_type: Some(response_type),
+            } = *message_types
+            {
+                let CallClient::FileDescriptor(mut client) = client else {
+                    unreachable!("Clap ensures that message types come with a file descriptor set")
+                };
+
+                let hex_output = match bytes {
+                    BytesFormat::Base64 => None,
+                    BytesFormat::Hex => client
+                        .get_descriptor_by_symbol(&response_type)
+                        .and_then(|descriptor| descriptor.message_descriptor().cloned()),
+                };
+
+                let response = match text_bodies {
+                    Some(bodies) => {
+                        // An unknown request type is reported by the call itself
+                        let messages = client
+                            .get_descriptor_by_symbol(&request_type)
+                            .and_then(|descriptor| descriptor.message_descriptor().cloned())
+                            .map(|input| text_messages(bodies, &input, body_options))
+                            .transpose()?
+                            .unwrap_or_default();
+                        let request = request.with_body(messages);
+
+                        client
+                            .dynamic_with_types(request, &request_type, &response_type)
+                            .await
+                    }
+                    None => {
+                        client
+                            .dynamic_with_types(request, &request_type, &response_type)
+                            .await
+                    }
+                };
+                let response = with_hex_bytes(response.or_fail()?, hex_output.as_ref());
+                let response = with_fields(response, &projection);
+                if let Some(status) = response_status(&response) {
+                    register_offline_detail_types(&client, status);
+                }
+                let error = response_error(&response);
+                write_output(out, response)?;
+
+                return match error {
+                    Some(code) => Err(Failure::exit(exit_code(code))),
+                    None => Ok(()),
+                };
+            }
+
+            let text_messages = match text_bodies {
+                Some(bodies) => {
+                    let input = client
+                        .method_descriptor(&request.service, &request.method)
+                        .await?
+                        .input();
+                    Some(text_messages(bodies, &input, body_options)?)
+                }
+                None => None,
+            };
+
+            let hex_output = match bytes {
+                BytesFormat::Base64 => None,
+                BytesFormat::Hex => Some(
+                    client
+                        .method_descriptor(&request.service, &request.method)
+                        .await?
+                        .output(),
+                ),
+            };
+
+            if stdin {
+                return interactive_call(client, request, hex_output, &projection, out).await;
+            }
+
+            match (watch, text_messages) {
+                (Some(interval), Some(messages)) => {
+                    let request = request.with_body(messages);
+                    watch_call(client, request, interval, hex_output, &projection, out).await
+                }
+                (Some(interval), None) => {
+                    watch_call(client, request, interval, hex_output, &projection, out).await
+                }
+                (None, Some(messages)) => {
+                    let request = request.with_body(messages);
+                    single_call(client, request, hex_output, &projection, output, out).await
+                }
+                (None, None) => {
+                    single_call(client, request, hex_output, &projection, output, out).await
+                }
+            }
+        }
+
+        Commands::Batch {
+            script,
+            uri,
             headers,
             file_descriptor_set,
+            timeout,
         } => {
-            let response = call(endpoint, uri, body, headers, file_descriptor_set).await;
-            println!("{}", FormattedString::from(response))
+            set_output_format(OutputFormat::Json);
+
+            let content = read_script(&script, "Failed to read the script")?;
+
+            let headers = config.with_default_headers(headers);
+            let client = CallClient::connect(&uri, file_descriptor_set, &headers).await?;
+            let mut batch = Batch::new(client, headers, timeout);
+            let mut failed = false;
+
+            for (number, line) in content.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let result = batch.run(number + 1, line).await;
+                failed |= result.is_error();
+                write_line(out, JsonString::from(result))?;
+            }
+
+            if failed {
+                Err(Failure::exit(1))
+            } else {
+                Ok(())
+            }
         }
 
-        Commands::List { source } => {
-            let services = list(source.value()).await;
-            println!(
-                "{}",
-                FormattedString::from(formatter::ServiceList(services))
+        Commands::List {
+            target,
+            source,
+            output,
+            with_methods,
+            summary,
+            include_reflection,
+            headers,
+        } => {
+            set_output_format(output.into());
+            let headers = config.with_default_headers(headers);
+
+            match (target, output, with_methods) {
+                (ListTarget::Methods, output, _) => {
+                    let mut methods: Vec<_> =
+                        list_with_descriptors(source.value(), include_reflection, &headers)
+                            .await?
+                            .iter()
+                            .flat_map(|service| service.methods())
+                            .collect();
+                    methods.sort_by(|a, b| a.full_name().cmp(b.full_name()));
+
+                    let methods = formatter::MethodList(methods);
+                    match output {
+                        ListFormat::Table => {
+                            let table = formatter::table::MethodTable(methods);
+                            write_line(out, FormattedString::from(table))
+                        }
+                        _ => write_output(out, methods),
+                    }
+                }
+                (ListTarget::Services, ListFormat::Table, _) => {
+                    let services =
+                        list_with_descriptors(source.value(), include_reflection, &headers).await?;
+                    let table = formatter::table::ServiceTable {
+                        services,
+                        with_methods,
+                    };
+                    write_line(out, FormattedString::from(table))
+                }
+                (ListTarget::Services, _, _) if summary => {
+                    let services =
+                        list_with_descriptors(source.value(), include_reflection, &headers).await?;
+                    write_output(out, formatter::ServiceSummaryList(services))
+                }
+                (ListTarget::Services, _, true) => {
+                    let services =
+                        list_with_descriptors(source.value(), include_reflection, &headers).await?;
+                    write_output(out, formatter::ServiceMethodsList(services))
+                }
+                (ListTarget::Services, _, false) => {
+                    let services = list(source.value(), include_reflection, &headers).await?;
+                    write_output(out, formatter::ServiceList(services))
+                }
+            }
+        }
+
+        Commands::Describe {
+            symbol,
+            source,
+            output,
+            sort_fields,
+            expand,
+            raw,
+            headers,
+        } => {
+            set_output_format(output);
+            let headers = config.with_default_headers(headers);
+            let definition = describe(symbol, source.value(), &headers).await?;
+            if raw {
+                write_output(out, formatter::raw_files(&definition))
+            } else {
+                write_output(out, Described::new(definition, expand, sort_fields))
+            }
+        }
+
+        Commands::File {
+            source,
+            filename,
+            output,
+            headers,
+        } => {
+            set_output_format(output);
+            let headers = config.with_default_headers(headers);
+            let file = file(filename, source.value(), &headers).await?;
+            write_output(out, file)
+        }
+
+        Commands::Health {
+            uri,
+            service,
+            output,
+            headers,
+        } => {
+            set_output_format(output);
+            let headers = config.with_default_headers(headers);
+            let status = health(uri, service, headers).await?;
+            let serving = status.is_serving();
+            write_output(out, status)?;
+
+            if serving {
+                Ok(())
+            } else {
+                Err(Failure::exit(1))
+            }
+        }
+
+        Commands::Ping {
+            uri,
+            no_reflection,
+            headers,
+            output,
+        } => {
+            set_output_format(output);
+            let headers = config.with_default_headers(headers);
+            write_output(out, ping(uri, !no_reflection, &headers).await?)
+        }
+
+        Commands::Export {
+            symbol,
+            uri,
+            all,
+            output,
+            headers,
+        } => {
+            let symbol = match (symbol, all) {
+                (Some(symbol), false) => Some(symbol),
+                (None, true) => None,
+                _ => unreachable!("Clap ensures that either a symbol or --all is given"),
+            };
+            let headers = config.with_default_headers(headers);
+            let file_count = export(uri, symbol, &output, &headers).await?;
+            write_line(
+                out,
+                format!("Exported {file_count} files to '{}'.", output.display()),
             )
         }
 
-        Commands::Describe { symbol, source } => {
-            let descriptor = describe(symbol, source.value()).await;
-            println!("{}", FormattedString::from(descriptor))
+        Commands::Diff {
+            old,
+            new,
+            headers,
+            output,
+        } => {
+            set_output_format(output);
+            let headers = config.with_default_headers(headers);
+            let old = schema(old.value(), &headers).await?;
+            let new = schema(new.value(), &headers).await?;
+            let diff = diff::SchemaDiff::new(&old, &new);
+            let breaking = diff.is_breaking();
+            write_output(out, diff)?;
+
+            if breaking {
+                Err(Failure::exit(1))
+            } else {
+                Ok(())
+            }
         }
 
         // Add the Doc handler
@@ -51,107 +583,1343 @@ async fn main() {
             symbol,
             source,
             output,
+            single_file,
+            mermaid,
+            sort_fields,
+            headers,
         } => {
-            let descriptor = describe(symbol.clone(), source.value()).await;
-
-            let service_descriptor = descriptor
-                .service_descriptor()
-                .cloned()
+            let headers = config.with_default_headers(headers);
+            let service_descriptor =
+                match describe(symbol.clone(), source.value(), &headers).await? {
+                    Definition::Symbol(descriptor) => descriptor.service_descriptor().cloned(),
+                    Definition::Method(_) => None,
+                }
                 .ok_or(GenericError("The symbol must be a Service", symbol))
-                .unwrap_or_exit();
+                .or_fail()?;
+
+            let layout = if single_file {
+                docgen::markdown::Layout::SingleFile
+            } else {
+                docgen::markdown::Layout::PerPackage
+            };
 
-            docgen::markdown::generate(output, service_descriptor)
-                .map_err(|e| GenericError("Failed to generate docs", e))
-                .unwrap_or_exit();
+            docgen::markdown::generate(
+                output,
+                service_descriptor,
+                layout,
+                mermaid,
+                sort_fields,
+                out,
+            )
+            .map_err(|e| GenericError("Failed to generate docs", e))
+            .or_fail()?;
 
-            println!("Documentation generated successfully.");
+            write_line(out, "Documentation generated successfully.")
         }
     }
 }
 
-async fn call(
-    endpoint: (String, String),
-    uri: String,
-    body: serde_json::Value,
-    headers: Vec<(String, String)>,
-    file_descriptor_set: Option<std::path::PathBuf>,
-) -> DynamicResponse {
-    let (service, method) = endpoint;
+/// A client connected to a server, resolving schemas either via reflection or via a local descriptor set.
+enum CallClient {
+    Reflection(Box<GrancClient<Online<Transport>>>),
+    FileDescriptor(GrancClient<OnlineWithoutReflection<Transport>>),
+}
+
+impl CallClient {
+    async fn connect(
+        uri: &str,
+        file_descriptor_set: Option<PathBuf>,
+        headers: &[(String, String)],
+    ) -> Outcome<Self> {
+        let client = connect(uri, headers).await?;
+
+        match file_descriptor_set {
+            Some(path) => {
+                tracing::debug!("Resolving schemas from file {}", path.display());
+                let bytes = std::fs::read(path).or_fail()?;
+                let client = client.with_file_descriptor(bytes).or_fail()?;
+                Ok(CallClient::FileDescriptor(client))
+            }
+            None => {
+                tracing::debug!("Resolving schemas via server reflection");
+                Ok(CallClient::Reflection(Box::new(client)))
+            }
+        }
+    }
+
+    async fn dynamic<B: RequestBody>(
+        &mut self,
+        request: DynamicRequest<B>,
+    ) -> Outcome<DynamicResponse> {
+        match self {
+            CallClient::Reflection(client) => client.dynamic(request).await.or_fail(),
+            CallClient::FileDescriptor(client) => client.dynamic(request).await.or_fail(),
+        }
+    }
+
+    async fn raw(
+        &mut self,
+        service: &str,
+        method: &str,
+        body: Bytes,
+        headers: Vec<(String, String)>,
+        timeout: Option<Duration>,
+    ) -> Outcome<Result<Bytes, Status>> {
+        let result = match self {
+            CallClient::Reflection(client) => {
+                client.raw(service, method, body, headers, timeout).await
+            }
+            CallClient::FileDescriptor(client) => {
+                client.raw(service, method, body, headers, timeout).await
+            }
+        };
+
+        result.or_fail()
+    }
+
+    // The responses of a bidirectional stream, a status being its last item if the call failed
+    async fn bidirectional_stream(
+        &mut self,
+        request: DynamicRequest,
+        messages: impl Stream<Item = serde_json::Value> + Send + 'static,
+    ) -> Outcome<BoxStream<'static, Result<serde_json::Value, Status>>> {
+        let DynamicRequest {
+            service,
+            method,
+            headers,
+            timeout,
+            cancellation,
+            ..
+        } = request;
+
+        let result = match self {
+            CallClient::Reflection(client) => client
+                .bidirectional_stream(&service, &method, messages, headers, timeout, cancellation)
+                .await
+                .or_fail()?
+                .map(StreamExt::boxed),
+            CallClient::FileDescriptor(client) => client
+                .bidirectional_stream(&service, &method, messages, headers, timeout, cancellation)
+                .await
+                .or_fail()?
+                .map(StreamExt::boxed),
+        };
+
+        Ok(result
+            .unwrap_or_else(|status| futures_util::stream::once(async { Err(status) }).boxed()))
+    }
+
+    async fn method_descriptor(
+        &mut self,
+        service: &str,
+        method: &str,
+    ) -> Outcome<MethodDescriptor> {
+        let descriptor = match self {
+            CallClient::Reflection(client) => {
+                client.get_descriptor_by_symbol(service).await.or_fail()?
+            }
+            CallClient::FileDescriptor(client) => client
+                .get_descriptor_by_symbol(service)
+                .ok_or(DynamicCallError::ServiceNotFound(service.to_string()))
+                .or_fail()?,
+        };
+
+        find_method(descriptor, service, method)
+    }
+
+    // Looks up the message types of the details of `status`, so they are printed decoded
+    async fn register_detail_types(&mut self, status: &Status) {
+        match self {
+            CallClient::Reflection(client) => {
+                for name in json::unknown_detail_types(status) {
+                    match client.get_descriptor_by_symbol(&name).await {
+                        Ok(Descriptor::MessageDescriptor(message)) => {
+                            json::register_detail_type(message)
+                        }
+                        _ => tracing::debug!("No schema for the status detail type '{name}'"),
+                    }
+                }
+            }
+            CallClient::FileDescriptor(client) => register_offline_detail_types(client, status),
+        }
+    }
+}
+
+fn register_offline_detail_types(
+    client: &GrancClient<OnlineWithoutReflection<Transport>>,
+    status: &Status,
+) {
+    for name in json::unknown_detail_types(status) {
+        match client.get_descriptor_by_symbol(&name) {
+            Some(Descriptor::MessageDescriptor(message)) => json::register_detail_type(message),
+            _ => tracing::debug!("No schema for the status detail type '{name}'"),
+        }
+    }
+}
+
+// Connects to the server, honouring `--connect-timeout`, `--grpc-web` and `--plaintext`
+async fn connect(
+    uri: &str,
+    headers: &[(String, String)],
+) -> Outcome<GrancClient<Online<Transport>>> {
+    let options = CONNECT_OPTIONS.get().cloned().unwrap_or_default();
+    let uri = &connect_uri(uri, options.plaintext);
+    // Servers that require authentication usually require it for reflection too
+    let metadata = metadata_from_headers(headers.to_vec()).or_fail()?;
+
+    let client = if options.grpc_web {
+        tracing::debug!("Using the gRPC-Web transport");
+        let client = GrancClient::grpc_web(uri, options.timeout)
+            .await
+            .or_fail()?;
+        client.map_service(|channel| Transport::GrpcWeb(Box::new(channel)))
+    } else {
+        let result = match options.timeout {
+            Some(timeout) => GrancClient::connect_with_timeout(uri, timeout).await,
+            None => GrancClient::connect(uri).await,
+        };
+
+        result.or_fail()?.map_service(Transport::Grpc)
+    };
+
+    Ok(client.map_reflection_client(|reflection| {
+        let reflection = reflection.with_metadata(metadata);
+
+        match options.reflection_host {
+            Some(host) => reflection.with_host(host),
+            None => reflection,
+        }
+    }))
+}
+
+/// The URI to connect to: with `--plaintext`, the scheme (if any) is replaced by `http://`.
+fn connect_uri(uri: &str, plaintext: bool) -> String {
+    if !plaintext {
+        return uri.to_string();
+    }
+
+    let address = uri.split_once("://").map_or(uri, |(_, address)| address);
+
+    format!("http://{address}")
+}
+
+fn find_method(descriptor: Descriptor, service: &str, method: &str) -> Outcome<MethodDescriptor> {
+    descriptor
+        .service_descriptor()
+        .ok_or(GenericError("The symbol must be a Service", service))
+        .or_fail()?
+        .methods()
+        .find(|m| m.name() == method)
+        .ok_or(DynamicCallError::MethodNotFound(method.to_string()))
+        .or_fail()
+}
 
-    let request = DynamicRequest {
-        service,
+// Resolves the method of a dry run. A local descriptor set is used without connecting to the server.
+async fn resolve_method(
+    uri: &str,
+    file_descriptor_set: Option<PathBuf>,
+    request: &DynamicRequest,
+) -> Outcome<MethodDescriptor> {
+    match file_descriptor_set {
+        Some(path) => {
+            let bytes = std::fs::read(path).or_fail()?;
+            let client = GrancClient::offline(bytes).or_fail()?;
+            let descriptor = client
+                .get_descriptor_by_symbol(&request.service)
+                .ok_or(DynamicCallError::ServiceNotFound(request.service.clone()))
+                .or_fail()?;
+
+            find_method(descriptor, &request.service, &request.method)
+        }
+        None => {
+            let mut client = CallClient::connect(uri, None, &request.headers).await?;
+            client
+                .method_descriptor(&request.service, &request.method)
+                .await
+        }
+    }
+}
+
+/// Checks the body of `request` against the input message of `method`, the same way the call would.
+fn validate_request<B: RequestBody>(
+    method: MethodDescriptor,
+    request: DynamicRequest<B>,
+) -> Result<DryRun, DynamicCallError> {
+    let bodies = request
+        .body
+        .into_messages(method.is_client_streaming())
+        .map_err(|expected| {
+            DynamicCallError::InvalidInput(format!("'{}' expects {expected}", method.full_name()))
+        })?;
+
+    let messages = bodies
+        .into_iter()
+        .map(|body| {
+            if request.strict
+                && let Some(err) = body.find_unknown_field(&method.input())
+            {
+                return Err(DynamicCallError::InvalidInput(err.to_string()));
+            }
+
+            let message = body
+                .into_message(&method.input(), &DeserializeOptions::new())
+                .map_err(|status| DynamicCallError::InvalidInput(status.message().to_string()))?;
+
+            serde_json::to_value(&message)
+                .map_err(|e| DynamicCallError::InvalidInput(e.to_string()))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(DryRun {
         method,
-        body,
-        headers,
+        headers: request.headers,
+        messages,
+    })
+}
+
+/// Sends a call once and prints its response, failing with the status code of the call if it failed.
+///
+/// Ctrl-C cancels the call: a stream ends cleanly with the messages received so far.
+async fn single_call<B: RequestBody>(
+    mut client: CallClient,
+    mut request: DynamicRequest<B>,
+    hex_output: Option<MessageDescriptor>,
+    projection: &FieldProjection,
+    output: CallFormat,
+    out: &mut impl Write,
+) -> Outcome {
+    let cancellation = cancel_on_ctrl_c();
+    request.cancellation = Some(cancellation.clone());
+
+    let response = client.dynamic(request).await?;
+    let response = with_hex_bytes(response, hex_output.as_ref());
+    let response = with_fields(response, projection);
+    if let Some(status) = response_status(&response) {
+        client.register_detail_types(status).await;
+    }
+    let error = response_error(&response);
+
+    if output == CallFormat::JsonArray {
+        write_line(out, JsonString::from(JsonArray(response)))?
+    } else {
+        write_output(out, response)?
+    }
+
+    if let Some(code) = error {
+        return Err(Failure::exit(exit_code(code)));
+    }
+
+    // A stream ends cleanly, with the messages received before Ctrl-C
+    if cancellation.is_cancelled() {
+        return Err(interrupted());
+    }
+    Ok(())
+}
+
+/// Re-runs a unary call every `interval`, reprinting the response each time (like `watch`).
+///
+/// The loop only ends when the process is interrupted (Ctrl-C), or when writing the output fails.
+async fn watch_call<B: RequestBody + Clone>(
+    mut client: CallClient,
+    request: DynamicRequest<B>,
+    interval: Duration,
+    hex_output: Option<MessageDescriptor>,
+    projection: &FieldProjection,
+    out: &mut impl Write,
+) -> Outcome {
+    let method = client
+        .method_descriptor(&request.service, &request.method)
+        .await?;
+
+    if method.is_client_streaming() || method.is_server_streaming() {
+        return Err(GenericError(
+            "Watch mode only supports unary methods",
+            method.full_name().to_string(),
+        ))
+        .or_fail();
+    }
+
+    let clear_screen = output_format() == OutputFormat::Text && std::io::stdout().is_terminal();
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+
+        let response = client.dynamic(request.clone()).await?;
+        let response = with_hex_bytes(response, hex_output.as_ref());
+        let response = with_fields(response, projection);
+        if let Some(status) = response_status(&response) {
+            client.register_detail_types(status).await;
+        }
+
+        if clear_screen {
+            // Clear the screen and move the cursor to the top-left corner
+            write!(out, "\x1B[2J\x1B[H")
+                .map_err(|e| GenericError("Failed to write the output", e))
+                .or_fail()?;
+        }
+
+        if output_format() == OutputFormat::Text {
+            write_line(
+                out,
+                format!(
+                    "Every {:?}: {}/{}    {}",
+                    interval,
+                    request.service,
+                    request.method,
+                    utc_timestamp(SystemTime::now())
+                ),
+            )?;
+        }
+
+        write_output(out, response)?;
+    }
+}
+
+// Sends the lines typed on stdin as the messages of a bidirectional stream, printing every
+// response as soon as it arrives. The call ends once stdin is closed (e.g. with Ctrl-D), or
+// after `--max-messages` responses.
+async fn interactive_call(
+    mut client: CallClient,
+    mut request: DynamicRequest,
+    hex_output: Option<MessageDescriptor>,
+    projection: &FieldProjection,
+    out: &mut impl Write,
+) -> Outcome {
+    let max_messages = request.max_messages;
+    let cancellation = cancel_on_ctrl_c();
+    request.cancellation = Some(cancellation.clone());
+
+    let mut responses = client
+        .bidirectional_stream(request, stdin_messages())
+        .await?
+        .take(max_messages.unwrap_or(usize::MAX));
+    let mut failed = None;
+
+    while let Some(response) = responses.next().await {
+        match response {
+            Ok(mut value) => {
+                if let Some(output) = &hex_output {
+                    bytes_as_hex(output, &mut value);
+                }
+                write_output(out, projection.apply(value))?;
+            }
+            Err(status) => {
+                failed = Some(status.code());
+                client.register_detail_types(&status).await;
+                write_output(out, status)?;
+            }
+        }
+    }
+
+    if let Some(code) = failed {
+        return Err(Failure::exit(exit_code(code)));
+    }
+
+    if cancellation.is_cancelled() {
+        return Err(interrupted());
+    }
+    Ok(())
+}
+
+/// Cancels the call on Ctrl-C instead of killing the process, so that its stream is closed with
+/// `CANCEL` and the responses received so far are still printed.
+///
+/// A second Ctrl-C ends the program right away (see [`ABORTED`]), e.g. while a schema is being
+/// resolved.
+fn cancel_on_ctrl_c() -> CancellationToken {
+    let token = CancellationToken::new();
+    let cancellation = token.clone();
+
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        cancellation.cancel();
+
+        let _ = tokio::signal::ctrl_c().await;
+        ABORTED.notify_one();
+    });
+
+    token
+}
+
+// The failure of a call interrupted by Ctrl-C, reported once its responses are printed
+fn interrupted() -> Failure {
+    Failure::from(Status::cancelled("Interrupted, the call was cancelled"))
+}
+
+// The messages typed on stdin, one JSON object per line. Invalid lines are reported and not sent.
+fn stdin_messages() -> impl Stream<Item = serde_json::Value> + Send + 'static {
+    let lines = BufReader::new(tokio::io::stdin()).lines();
+
+    futures_util::stream::unfold(lines, |mut lines| async move {
+        loop {
+            match lines.next_line().await {
+                Ok(Some(line)) if line.trim().is_empty() => continue,
+                Ok(Some(line)) => match serde_json::from_str(&line) {
+                    Ok(message) => return Some((message, lines)),
+                    Err(e) => eprintln!(
+                        "{}",
+                        render_error(GenericError("Invalid JSON, the line was not sent", e))
+                    ),
+                },
+                Ok(None) => return None,
+                Err(e) => {
+                    eprintln!("{}", render_error(GenericError("Failed to read stdin", e)));
+                    return None;
+                }
+            }
+        }
+    })
+}
+
+/// With `--body-format textproto`, parses the bodies as messages of the `input` type.
+fn text_messages(
+    bodies: Vec<String>,
+    input: &MessageDescriptor,
+    options: BodyOptions,
+) -> Outcome<Vec<DynamicMessage>> {
+    parse_text_bodies(bodies, input, options)
+        .map_err(|e| GenericError("Invalid body", e))
+        .or_fail()
+}
+
+/// With `--bytes hex`, rewrites the `bytes` fields of every response message, described by `output`.
+fn with_hex_bytes(
+    response: DynamicResponse,
+    output: Option<&MessageDescriptor>,
+) -> DynamicResponse {
+    let Some(output) = output else {
+        return response;
     };
 
-    let mut client = GrancClient::connect(&uri).await.unwrap_or_exit();
+    map_messages(response, |mut value| {
+        bytes_as_hex(output, &mut value);
+        value
+    })
+}
+
+/// With `--fields`, keeps only the projected fields of every response message.
+fn with_fields(response: DynamicResponse, projection: &FieldProjection) -> DynamicResponse {
+    if projection.is_empty() {
+        return response;
+    }
+
+    map_messages(response, |value| projection.apply(value))
+}
+
+fn map_messages(
+    response: DynamicResponse,
+    rewrite: impl Fn(serde_json::Value) -> serde_json::Value,
+) -> DynamicResponse {
+    match response {
+        DynamicResponse::Unary(kind, result) => DynamicResponse::Unary(kind, result.map(rewrite)),
+        DynamicResponse::Streaming(kind, result) => DynamicResponse::Streaming(
+            kind,
+            result.map(|items| items.into_iter().map(|item| item.map(&rewrite)).collect()),
+        ),
+    }
+}
+
+/// Reads the value of every `--header-file`, without surrounding whitespace (e.g. a trailing newline).
+fn read_header_files(
+    header_files: Vec<(String, PathBuf)>,
+) -> Result<Vec<(String, String)>, GenericError<String>> {
+    header_files
+        .into_iter()
+        .map(|(key, path)| match std::fs::read_to_string(&path) {
+            Ok(value) => Ok((key, value.trim().to_string())),
+            Err(e) => Err(GenericError(
+                "Failed to read header file",
+                format!("{}: {e}", path.display()),
+            )),
+        })
+        .collect()
+}
+
+/// Reads the headers of a `--headers-file`, a JSON object whose values are strings.
+fn read_headers_file(path: &Path) -> Result<Vec<(String, String)>, GenericError<String>> {
+    let invalid =
+        |e: String| GenericError("Invalid headers file", format!("{}: {e}", path.display()));
+
+    let content = std::fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+    let headers: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+
+    headers
+        .into_iter()
+        .map(|(key, value)| match value {
+            serde_json::Value::String(value) => Ok((key, value)),
+            other => Err(invalid(format!(
+                "the value of '{key}' is not a string: {other}"
+            ))),
+        })
+        .collect()
+}
+
+/// Builds a `grpcurl` command line that makes the same call as `request`.
+///
+/// Every argument is quoted for POSIX shells, so the command can be pasted as is.
+/// Stream messages are passed to `-d` one after the other, as grpcurl expects them.
+fn grpcurl_command(
+    uri: &str,
+    file_descriptor_set: Option<&Path>,
+    request: &DynamicRequest,
+) -> String {
+    let mut args = vec!["grpcurl".to_string()];
+    let plaintext = CONNECT_OPTIONS
+        .get()
+        .is_some_and(|options| options.plaintext);
+    let uri = &connect_uri(uri, plaintext);
+
+    // grpcurl takes a `host:port` address and uses TLS unless told otherwise
+    let address = match uri.strip_prefix("http://") {
+        Some(address) => {
+            args.push("-plaintext".to_string());
+            address
+        }
+        None => uri.strip_prefix("https://").unwrap_or(uri),
+    };
 
     if let Some(path) = file_descriptor_set {
-        let bytes = std::fs::read(path).unwrap_or_exit();
-        let mut client = client.with_file_descriptor(bytes).unwrap_or_exit();
-        client.dynamic(request).await.unwrap_or_exit()
+        args.extend(["-protoset".to_string(), path.display().to_string()]);
+    }
+
+    if let Some(timeout) = CONNECT_OPTIONS.get().and_then(|options| options.timeout) {
+        args.extend([
+            "-connect-timeout".to_string(),
+            timeout.as_secs_f64().to_string(),
+        ]);
+    }
+
+    if let Some(timeout) = request.timeout {
+        args.extend(["-max-time".to_string(), timeout.as_secs_f64().to_string()]);
+    }
+
+    for (key, value) in &request.headers {
+        args.extend(["-H".to_string(), format!("{key}: {value}")]);
+    }
+
+    let data = match &request.body {
+        serde_json::Value::Array(messages) => messages
+            .iter()
+            .map(|message| message.to_string())
+            .collect::<Vec<_>>()
+            .join(" "),
+        body => body.to_string(),
+    };
+
+    args.extend([
+        "-d".to_string(),
+        data,
+        address.trim_end_matches('/').to_string(),
+        format!("{}/{}", request.service, request.method),
+    ]);
+
+    args.iter()
+        .map(|arg| shell_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Single quotes keep everything literal, except single quotes themselves which are spliced in
+fn shell_quote(arg: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=@,+%".contains(c);
+
+    if !arg.is_empty() && arg.chars().all(is_safe) {
+        arg.to_string()
     } else {
-        client.dynamic(request).await.unwrap_or_exit()
+        format!("'{}'", arg.replace('\'', r"'\''"))
     }
 }
 
-async fn list(source: Source) -> Vec<String> {
-    match source {
+// Formats a point in time as `YYYY-MM-DD HH:MM:SS UTC`
+fn utc_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    let (hour, minute, second) = (rem / 3600, (rem % 3600) / 60, rem % 60);
+
+    // Civil date from days since the Unix epoch (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02} {hour:02}:{minute:02}:{second:02} UTC")
+}
+
+async fn health(
+    uri: String,
+    service: Option<String>,
+    headers: Vec<(String, String)>,
+) -> Outcome<HealthStatus> {
+    let client = connect(&uri, &headers).await?;
+    let mut client = client
+        .with_file_descriptor(health::file_descriptor_set())
+        .or_fail()?;
+
+    match client
+        .dynamic(health::check_request(service, headers))
+        .await
+        .or_fail()?
+    {
+        DynamicResponse::Unary(_, result) => Ok(HealthStatus::from(result.or_fail()?)),
+        DynamicResponse::Streaming(..) => unreachable!("Health/Check is a unary method"),
+    }
+}
+
+// Connects to the server, then asks for its services to find out whether it supports reflection.
+// Connection failures are failures of the command, and so are reflection failures other than
+// `Unimplemented`.
+//
+// Without the reflection check, a health check is sent instead: connections can be lazy (e.g.
+// with gRPC-Web), so only an answer of the server proves that it is reachable.
+async fn ping(
+    uri: String,
+    check_reflection: bool,
+    headers: &[(String, String)],
+) -> Outcome<Reachability> {
+    let mut client = connect(&uri, headers).await?;
+
+    if !check_reflection {
+        let mut client = client
+            .with_file_descriptor(health::file_descriptor_set())
+            .or_fail()?;
+        let request = health::check_request(None, headers.to_vec());
+
+        // Any status but `Unavailable` comes from the server, even `Unimplemented`. Statuses of
+        // failed connections carry the transport error as their source.
+        return match client.dynamic(request).await.or_fail()? {
+            DynamicResponse::Unary(_, Err(status))
+                if status.code() == Code::Unavailable || status.source().is_some() =>
+            {
+                Err(GenericError("Failed to reach the server", status)).or_fail()
+            }
+            DynamicResponse::Unary(..) => Ok(Reachability(None)),
+            DynamicResponse::Streaming(..) => unreachable!("Health/Check is a unary method"),
+        };
+    }
+
+    match client.list_services().await {
+        Ok(_) => Ok(Reachability(Some(true))),
+        Err(ReflectionResolveError::ServerStreamInitFailed(status))
+            if status.code() == Code::Unimplemented =>
+        {
+            Ok(Reachability(Some(false)))
+        }
+        Err(err) => Err(GenericError("Failed to reach the server", err)).or_fail(),
+    }
+}
+
+// Resolves the schema of `symbol` (or of every service when `None`) and writes it to `output`
+async fn export(
+    uri: String,
+    symbol: Option<String>,
+    output: &Path,
+    headers: &[(String, String)],
+) -> Outcome<usize> {
+    let mut client = connect(&uri, headers).await?;
+
+    let symbols = match symbol {
+        Some(symbol) => vec![symbol],
+        None => client
+            .list_services()
+            .await
+            .map_err(|e| GenericError("Failed to list services", e))
+            .or_fail()?,
+    };
+
+    let fd_set = client.file_descriptor_set(&symbols).await.or_fail()?;
+
+    std::fs::write(output, fd_set.encode_to_vec()).or_fail()?;
+
+    Ok(fd_set.file.len())
+}
+
+// Resolves a schema to diff, from a file or from every service of a running server.
+//
+// The tooling services are left out, a descriptor set in a repository rarely contains them.
+async fn schema(source: Source, headers: &[(String, String)]) -> Outcome<DescriptorPool> {
+    let bytes = match source {
+        Source::File(path) => std::fs::read(path).or_fail()?,
         Source::Uri(uri) => {
-            let mut client = GrancClient::connect(&uri).await.unwrap_or_exit();
+            let mut client = connect(&uri, headers).await?;
+            let services = client
+                .list_services()
+                .await
+                .map_err(|e| GenericError("Failed to list services", e))
+                .or_fail()?;
+            let symbols = displayed_services(services, false);
+            let fd_set = client.file_descriptor_set(&symbols).await.or_fail()?;
+            fd_set.encode_to_vec()
+        }
+    };
+
+    Ok(GrancClient::offline(bytes)
+        .or_fail()?
+        .descriptor_pool()
+        .clone())
+}
+
+/// Services of the gRPC tooling itself, hidden by `list` unless `--include-reflection` is given.
+const INFRASTRUCTURE_PACKAGES: [&str; 3] = ["grpc.reflection.", "grpc.health.", "grpc.channelz."];
+
+fn is_infrastructure_service(service: &str) -> bool {
+    INFRASTRUCTURE_PACKAGES
+        .iter()
+        .any(|package| service.starts_with(package))
+}
+
+// Keeps the services to display, the core clients list every service of the server
+fn displayed_services(mut services: Vec<String>, include_reflection: bool) -> Vec<String> {
+    if !include_reflection {
+        services.retain(|service| !is_infrastructure_service(service));
+    }
+    services
+}
+
+async fn list(
+    source: Source,
+    include_reflection: bool,
+    headers: &[(String, String)],
+) -> Outcome<Vec<String>> {
+    let services = match source {
+        Source::Uri(uri) => {
+            let mut client = connect(&uri, headers).await?;
             client
                 .list_services()
                 .await
-                .map_err(|e| GenericError("Failed to list services:", e))
-                .unwrap_or_exit()
+                .map_err(|e| GenericError("Failed to list services", e))
+                .or_fail()?
         }
 
         Source::File(path) => {
-            let fd_bytes = std::fs::read(path).unwrap_or_exit();
-            let client = GrancClient::offline(fd_bytes).unwrap_or_exit();
+            let fd_bytes = std::fs::read(path).or_fail()?;
+            let client = GrancClient::offline(fd_bytes).or_fail()?;
             client.list_services()
         }
-    }
+    };
+
+    Ok(displayed_services(services, include_reflection))
 }
 
-async fn describe(symbol: String, source: Source) -> Descriptor {
-    match source {
+// Lists the services together with their descriptors, to be able to show their methods
+async fn list_with_descriptors(
+    source: Source,
+    include_reflection: bool,
+    headers: &[(String, String)],
+) -> Outcome<Vec<ServiceDescriptor>> {
+    let descriptors = match source {
         Source::Uri(uri) => {
-            let mut client = GrancClient::connect(&uri).await.unwrap_or_exit();
-            client
-                .get_descriptor_by_symbol(&symbol)
+            let mut client = connect(&uri, headers).await?;
+            let services = client
+                .list_services()
                 .await
-                .unwrap_or_exit()
+                .map_err(|e| GenericError("Failed to list services", e))
+                .or_fail()?;
+            let services = displayed_services(services, include_reflection);
+
+            // Resolve the services concurrently, with a bounded number of requests in flight
+            let mut tasks = JoinSet::new();
+            let mut descriptors = Vec::with_capacity(services.len());
+
+            for service in services {
+                if tasks.len() >= MAX_CONCURRENT_REQUESTS
+                    && let Some(result) = tasks.join_next().await
+                {
+                    descriptors.push(joined(result)?);
+                }
+
+                let mut client = client.clone();
+                tasks.spawn(async move { client.get_descriptor_by_symbol(&service).await });
+            }
+
+            while let Some(result) = tasks.join_next().await {
+                descriptors.push(joined(result)?);
+            }
+
+            descriptors
+        }
+
+        Source::File(path) => {
+            let fd_bytes = std::fs::read(path).or_fail()?;
+            let client = GrancClient::offline(fd_bytes).or_fail()?;
+            displayed_services(client.list_services(), include_reflection)
+                .into_iter()
+                .filter_map(|service| client.get_descriptor_by_symbol(&service))
+                .collect()
+        }
+    };
+
+    let mut services: Vec<_> = descriptors
+        .iter()
+        .filter_map(Descriptor::service_descriptor)
+        .cloned()
+        .collect();
+    services.sort_by(|a, b| a.full_name().cmp(b.full_name()));
+    Ok(services)
+}
+
+// Unwraps the descriptor resolved by a `list_with_descriptors` task
+fn joined(
+    result: Result<Result<Descriptor, online::GetDescriptorError>, JoinError>,
+) -> Outcome<Descriptor> {
+    result
+        .map_err(|e| GenericError("Failed to resolve a service", e))
+        .or_fail()?
+        .or_fail()
+}
+
+// Loads the schema of a file descriptor set
+async fn file(
+    filename: String,
+    source: Source,
+    headers: &[(String, String)],
+) -> Outcome<FileDescriptor> {
+    match source {
+        Source::Uri(uri) => {
+            let mut client = connect(&uri, headers).await?;
+            client.get_file_by_name(&filename).await.or_fail()
         }
 
         Source::File(path) => {
-            let fd_bytes = std::fs::read(path).unwrap_or_exit();
-            let client = GrancClient::offline(fd_bytes).unwrap_or_exit();
+            let fd_bytes = std::fs::read(path).or_fail()?;
+            let client = GrancClient::offline(fd_bytes).or_fail()?;
             client
-                .get_descriptor_by_symbol(&symbol)
-                .ok_or(GenericError("Symbol not found", symbol))
-                .unwrap_or_exit()
+                .get_file_by_name(&filename)
+                .ok_or(GenericError("File not found", filename))
+                .or_fail()
+        }
+    }
+}
+
+async fn describe(
+    symbol: String,
+    source: Source,
+    headers: &[(String, String)],
+) -> Outcome<Definition> {
+    let is_method = symbol.contains('/');
+
+    let definition = match source {
+        Source::Uri(uri) => {
+            let mut client = connect(&uri, headers).await?;
+            if is_method {
+                Definition::Method(client.resolve_method(&symbol).await.or_fail()?)
+            } else {
+                Definition::Symbol(client.get_descriptor_by_symbol(&symbol).await.or_fail()?)
+            }
+        }
+
+        Source::File(path) => {
+            let fd_bytes = std::fs::read(path).or_fail()?;
+            let client = GrancClient::offline(fd_bytes).or_fail()?;
+            if is_method {
+                Definition::Method(client.resolve_method(&symbol).or_fail()?)
+            } else {
+                Definition::Symbol(
+                    client
+                        .get_descriptor_by_symbol(&symbol)
+                        .ok_or(GenericError("Symbol not found", symbol))
+                        .or_fail()?,
+                )
+            }
+        }
+    };
+
+    Ok(definition)
+}
+
+// Decides once, at startup, whether the whole program should emit ANSI colors
+fn configure_colors(choice: ColorChoice) {
+    let enabled = match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+            std::io::stdout().is_terminal() && !no_color
+        }
+    };
+
+    colored::control::set_override(enabled);
+}
+
+// Reads a file of one JSON document per line (a batch script or a body file)
+fn read_script(path: &Path, error: &'static str) -> Outcome<String> {
+    std::fs::read_to_string(path)
+        .map_err(|e| GenericError(error, format!("{}: {e}", path.display())))
+        .or_fail()
+}
+
+fn set_output_format(format: OutputFormat) {
+    let _ = OUTPUT_FORMAT.set(format);
+}
+
+fn output_format() -> OutputFormat {
+    OUTPUT_FORMAT.get().copied().unwrap_or_default()
+}
+
+// Writes a successful result in the selected output format
+fn write_output<T>(out: &mut impl Write, value: T) -> Outcome
+where
+    T: Into<FormattedString> + Into<JsonString>,
+{
+    match output_format() {
+        OutputFormat::Text => write_line(out, Into::<FormattedString>::into(value)),
+        OutputFormat::Json => write_line(out, Into::<JsonString>::into(value)),
+    }
+}
+
+fn write_line(out: &mut impl Write, line: impl std::fmt::Display) -> Outcome {
+    writeln!(out, "{line}")
+        .map_err(|e| GenericError("Failed to write the output", e))
+        .or_fail()
+}
+
+/// The result of a command, or of a step of it.
+type Outcome<T = ()> = Result<T, Failure>;
+
+/// Why a command failed: the exit code of the process, and the error to report.
+///
+/// The error is rendered in the output format of the command. It is `None` when the failure is
+/// part of the output already (e.g. the status of a failed call).
+#[derive(Debug)]
+struct Failure {
+    code: i32,
+    error: Option<String>,
+}
+
+impl Failure {
+    /// A failure that is already reported by the output of the command.
+    fn exit(code: i32) -> Self {
+        Failure { code, error: None }
+    }
+
+    /// Writes the error to `err`, returning the exit code of the process.
+    fn report(self, err: &mut impl Write) -> i32 {
+        if let Some(error) = self.error {
+            let _ = writeln!(err, "{error}");
+        }
+        self.code
+    }
+}
+
+impl<E> From<E> for Failure
+where
+    E: Into<FormattedString> + Into<JsonError> + StatusCode,
+{
+    fn from(e: E) -> Self {
+        Failure {
+            code: exit_code(e.status_code()),
+            error: Some(render_error(e)),
         }
     }
 }
 
 // Utility trait to standardize the way we handle errors in the program
-trait UnwrapOrExit<T, E> {
-    fn unwrap_or_exit(self) -> T;
+trait OrFail<T> {
+    /// Turns the error into a [`Failure`] of the command, rendered in the output format.
+    fn or_fail(self) -> Outcome<T>;
 }
 
-impl<T, E> UnwrapOrExit<T, E> for Result<T, E>
+impl<T, E> OrFail<T> for Result<T, E>
 where
-    E: Into<FormattedString>,
+    E: Into<FormattedString> + Into<JsonError> + StatusCode,
 {
-    fn unwrap_or_exit(self) -> T {
-        match self {
-            Ok(v) => v,
-            Err(e) => {
-                eprintln!("{}", Into::<FormattedString>::into(e));
-                process::exit(1);
-            }
+    fn or_fail(self) -> Outcome<T> {
+        self.map_err(Failure::from)
+    }
+}
+
+/// The exit code of a failure whose gRPC status is `code`: the number of the code (e.g. 5 for
+/// `NOT_FOUND`, 16 for `UNAUTHENTICATED`), capped to 125 as higher exit codes mean something
+/// else to shells. It is never 0, even for a failure mapped to `OK`.
+fn exit_code(code: Code) -> i32 {
+    (code as i32).clamp(1, 125)
+}
+
+// The status of a failed response: the last status of a stream that ended with an error
+fn response_error(response: &DynamicResponse) -> Option<Code> {
+    response_status(response).map(Status::code)
+}
+
+/// The status of a failed call, or of the last failure of a stream.
+fn response_status(response: &DynamicResponse) -> Option<&Status> {
+    match response {
+        DynamicResponse::Unary(_, Err(status)) | DynamicResponse::Streaming(_, Err(status)) => {
+            Some(status)
+        }
+        DynamicResponse::Streaming(_, Ok(values)) => {
+            values.iter().rev().find_map(|value| value.as_ref().err())
         }
+        DynamicResponse::Unary(_, Ok(_)) => None,
+    }
+}
+
+// Reports an error on stderr, in the output format of the running command
+// Renders an error in the selected output format
+fn render_error<E>(e: E) -> String
+where
+    E: Into<FormattedString> + Into<JsonError>,
+{
+    match output_format() {
+        OutputFormat::Text => Into::<FormattedString>::into(e).to_string(),
+        OutputFormat::Json => JsonString::from(Into::<JsonError>::into(e)).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use granc_test_support::echo_service::FILE_DESCRIPTOR_SET;
+
+    // Runs `granc <args>` offline, against the echo service descriptor set, capturing its output
+    async fn run_offline(args: &[&str]) -> String {
+        let (code, out, err) = run_offline_with_errors(args).await;
+        assert_eq!(code, 0, "{err}");
+        out
+    }
+
+    // Same as `run_offline`, also capturing the exit code and the errors
+    async fn run_offline_with_errors(args: &[&str]) -> (i32, String, String) {
+        colored::control::set_override(false);
+
+        // Tests run concurrently, so each run gets its own file
+        static RUNS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let run_id = RUNS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("granc-echo-{}-{run_id}.bin", process::id()));
+        std::fs::write(&path, FILE_DESCRIPTOR_SET).unwrap();
+
+        let mut argv = vec!["granc"];
+        argv.extend(args);
+        argv.extend(["-f", path.to_str().unwrap()]);
+        let cli = Cli::try_parse_from(argv).expect("Parsing failed");
+
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        let code = run(cli.command, &config::Config::default(), &mut out, &mut err).await;
+        std::fs::remove_file(&path).unwrap();
+
+        (
+            code,
+            String::from_utf8(out).unwrap(),
+            String::from_utf8(err).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_run_writes_to_the_given_output() {
+        assert_eq!(
+            run_offline(&["list"]).await,
+            "\nAvailable Services:\n  - echo.EchoService\n\n"
+        );
+
+        let describe = run_offline(&["describe", "echo.EchoRequest"]).await;
+        assert!(describe.contains("message EchoRequest {\n"), "{describe}");
+    }
+
+    #[tokio::test]
+    async fn test_run_writes_errors_to_the_given_error_output() {
+        let (code, out, err) = run_offline_with_errors(&["describe", "echo.Missing"]).await;
+
+        assert_eq!(code, exit_code(Code::Unknown));
+        assert!(out.is_empty(), "{out}");
+        assert_eq!(err, "\nSymbol not found:\n\n'echo.Missing'\n\n");
+
+        // The exit code is the one of the gRPC status of the error
+        let (code, out, err) =
+            run_offline_with_errors(&["describe", "echo.EchoService/Missing"]).await;
+
+        assert_eq!(code, exit_code(Code::NotFound));
+        assert!(out.is_empty(), "{out}");
+        assert!(err.contains("Method 'Missing' not found"), "{err}");
+    }
+
+    #[test]
+    fn test_infrastructure_services_are_hidden() {
+        let services = vec![
+            "grpc.reflection.v1.ServerReflection".to_string(),
+            "grpc.reflection.v1alpha.ServerReflection".to_string(),
+            "grpc.health.v1.Health".to_string(),
+            "echo.EchoService".to_string(),
+            "grpc.healthcheck.Custom".to_string(),
+        ];
+
+        assert_eq!(
+            displayed_services(services.clone(), false),
+            ["echo.EchoService", "grpc.healthcheck.Custom"]
+        );
+        assert_eq!(displayed_services(services.clone(), true), services);
+    }
+
+    #[tokio::test]
+    async fn test_describe_method_path() {
+        let signature = "rpc UnaryEcho(echo.EchoRequest) returns (echo.EchoResponse);";
+
+        assert_eq!(
+            run_offline(&["describe", "echo.EchoService/UnaryEcho"]).await,
+            format!("\n{signature}\n\n")
+        );
+        assert_eq!(
+            run_offline(&["describe", "echo.EchoService/UnaryEcho", "--expand"]).await,
+            format!(
+                "\n{signature}\n\nmessage EchoRequest {{\n  string  message = 1;\n}}\n\n\
+                 message EchoResponse {{\n  string  message = 1;\n}}\n\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_read_header_files_trims_values() {
+        let path = std::env::temp_dir().join(format!("granc-token-{}", process::id()));
+        std::fs::write(&path, "  secret-token\n").unwrap();
+
+        let headers = read_header_files(vec![("authorization".to_string(), path.clone())]);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            headers.ok(),
+            Some(vec![(
+                "authorization".to_string(),
+                "secret-token".to_string()
+            )])
+        );
+
+        let missing = read_header_files(vec![("authorization".to_string(), path.clone())]);
+        let GenericError(msg, err) = missing.unwrap_err();
+        assert_eq!(msg, "Failed to read header file");
+        assert!(err.starts_with(&path.display().to_string()), "{err}");
+    }
+
+    #[test]
+    fn test_read_headers_file() {
+        let path = std::env::temp_dir().join(format!("granc-headers-{}.json", process::id()));
+
+        std::fs::write(
+            &path,
+            r#"{"authorization": "Bearer abc", "x-request-id": "42"}"#,
+        )
+        .unwrap();
+        let headers = read_headers_file(&path).ok();
+        assert_eq!(
+            headers,
+            Some(vec![
+                ("authorization".to_string(), "Bearer abc".to_string()),
+                ("x-request-id".to_string(), "42".to_string())
+            ])
+        );
+
+        // `-H` wins over the file
+        let merged = config::merge_headers(
+            &headers.unwrap(),
+            vec![("Authorization".to_string(), "Bearer xyz".to_string())],
+        );
+        assert_eq!(
+            merged,
+            [
+                ("x-request-id".to_string(), "42".to_string()),
+                ("Authorization".to_string(), "Bearer xyz".to_string())
+            ]
+        );
+
+        std::fs::write(&path, r#"{"x-retries": 3}"#).unwrap();
+        let GenericError(msg, err) = read_headers_file(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(msg, "Invalid headers file");
+        assert!(
+            err.ends_with("the value of 'x-retries' is not a string: 3"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_connect_uri() {
+        assert_eq!(connect_uri("localhost:50051", false), "localhost:50051");
+        assert_eq!(
+            connect_uri("localhost:50051", true),
+            "http://localhost:50051"
+        );
+        assert_eq!(
+            connect_uri("https://api.example.com", true),
+            "http://api.example.com"
+        );
+    }
+
+    #[test]
+    fn test_grpcurl_command_quotes_body_and_headers() {
+        let request = DynamicRequest::builder("echo.EchoService", "UnaryEcho")
+            .body(serde_json::json!({"message": "it's $HOME"}))
+            .header("authorization", "Bearer abc")
+            .timeout(Duration::from_millis(1500))
+            .build();
+
+        assert_eq!(
+            grpcurl_command("http://localhost:50051", None, &request),
+            r#"grpcurl -plaintext -max-time 1.5 -H 'authorization: Bearer abc' -d '{"message":"it'\''s $HOME"}' localhost:50051 echo.EchoService/UnaryEcho"#
+        );
+    }
+
+    #[test]
+    fn test_grpcurl_command_streams_and_protoset() {
+        let request = DynamicRequest::builder("echo.EchoService", "ClientStreamingEcho")
+            .body(serde_json::json!([{"message": "a"}, {"message": "b"}]))
+            .build();
+
+        assert_eq!(
+            grpcurl_command(
+                "https://api.example.com:443",
+                Some(Path::new("my protos.bin")),
+                &request
+            ),
+            r#"grpcurl -protoset 'my protos.bin' -d '{"message":"a"} {"message":"b"}' api.example.com:443 echo.EchoService/ClientStreamingEcho"#
+        );
+    }
+
+    #[test]
+    fn test_exit_codes_match_status_codes() {
+        assert_eq!(exit_code(Code::NotFound), 5);
+        assert_eq!(exit_code(Code::Unavailable), 14);
+        assert_eq!(exit_code(Code::Unauthenticated), 16);
+        // A failure is never reported as a success
+        assert_eq!(exit_code(Code::Ok), 1);
+
+        let missing = GenericError("Symbol not found", "echo.Ghost");
+        assert_eq!(exit_code(missing.status_code()), 2);
+
+        let io = std::io::Error::from(std::io::ErrorKind::NotFound);
+        assert_eq!(exit_code(io.status_code()), 5);
+    }
+
+    #[test]
+    fn test_response_error() {
+        use granc_core::client::MethodKind;
+
+        let ok = DynamicResponse::Unary(MethodKind::Unary, Ok(serde_json::json!({})));
+        assert_eq!(response_error(&ok), None);
+
+        let denied =
+            DynamicResponse::Unary(MethodKind::Unary, Err(Status::permission_denied("no")));
+        assert_eq!(response_error(&denied), Some(Code::PermissionDenied));
+
+        let broken_stream = DynamicResponse::Streaming(
+            MethodKind::ServerStreaming,
+            Ok(vec![
+                Ok(serde_json::json!({})),
+                Err(Status::deadline_exceeded("late")),
+            ]),
+        );
+        assert_eq!(response_error(&broken_stream), Some(Code::DeadlineExceeded));
+
+        let empty_stream = DynamicResponse::Streaming(MethodKind::ServerStreaming, Ok(vec![]));
+        assert_eq!(response_error(&empty_stream), None);
     }
 }
@@ -0,0 +1,67 @@
+//! # Transport
+//!
+//! The connection used to reach a server: standard gRPC over HTTP/2, or gRPC-Web over the same
+//! connection when `--grpc-web` is given (e.g. for browser-facing servers behind Envoy).
+//!
+//! Both are put behind a single [`Transport`] type, so every command works with either of them.
+//! Requests to a server that doesn't speak HTTP/2 (e.g. a REST endpoint) fail with a status
+//! explaining it, instead of an HTTP/2 protocol error.
+use granc_core::{
+    client::online::is_not_http2,
+    grpc::web::GrpcWebChannel,
+    tonic::{
+        Status,
+        body::Body,
+        codegen::{BoxFuture, Context, Poll, Service, StdError, http},
+        transport::{self, Channel},
+    },
+};
+
+const NOT_HTTP2: &str = "The target does not appear to be a gRPC (HTTP/2) server. \
+    Check that the URI points to the gRPC port rather than a REST or web one, \
+    and use --grpc-web for gRPC-Web servers. \
+    Only plaintext HTTP/2 is supported: a server that requires TLS can't be reached, \
+    and --plaintext turns an https:// URI into a plaintext one.";
+
+#[derive(Debug, Clone)]
+pub enum Transport {
+    Grpc(Channel),
+    GrpcWeb(Box<GrpcWebChannel>),
+}
+
+impl Service<http::Request<Body>> for Transport {
+    type Response = http::Response<Body>;
+    type Error = StdError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        match self {
+            Transport::Grpc(channel) => channel.poll_ready(cx).map_err(Into::into),
+            Transport::GrpcWeb(channel) => channel.poll_ready(cx).map_err(Into::into),
+        }
+    }
+
+    fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+        match self {
+            Transport::Grpc(channel) => {
+                let response = channel.call(request);
+                Box::pin(async move { response.await.map_err(not_http2_status) })
+            }
+            Transport::GrpcWeb(channel) => {
+                let response = channel.call(request);
+                Box::pin(
+                    async move { Ok(response.await.map_err(not_http2_status)?.map(Body::new)) },
+                )
+            }
+        }
+    }
+}
+
+// Explains a failure caused by a server that doesn't speak HTTP/2
+fn not_http2_status(err: transport::Error) -> StdError {
+    if is_not_http2(&err) {
+        Status::unavailable(NOT_HTTP2).into()
+    } else {
+        err.into()
+    }
+}